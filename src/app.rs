@@ -3,6 +3,7 @@
 // 假设此模块定义了所有与后端通信所需的 Command 和 Update 枚举
 // For standalone compilation, you would need to provide dummy definitions.
 use crate::communication::{self, *};
+use crate::settings::{AppSettings, PlotStyle, ThemePreference};
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use egui::{
     CentralPanel, Color32, ComboBox, DragValue, Frame, RichText, Stroke, TopBottomPanel, Ui,
@@ -11,7 +12,7 @@ use egui::{Pos2, Rect, Vec2};
 use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
 // 新增：导入 Rect, Pos2, Vec2
 use egui_extras::{Column, TableBuilder};
-use egui_plot::{Line, Plot, PlotPoints, Points};
+use egui_plot::{Line, MarkerShape, Plot, PlotPoints, Points};
 use std::collections::VecDeque;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -21,7 +22,7 @@ use tracing::Level;
 // 新增：用于管理左侧主工作区当前显示的标签页
 #[derive(PartialEq, Clone, Copy)]
 enum Tab {
-    // Welcome, // 新增欢迎页
+    Welcome, // 新增欢迎页
     DeviceControl,
     ModelTraining,
     StaticMeasurement,
@@ -29,12 +30,166 @@ enum Tab {
     DataProcessing,
 }
 
+// 通知气泡的显示时长
+const TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(5);
+const MAX_TOASTS: usize = 5;
+// 零点确立超过这么久后提示重新寻找零点
+const ZERO_STALE_AFTER_MINUTES: i64 = 60;
+// 圆半径稳定度 (%) 与检出率低于这些值时以橙色提示
+const RADIUS_STABILITY_WARN: f32 = 90.0;
+const RADIUS_DETECTION_WARN: f32 = 0.8;
+// 采样间隔超过中位数的这个倍数（或不足其倒数）时标为异常
+const INTERVAL_ANOMALY_RATIO: f64 = 2.0;
+// 当前角度的显示与复制精度（小数位数）
+const ANGLE_DECIMALS: usize = 2;
+// 曝光、半径等拖动控件停止变化多久后才发送命令
+const SLIDER_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(100);
+
+// 内置的欢迎语，未自定义时显示
+const DEFAULT_WELCOME_TEXT: &str = r#"欢迎使用旋光仪控制软件 v1.5.5
+请遵循顶部标签页的引导，按以下顺序完成实验：
+1.  设备控制: 连接并检查旋光仪电机与相机硬件。
+2.  模型训练: (首次使用或更换环境时) 录制视频并训练用于识别旋光状态的 AI 模型。
+3.  静态与动态测量: 填入实验参数，开始自动化的数据采集流程。
+4.  数据处理: 导入实验数据，动力学拟合与分析。
+
+祝实验顺利！"#;
+
+// 自定义欢迎语随设置保存，便于各实验室分别修改
+fn welcome_text(settings: &AppSettings) -> String {
+    settings
+        .welcome_text
+        .clone()
+        .filter(|text| !text.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_WELCOME_TEXT.to_string())
+}
+
+fn normalization_label(normalization: Normalization) -> &'static str {
+    match normalization {
+        Normalization::None => "无",
+        Normalization::Standardize => "均值/方差标准化",
+        Normalization::HistogramEqualize => "直方图均衡化",
+    }
+}
+
+fn marker_label(shape: MarkerShape) -> &'static str {
+    match shape {
+        MarkerShape::Circle => "圆点",
+        MarkerShape::Diamond => "菱形",
+        MarkerShape::Square => "方块",
+        MarkerShape::Cross => "叉号",
+        MarkerShape::Plus => "加号",
+        MarkerShape::Up => "上三角",
+        MarkerShape::Down => "下三角",
+        MarkerShape::Left => "左三角",
+        MarkerShape::Right => "右三角",
+        MarkerShape::Asterisk => "星号",
+    }
+}
+
+// 以所选单位显示和编辑浓度，参数中保存的始终是换算后的标准单位
+fn conc_input(ui: &mut Ui, id: &str, canonical: &mut f32, unit: &mut ConcUnit, units: &[ConcUnit]) {
+    ui.horizontal(|ui| {
+        let mut shown = *canonical / unit.to_canonical();
+        if ui.add(egui::DragValue::new(&mut shown).speed(0.01)).changed() {
+            *canonical = shown * unit.to_canonical();
+        }
+        egui::ComboBox::from_id_source(id)
+            .selected_text(unit.label())
+            .width(70.0)
+            .show_ui(ui, |ui| {
+                for candidate in units {
+                    ui.selectable_value(unit, *candidate, candidate.label());
+                }
+            });
+    });
+}
+
+/// 相邻采样点的时间间隔（第一个点没有间隔）与间隔的中位数；间隔少于 3 个时不计算中位数
+fn sample_intervals(results: &[DynamicResult]) -> (Vec<Option<f64>>, Option<f64>) {
+    let intervals: Vec<Option<f64>> = std::iter::once(None)
+        .chain(results.windows(2).map(|w| Some(w[1].time - w[0].time)))
+        .take(results.len())
+        .collect();
+    let mut sorted: Vec<f64> = intervals.iter().flatten().copied().collect();
+    if sorted.len() < 3 {
+        return (intervals, None);
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    (intervals, Some(sorted[sorted.len() / 2]))
+}
+
+struct Toast {
+    message: String,
+    is_error: bool,
+    created: std::time::Instant,
+}
+
+// 每度步数标定对话框的状态
+struct StepCalibration {
+    steps: i32,          // 标定时转动的步数
+    rotated: bool,       // 是否已发出旋转命令
+    measured_angle: f32, // 用户从刻度盘读出的实际转角
+}
+
+impl Default for StepCalibration {
+    fn default() -> Self {
+        Self {
+            steps: (DEFAULT_STEPS_PER_DEGREE * 10.0).round() as i32, // 按默认传动比约转 10°
+            rotated: false,
+            measured_angle: 0.0,
+        }
+    }
+}
+
+// 动态实验参数的合理范围 (下限, 上限)，超出时只在界面上提示，不阻止实验
+struct ParamBounds {
+    temperature: (f32, f32),
+    sucrose_conc: (f32, f32),
+    hcl_conc: (f32, f32),
+}
+
+impl Default for ParamBounds {
+    fn default() -> Self {
+        Self {
+            temperature: (0.0, 60.0),
+            sucrose_conc: (0.0, 0.5),
+            hcl_conc: (0.0, 6.0),
+        }
+    }
+}
+
+impl ParamBounds {
+    fn warnings(&self, params: &DynamicExpParams) -> Vec<String> {
+        [
+            ("实验温度", params.temperature, self.temperature, "°C"),
+            ("蔗糖浓度", params.sucrose_conc, self.sucrose_conc, " g/mL"),
+            ("盐酸浓度", params.hcl_conc, self.hcl_conc, " mol/L"),
+        ]
+        .into_iter()
+        .filter(|(_, value, (min, max), _)| value < min || value > max)
+        .map(|(name, value, (min, max), unit)| {
+            format!("{} {}{} 超出常见范围 {}~{}{}，请确认是否输入有误", name, value, unit, min, max, unit)
+        })
+        .collect()
+    }
+}
+
+// 导入多工作表 xlsx 时的工作表选择
+struct SheetPicker {
+    path: PathBuf,
+    sheets: Vec<String>,
+    selected: usize,
+}
+
 pub struct PolarimeterApp {
     // --- 通信 ---
     cmd_tx: Sender<Command>,
     update_rx: Receiver<Update>,
     backend_handle: Option<thread::JoinHandle<()>>,
     log_buffer: VecDeque<communication::LogMessage>,
+    // 实验记录：只保存里程碑事件，不做条数限制，便于完整导出
+    experiment_log: Vec<ExperimentEvent>,
     cache: CommonMarkCache,
     file_dialog_rx: Receiver<Option<FileDialogResult>>, // 通用接收器
     file_dialog_tx: Sender<Option<FileDialogResult>>,   // 通用发送器
@@ -44,9 +199,14 @@ pub struct PolarimeterApp {
 
     // --- UI 核心状态 ---
     active_tab: Tab, // 当前激活的标签页
+    welcome_text: String,
+    plot_style: PlotStyle,
+    min_r2: f64, // 拟合 R² 低于此值时回归方程标红
+    welcome_editing: Option<String>, // 编辑中的草稿，None 表示未在编辑
 
     // --- 通用 UI 状态 ---
     status_message: String,
+    toasts: VecDeque<Toast>,
     cm_data: Option<ConfusionMatrixData>,
     roc_data: Option<RocCurveData>,
     is_doc_window_open: bool, // 训练结果评估窗口仍然可以是一个独立的弹出窗口
@@ -55,9 +215,17 @@ pub struct PolarimeterApp {
     serial_ports: Vec<String>,
     selected_serial_port: String,
     is_serial_connected: bool,
+    serial_ack_token: String,
+    serial_stop_byte_enabled: bool,
+    serial_stop_byte: u8,
+    serial_test_result: Option<SerialTestResult>,
     rotation_direction_is_ama: bool,
     rotation_direction_reverse: bool,
+    step_calibration: Option<StepCalibration>,
     manual_rotation_angle: f32,
+    // 方向键微调的步数：单按为小步，按住 Shift 为大步
+    jog_small_steps: i32,
+    jog_large_steps: i32,
     manual_rotation_to_angle: f32,
     current_angle: Option<f32>,
     anglesteps: f32,
@@ -66,13 +234,32 @@ pub struct PolarimeterApp {
     camera_list: Vec<String>,
     selected_camera_idx: usize,
     is_camera_connected: bool,
+    // 最近一次相机失败的原因，连接成功后清除
+    camera_error: Option<String>,
+    // 多相机：已连接的索引、驱动识别的主相机、当前显示的画面
+    connected_cameras: Vec<usize>,
+    primary_camera: Option<usize>,
+    viewed_camera: Option<usize>,
     camera_texture: Option<egui::TextureHandle>,
     camera_image: Option<Arc<egui::ColorImage>>,
     exposure: f64,
+    capture_fps: f32, // 相机采集线程的目标帧率
+    capture_paused: bool,
+    odometer: u64, // 电机累计转动步数
+    // 主相机读回的实际曝光值
+    exposure_applied: Option<f64>,
+    exposure_sweep: Option<ExposureSweep>,
+    radius_stats: Option<RadiusStats>,
+    opencv_status: Option<Result<String, String>>,
     min_radius: u32,
     max_radius: u32,
+    // 防抖：最近一次改动的时间，None 表示没有待发送的值
+    pending_exposure: Option<std::time::Instant>,
+    pending_radius: Option<std::time::Instant>,
     rotation: bool,
     camera_lock_circle: bool,
+    show_prediction_preview: bool,
+    prediction_preview: Option<(usize, f64)>,
     camera_view_rect: Option<Rect>, // 用 Rect 存储当前视图的范围 (uv-coordinates)
     is_dragging_camera_view: bool,  // 标记是否正在拖动视图
 
@@ -81,17 +268,22 @@ pub struct PolarimeterApp {
     recording_elapsed_time: f32,
     recording_mode: String, // "MAM" or "AMA"
     recording_angle: f32,
+    recording_fps: f32,
 
     // --- 窗口 2: 模型训练 ---
     recorded_dataset_path: String,
-    ama_video_path: String,
+    mam_video_path: Option<PathBuf>,
+    ama_video_path: Option<PathBuf>,
     dataset_path: String,
+    shared_dataset_source: String,
     mam_video_status: String,
     ama_video_status: String,
     persistent_dataset_status: String,
     training_status: String,
     is_model_ready: bool,
+    feature_config_warning: Option<String>,
     train_show_roc: bool,
+    normalization: Normalization, // 下次训练使用的图像归一化
     train_show_cm: bool,
 
     // --- 窗口 3: 静态测量 ---
@@ -99,28 +291,62 @@ pub struct PolarimeterApp {
     static_pre_rotation_angle: f32,
     static_measurement_status: String,
     static_results: Vec<StaticResult>,
+    static_results_dirty: bool, // 有尚未保存的静态测量结果
+    dynamic_results_dirty: bool, // 有尚未成功写入文件的动态测量结果
+    show_exit_confirm: bool,
+    allow_close: bool,
+    exit_after_save: bool, // 退出确认中选择“先保存”，保存成功后关闭程序
     static_times: i32,
+    zero_record: Option<ZeroRecord>,
+    serial_reconnected_since_zero: bool, // 零点确立后串口是否重新连接过
+    // 判定越过零点时的迟滞余量，与后端默认值一致
+    decision_margin: usize,
+    zero_search_progress: Option<(f32, f32)>,
+    rezero_confirmed: bool,
 
     // --- 窗口 4: 动态测量 ---
     dynamic_params: DynamicExpParams,
 
     dynamic_measurement_status: String,
+    dynamic_phase: DynamicPhase,
     dynamic_results: Vec<DynamicResult>,
     is_dynamic_exp_running: bool,
     start_time: Option<std::time::Instant>,
+    dynamic_run_id: Option<String>,
 
     // --- 窗口 5: 数据处理 ---
     data_import_path: String,
     alpha_inf: f64,
+    // 后端是否已有 α∞（输入、估算或带入），α∞ = 0 也是合法值
+    alpha_inf_set: bool,
+    // 从动态测量页切换过来时，自动以最后一个测量点的角度作为 α∞
+    auto_alpha_inf: bool,
+    // 已带入 α∞ 的动态测量点（运行编号, 序号），同一个点不重复带入
+    carried_alpha_inf: Option<(Option<String>, usize)>,
     regression_mode: RegressionMode,
     regression_formula: String,
+    r_squared: f64, // 没有拟合结果时为 NaN，不标红
+    regression_summary: String,
+    regression_warning: String,
+    exclude_trend_reversals: bool,
     raw_plot_data: Arc<Vec<(f64, i32, f64, bool)>>,
     plot_scatter_points: Vec<(f64, f64)>,
     plot_line_points: Vec<(f64, f64)>,
+    sheet_picker: Option<SheetPicker>,
+    param_bounds: ParamBounds,
+    // xlsx 结果文件的列布局，由后端保存
+    export_layout: ExportLayout,
+    is_export_layout_open: bool,
+    // 后端定期发送的资源占用
+    diagnostics: Option<Diagnostics>,
+    theme: ThemePreference,
 }
 
 impl eframe::App for PolarimeterApp {
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Err(e) = AppSettings::update(|settings| self.store_settings(settings)) {
+            tracing::error!("前端：保存设置失败: {}", e);
+        }
         tracing::info!("前端：正在退出，通知后端关闭...");
         if let Err(e) = self.cmd_tx.send(Command::General(GeneralCommand::Shutdown)) {
             tracing::error!("前端：发送关闭指令失败: {}", e);
@@ -136,11 +362,32 @@ impl eframe::App for PolarimeterApp {
     }
 
     /// 主更新循环，实现新的 "标签页 + 监视器" 布局
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let dark = self.theme.is_dark(frame.info().system_theme);
+        if ctx.style().visuals.dark_mode != dark {
+            ctx.set_visuals(if dark { egui::Visuals::dark() } else { egui::Visuals::light() });
+        }
         // 1. 优先处理所有后端消息和相机图像更新
         self.handle_backend_updates();
 
         self.handle_file_dialog_results();
+        self.flush_debounced_sliders();
+        self.handle_jog_keys(ctx);
+        self.handle_measure_hotkey(ctx);
+
+        // 拦截关闭请求：有未保存的结果时先弹窗确认
+        let dirty = self.static_results_dirty || self.dynamic_results_dirty;
+        if ctx.input(|i| i.viewport().close_requested()) && dirty && !self.allow_close {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.show_exit_confirm = true;
+        }
+        // “先保存”的结果都已写入文件后再关闭
+        if self.exit_after_save && !dirty {
+            self.exit_after_save = false;
+            self.allow_close = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+        self.show_exit_confirm_window(ctx);
         if let Some(image) = self.camera_image.take() {
             let texture = ctx.load_texture("camera_feed", image, Default::default());
             self.camera_texture = Some(texture);
@@ -148,15 +395,47 @@ impl eframe::App for PolarimeterApp {
 
         // 2. 绘制底部固定的状态栏
         // 2. 绘制贯通顶部的标签栏
+        let previous_tab = self.active_tab;
         TopBottomPanel::top("main_top_bar").show(ctx, |ui| {
             ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
-                // ui.selectable_value(&mut self.active_tab, Tab::Welcome, "0. 欢迎");
-                ui.selectable_value(&mut self.active_tab, Tab::DeviceControl, "1. 设备");
-                ui.selectable_value(&mut self.active_tab, Tab::ModelTraining, "2. 模型");
-                ui.selectable_value(&mut self.active_tab, Tab::StaticMeasurement, "3. 静态测量");
-                ui.selectable_value(&mut self.active_tab, Tab::DynamicMeasurement, "4. 动态测量");
-                ui.selectable_value(&mut self.active_tab, Tab::DataProcessing, "5. 数据处理");
+                // 有后台任务的标签页后面显示转圈，提醒切走后任务仍在执行
+                let tabs = [
+                    (Tab::Welcome, "0. 欢迎", false),
+                    (Tab::DeviceControl, "1. 设备", false),
+                    (Tab::ModelTraining, "2. 模型", self.is_recording),
+                    (Tab::StaticMeasurement, "3. 静态测量", self.is_static_running),
+                    (Tab::DynamicMeasurement, "4. 动态测量", self.is_dynamic_exp_running),
+                    (Tab::DataProcessing, "5. 数据处理", false),
+                ];
+                for (tab, label, busy) in tabs {
+                    ui.selectable_value(&mut self.active_tab, tab, label);
+                    if busy {
+                        ui.add(egui::Spinner::new().size(12.0))
+                            .on_hover_text("该页有任务正在运行");
+                    }
+                }
                 ui.toggle_value(&mut self.is_doc_window_open, "文档");
+                let task_active =
+                    self.is_static_running || self.is_dynamic_exp_running || self.is_recording;
+                if task_active
+                    && ui
+                        .button(RichText::new("⏹ 停止当前任务").color(Color32::LIGHT_RED))
+                        .clicked()
+                {
+                    self.cmd_tx
+                        .send(Command::General(GeneralCommand::StopActiveTask))
+                        .unwrap();
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ComboBox::from_id_source("theme_preference")
+                        .selected_text(self.theme.label())
+                        .show_ui(ui, |ui| {
+                            for theme in ThemePreference::ALL {
+                                ui.selectable_value(&mut self.theme, theme, theme.label());
+                            }
+                        });
+                    ui.label("主题:");
+                });
             });
         });
         // TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
@@ -166,7 +445,14 @@ impl eframe::App for PolarimeterApp {
         //         });
         //     });
         // });
+        if previous_tab == Tab::DynamicMeasurement && self.active_tab == Tab::DataProcessing {
+            self.carry_alpha_inf_from_dynamic();
+        }
         self.show_doc_window(ctx);
+        self.show_step_calibration_window(ctx);
+        self.show_sheet_picker_window(ctx);
+        self.show_export_layout_window(ctx);
+        self.show_toasts(ctx);
         // 3. 根据当前激活的标签页，选择合适的布局
         {
             // 对于其他所有页面，使用固定的 50/50 分栏布局
@@ -226,6 +512,23 @@ impl PolarimeterApp {
             .send(Command::Camera(CameraCommand::RefreshCameras))
             .unwrap();
         let (file_dialog_tx, file_dialog_rx) = unbounded(); // 创建通道
+        // 恢复上次退出时的设备设置，并同步给后端
+        let settings = AppSettings::load();
+        for cmd in [
+            Command::Device(DeviceCommand::SetRotationDirection(settings.rotation_direction_is_ama)),
+            Command::Device(DeviceCommand::SetRotationReverse(
+                settings.rotation_direction_need_reverse,
+            )),
+            Command::Device(DeviceCommand::SetStep(settings.steps_per_degree)),
+            Command::Camera(CameraCommand::Exposure(settings.exposure)),
+            Command::Camera(CameraCommand::SetHoughCircleRadius {
+                min: settings.min_radius,
+                max: settings.max_radius,
+            }),
+            Command::Camera(CameraCommand::SetLock(settings.lock_circle)),
+        ] {
+            cmd_tx.send(cmd).unwrap();
+        }
 
         Self {
             cmd_tx,
@@ -234,75 +537,147 @@ impl PolarimeterApp {
             file_dialog_tx,
             file_dialog_rx,
             selected_record: None,
-            anglesteps:746.0,
+            anglesteps: settings.steps_per_degree,
             log_buffer: VecDeque::with_capacity(100),
+            experiment_log: Vec::new(),
             backend_handle,
             cache: CommonMarkCache::default(),
             active_tab: Tab::DeviceControl, // 默认打开第一个标签页
+            welcome_text: welcome_text(&settings),
+            plot_style: settings.plot_style,
+            min_r2: settings.min_r2,
+            welcome_editing: None,
             status_message: "欢迎使用!".to_string(),
+            toasts: VecDeque::new(),
             is_doc_window_open: false,
             recording_angle: 15.0,
+            recording_fps: 30.0,
             // ... 其他所有字段的默认值和原先保持一致 ...
             cm_data: None,
             roc_data: None,
             serial_ports: vec!["刷新中...".to_string()],
             selected_serial_port: "".to_string(),
             is_serial_connected: false,
-            rotation_direction_is_ama: false,
-            rotation_direction_reverse: false,
+            serial_ack_token: "1".to_string(),
+            serial_stop_byte_enabled: false,
+            serial_stop_byte: 100,
+            serial_test_result: None,
+            rotation_direction_is_ama: settings.rotation_direction_is_ama,
+            rotation_direction_reverse: settings.rotation_direction_need_reverse,
             manual_rotation_angle: 0.0,
+            jog_small_steps: 1,
+            jog_large_steps: 20,
             manual_rotation_to_angle: 0.0,
             current_angle: None,
             camera_list: vec!["刷新中...".to_string()],
             selected_camera_idx: 0,
+            camera_error: None,
+            connected_cameras: Vec::new(),
+            primary_camera: None,
+            viewed_camera: None,
             is_camera_connected: false,
             camera_texture: None,
             camera_image: None,
             camera_view_rect: None, // 初始为空，连接相机后设置
             is_dragging_camera_view: false,
-            exposure: -8.0,
-            min_radius: 30,
-            max_radius: 45,
-            camera_lock_circle: false,
+            exposure: settings.exposure,
+            capture_fps: 30.0,
+            capture_paused: false,
+            odometer: 0,
+            exposure_applied: None,
+            exposure_sweep: None,
+            radius_stats: None,
+            opencv_status: None,
+            min_radius: settings.min_radius,
+            max_radius: settings.max_radius,
+            pending_exposure: None,
+            pending_radius: None,
+            camera_lock_circle: settings.lock_circle,
+            theme: settings.theme,
+            show_prediction_preview: false,
+            prediction_preview: None,
             is_recording: false,
             recording_elapsed_time: 0.0,
             recording_mode: "MAM".to_string(),
             recorded_dataset_path: String::new(),
-            ama_video_path: String::new(),
+            mam_video_path: None,
+            ama_video_path: None,
             dataset_path: String::new(),
+            shared_dataset_source: String::new(),
             mam_video_status: "未导入".to_string(),
             ama_video_status: "未处理".to_string(),
             persistent_dataset_status: "未导入".to_string(),
             training_status: "无可用模型".to_string(),
             is_model_ready: false,
+            feature_config_warning: None,
             train_show_roc: true,
+            normalization: Normalization::None,
             train_show_cm: true,
             is_static_running: false,
             static_pre_rotation_angle: 0.0,
             static_measurement_status: "空闲".to_string(),
             static_results: Vec::new(),
+            static_results_dirty: false,
+            dynamic_results_dirty: false,
+            show_exit_confirm: false,
+            exit_after_save: false,
+            step_calibration: None,
+            allow_close: false,
             dynamic_params: DynamicExpParams {
                 path: PathBuf::new(),
                 temperature: 25.0,
                 sucrose_conc: 0.0,
                 hcl_conc: 0.0,
+                sucrose_unit: ConcUnit::GramPerMl,
+                hcl_unit: ConcUnit::MolPerL,
                 pre_rotation_angle: 5.0,
-                step_angle: -0.5,
+                step_angle: 0.5,
                 sample_points: 12,
+                sample_direction: SampleDirection::Dextro,
+                sampling_mode: SamplingMode::PredictionFlip,
+                sample_interval: 60.0,
+                settle_delay_ms: 100,
+                poll_interval_ms: 50,
+                consensus_window: 5,
+                consensus_count: 3,
+                confirm_delay_ms: 0,
+                max_duration_min: 90,
+                notes: String::new(),
+                skip_pre_rotation: false,
+                pre_rotation_target: PreRotationTarget::default(),
             },
             dynamic_save_path: None,
             dynamic_measurement_status: String::new(),
+            dynamic_phase: DynamicPhase::Idle,
             dynamic_results: Vec::new(),
             is_dynamic_exp_running: false,
             start_time: None,
+            dynamic_run_id: None,
             data_import_path: String::new(),
             alpha_inf: 0.0,
+            alpha_inf_set: false,
+            auto_alpha_inf: true,
+            carried_alpha_inf: None,
             regression_mode: RegressionMode::Log,
             regression_formula: String::new(),
+            r_squared: f64::NAN,
+            regression_summary: String::new(),
+            regression_warning: String::new(),
+            exclude_trend_reversals: true,
             raw_plot_data: Arc::new(Vec::new()),
             plot_scatter_points: Vec::new(),
             plot_line_points: Vec::new(),
+            sheet_picker: None,
+            param_bounds: ParamBounds::default(),
+            export_layout: ExportLayout::default(),
+            is_export_layout_open: false,
+            diagnostics: None,
             static_times: 1,
+            zero_record: None,
+            serial_reconnected_since_zero: false,
+            decision_margin: 1,
+            zero_search_progress: None,
+            rezero_confirmed: false,
         }
     }
 
@@ -311,9 +686,34 @@ impl PolarimeterApp {
         while let Ok(update) = self.update_rx.try_recv() {
             match update {
                 Update::General(update) => match update {
-                    GeneralUpdate::StatusMessage(msg) => self.status_message = msg,
-                    GeneralUpdate::Error(err_msg) => {
+                    GeneralUpdate::StatusMessage(msg) => {
+                        self.push_toast(msg.clone(), false);
+                        self.status_message = msg;
+                    }
+                    GeneralUpdate::Error(err) => {
+                        // 按错误类别补充下一步该做什么
+                        let hint = match &err {
+                            BackendError::SerialDisconnected(_) => {
+                                self.is_serial_connected = false;
+                                Some("请重新连接串口并重新找零点")
+                            }
+                            BackendError::CameraLost => Some("请检查相机连接，并在设备页重新连接相机"),
+                            BackendError::Timeout(_) => {
+                                Some("请检查视场与识别设置，必要时重新找零点")
+                            }
+                            BackendError::Interrupted => {
+                                Some("电机已停在当前位置，已转动的步数已计入，零点仍然有效")
+                            }
+                            BackendError::NotReady(_) | BackendError::Other(_) => None,
+                        };
+                        let err_msg = match hint {
+                            Some(hint) => format!("{}。{}", err, hint),
+                            None => err.to_string(),
+                        };
+                        self.push_toast(err_msg.clone(), true);
                         self.status_message = format!("错误: {}", err_msg);
+                        // 保存失败时留在程序中，结果仍标记为未保存
+                        self.exit_after_save = false;
                     }
                     GeneralUpdate::NewLog(log_line) => {
                         // <--- 新增的处理分支
@@ -323,6 +723,9 @@ impl PolarimeterApp {
                             self.log_buffer.pop_front();
                         }
                     }
+                    GeneralUpdate::ExperimentEvent(event) => self.experiment_log.push(event),
+                    GeneralUpdate::ExportLayout(layout) => self.export_layout = layout,
+                    GeneralUpdate::Diagnostics(diagnostics) => self.diagnostics = Some(diagnostics),
                 },
                 Update::Device(update) => match update {
                     DeviceUpdate::SerialPortsList(ports) => {
@@ -332,13 +735,95 @@ impl PolarimeterApp {
                         }
                     }
                     DeviceUpdate::SerialConnectionStatus(status) => {
-                        self.is_serial_connected = status
+                        self.is_serial_connected = status;
+                        if status && self.zero_record.is_some() {
+                            self.serial_reconnected_since_zero = true;
+                        }
+                    }
+                    DeviceUpdate::CameraList(cameras) => {
+                        if !cameras.is_empty() {
+                            self.camera_error = None;
+                        }
+                        self.selected_camera_idx =
+                            self.selected_camera_idx.min(cameras.len().saturating_sub(1));
+                        self.camera_list = cameras;
+                    }
+                    DeviceUpdate::CameraStatus { connected, message } => {
+                        self.is_camera_connected = connected;
+                        if !connected {
+                            self.exposure_applied = None;
+                        }
+                        if let Some(message) = message {
+                            self.camera_error = Some(message);
+                        } else if connected {
+                            self.camera_error = None;
+                        }
+                        if !connected {
+                            if let Some(primary) = self.primary_camera.take() {
+                                // 主相机异常被后端移除
+                                self.connected_cameras.retain(|&i| i != primary);
+                            }
+                        }
+                    }
+                    DeviceUpdate::CamerasChanged { connected, primary } => {
+                        if !self.viewed_camera.is_some_and(|i| connected.contains(&i)) {
+                            self.viewed_camera = primary;
+                            self.camera_texture = None;
+                        }
+                        self.connected_cameras = connected;
+                        self.primary_camera = primary;
                     }
-                    DeviceUpdate::CameraList(cameras) => self.camera_list = cameras,
-                    DeviceUpdate::CameraConnectionStatus(status) => {
-                        self.is_camera_connected = status
+                    DeviceUpdate::PrimaryCameraSettings {
+                        exposure,
+                        min_radius,
+                        max_radius,
+                        lock_circle,
+                        capture_fps,
+                    } => {
+                        self.exposure = exposure;
+                        self.min_radius = min_radius;
+                        self.max_radius = max_radius;
+                        self.camera_lock_circle = lock_circle;
+                        self.capture_fps = capture_fps;
+                        self.recording_fps = self.recording_fps.min(self.capture_fps);
+                    }
+                    DeviceUpdate::CameraError(message) => self.camera_error = Some(message),
+                    DeviceUpdate::NewCameraFrame(index, img) => {
+                        if self.viewed_camera.or(self.primary_camera) == Some(index) {
+                            self.camera_image = Some(img);
+                        }
+                    }
+                    DeviceUpdate::PredictionPreview(preview) => self.prediction_preview = preview,
+                    DeviceUpdate::SerialTestResult(result) => {
+                        self.serial_test_result = Some(result)
+                    }
+                    DeviceUpdate::RadiusCalibrated { min, max } => {
+                        self.min_radius = min;
+                        self.max_radius = max;
+                        self.status_message = format!("半径已自动标定为 {} ~ {}", min, max);
+                        self.cmd_tx
+                            .send(Command::Camera(CameraCommand::SetHoughCircleRadius {
+                                min,
+                                max,
+                            }))
+                            .unwrap();
+                    }
+                    DeviceUpdate::ExposureApplied(value) => self.exposure_applied = Some(value),
+                    DeviceUpdate::CapturePaused(paused) => self.capture_paused = paused,
+                    DeviceUpdate::Odometer(steps) => self.odometer = steps,
+                    DeviceUpdate::RadiusStats(stats) => self.radius_stats = stats,
+                    DeviceUpdate::ExposureSwept(sweep) => {
+                        if let Some(value) = sweep.suggested {
+                            self.status_message = format!("曝光扫描完成，建议曝光 {}", value);
+                        }
+                        self.exposure_sweep = Some(sweep);
+                    }
+                    DeviceUpdate::OpenCvStatus(status) => {
+                        if status.is_err() {
+                            self.push_toast("OpenCV 未正确安装，相机功能不可用，详见欢迎页".to_string(), true);
+                        }
+                        self.opencv_status = Some(status);
                     }
-                    DeviceUpdate::NewCameraFrame(img) => self.camera_image = Some(img),
                 },
                 Update::Recording(update) => match update {
                     RecordingUpdate::StatusUpdate(status) => match status {
@@ -371,6 +856,13 @@ impl PolarimeterApp {
                     }
                     TrainingUpdate::TrainingStatus(msg) => self.training_status = msg,
                     TrainingUpdate::ModelReady(ready) => self.is_model_ready = ready,
+                    TrainingUpdate::FeatureConfigWarning(warning) => {
+                        // 只在刚出现不一致时弹出提示，之后的变化只更新监视页上的说明
+                        if let (Some(warning), None) = (&warning, &self.feature_config_warning) {
+                            self.push_toast(warning.clone(), true);
+                        }
+                        self.feature_config_warning = warning;
+                    }
                     TrainingUpdate::TrainingPlotsReady { cm, roc } => {
                         if cm.is_some() || roc.is_some() {
                             self.cm_data = cm;
@@ -392,20 +884,48 @@ impl PolarimeterApp {
                         self.static_measurement_status = msg.clone();
                         self.status_message = msg;
                     }
-                    MeasurementUpdate::StaticResults(results) => self.static_results = results,
-                    MeasurementUpdate::DynamicResults(results) => self.dynamic_results = results,
+                    MeasurementUpdate::StaticResults(results) => {
+                        if results.len() != self.static_results.len() {
+                            self.static_results_dirty = !results.is_empty();
+                        }
+                        self.static_results = results;
+                    }
+                    MeasurementUpdate::StaticSaved => self.static_results_dirty = false,
+                    MeasurementUpdate::DynamicResults(results) => {
+                        if results.len() != self.dynamic_results.len() {
+                            self.dynamic_results_dirty = !results.is_empty();
+                        }
+                        self.dynamic_results = results;
+                    }
+                    MeasurementUpdate::DynamicSaved => self.dynamic_results_dirty = false,
                     MeasurementUpdate::DynamicRunning(running) => {
                         self.is_dynamic_exp_running = running
                     }
+                    MeasurementUpdate::DynamicParams(params) => {
+                        // 保存路径与备注仍以当前界面为准
+                        let path = std::mem::take(&mut self.dynamic_params.path);
+                        let notes = std::mem::take(&mut self.dynamic_params.notes);
+                        self.dynamic_params = DynamicExpParams { path, notes, ..params };
+                    }
                     MeasurementUpdate::StaticRunning(running) => self.is_static_running = running,
                     MeasurementUpdate::CurrentSteps(steps) => {
                         if let Some(steps) = steps {
-                            self.current_angle = Some((steps as f32) / self.anglesteps);
+                            self.current_angle = steps_to_angle(steps, self.anglesteps);
                         } else {
                             self.current_angle = None;
+                            self.zero_record = None;
                         }
                     }
+                    MeasurementUpdate::ZeroEstablished(record) => {
+                        self.zero_record = Some(record);
+                        self.serial_reconnected_since_zero = false;
+                    }
                     MeasurementUpdate::StartTime(time) => self.start_time = time,
+                    MeasurementUpdate::RunId(run_id) => self.dynamic_run_id = run_id,
+                    MeasurementUpdate::ZeroSearchProgress(progress) => {
+                        self.zero_search_progress = progress
+                    }
+                    MeasurementUpdate::DynamicPhase(phase) => self.dynamic_phase = phase,
                     MeasurementUpdate::DynamicStatus(msg) => {
                         self.dynamic_measurement_status = msg.clone();
                         self.status_message = msg;
@@ -414,18 +934,35 @@ impl PolarimeterApp {
                 Update::DataProcessing(update) => match update {
                     DataProcessingUpdate::FullState(state) => {
                         self.raw_plot_data = state.raw_data;
-                        self.alpha_inf = state.alpha_inf;
+                        self.alpha_inf_set = state.alpha_inf.is_some();
+                        self.alpha_inf = state.alpha_inf.unwrap_or(0.0);
                         self.regression_mode = state.regression_mode;
                         self.regression_formula = state.regression_formula;
+                        self.regression_summary = state.regression_summary;
+                        self.regression_warning = state.regression_warning;
+                        self.r_squared = state.r_squared;
+                        self.exclude_trend_reversals = state.exclude_trend_reversals;
                         self.plot_scatter_points = state.plot_scatter_points;
                         self.plot_line_points = state.plot_line_points;
                     }
+                    DataProcessingUpdate::SheetChoices { path, sheets } => {
+                        self.sheet_picker = Some(SheetPicker {
+                            path,
+                            sheets,
+                            selected: 0,
+                        });
+                    }
                 },
             }
         }
     }
     fn handle_file_dialog_results(&mut self) {
-        if let Ok(Some(result)) = self.file_dialog_rx.try_recv() {
+        if let Ok(result) = self.file_dialog_rx.try_recv() {
+            let Some(result) = result else {
+                // 取消了对话框；若是退出前的保存，则放弃退出
+                self.exit_after_save = false;
+                return;
+            };
             match result {
                 FileDialogResult::StartRecording(path) => {
                     // <--- 新增的分支
@@ -447,21 +984,101 @@ impl PolarimeterApp {
                         }))
                         .unwrap();
                 }
-                FileDialogResult::SaveStaticResults(path) => {
+                FileDialogResult::ExportExperimentLog(path) => {
+                    let text: String = self
+                        .experiment_log
+                        .iter()
+                        .map(|e| format!("{}  {}\n", e.timestamp.format("%Y-%m-%d %H:%M:%S"), e.message))
+                        .collect();
+                    match std::fs::write(&path, text) {
+                        Ok(_) => self.push_toast(format!("实验记录已导出到 {}", path.display()), false),
+                        Err(e) => self.push_toast(format!("导出实验记录失败：{}", e), true),
+                    }
+                }
+                FileDialogResult::ExportEvaluation(path) => {
+                    self.cmd_tx
+                        .send(Command::Training(TrainingCommand::ExportEvaluation { path }))
+                        .unwrap();
+                }
+                FileDialogResult::SaveModel(path) => {
+                    self.cmd_tx
+                        .send(Command::Training(TrainingCommand::SaveModel { path }))
+                        .unwrap();
+                }
+                FileDialogResult::LoadModel(path) => {
+                    self.cmd_tx
+                        .send(Command::Training(TrainingCommand::LoadModel { path }))
+                        .unwrap();
+                }
+                FileDialogResult::TrainingVideo { mode, path } => {
+                    if mode == "MAM" {
+                        self.mam_video_path = Some(path);
+                    } else {
+                        self.ama_video_path = Some(path);
+                    }
+                }
+                FileDialogResult::ReplayPredictions(path) => {
                     self.cmd_tx
-                        .send(Command::StaticMeasure(StaticMeasureCommand::SaveResults {
+                        .send(Command::StaticMeasure(StaticMeasureCommand::ReplayPredictions {
                             path,
                         }))
                         .unwrap();
                 }
+                FileDialogResult::SaveStaticResults(path) => {
+                    self.cmd_tx
+                        .send(Command::StaticMeasure(StaticMeasureCommand::SaveResults { path }))
+                        .unwrap();
+                }
                 FileDialogResult::SaveDynamicExperiment(path) => {
                     self.dynamic_params.path = path.clone();
                     self.dynamic_save_path = Some(path);
                 }
+                FileDialogResult::SaveDynamicCsv(path) => {
+                    self.cmd_tx
+                        .send(Command::DynamicMeasure(DynamicMeasureCommand::SaveResultsCsv {
+                            path,
+                        }))
+                        .unwrap();
+                }
+                FileDialogResult::ExportFitReady(path) => {
+                    self.cmd_tx
+                        .send(Command::DynamicMeasure(DynamicMeasureCommand::ExportFitReady {
+                            path,
+                        }))
+                        .unwrap();
+                }
+                FileDialogResult::ImportDynamicParams(path) => {
+                    self.cmd_tx
+                        .send(Command::DynamicMeasure(DynamicMeasureCommand::ImportParams {
+                            path,
+                        }))
+                        .unwrap();
+                }
                 FileDialogResult::LoadDataProcessingFile(path) => {
                     self.cmd_tx
                         .send(Command::DataProcessing(DataProcessingCommand::LoadData {
                             path,
+                            sheet: None,
+                        }))
+                        .unwrap();
+                }
+                FileDialogResult::ExportPlotData(path) => {
+                    match std::fs::write(&path, self.plot_data_csv()) {
+                        Ok(_) => self.push_toast(format!("绘图数据已导出到 {}", path.display()), false),
+                        Err(e) => self.push_toast(format!("导出绘图数据失败：{}", e), true),
+                    }
+                }
+                FileDialogResult::ExportTwoColumn(path) => {
+                    match std::fs::write(&path, self.two_column_csv()) {
+                        Ok(_) => self.push_toast(format!("两列数据已导出到 {}", path.display()), false),
+                        Err(e) => self.push_toast(format!("导出两列数据失败：{}", e), true),
+                    }
+                }
+                FileDialogResult::ExportMergedRuns { inputs, output } => {
+                    self.cmd_tx
+                        .send(Command::DataProcessing(DataProcessingCommand::ExportMerged {
+                            inputs,
+                            output,
                         }))
                         .unwrap();
                 }
@@ -473,29 +1090,72 @@ impl PolarimeterApp {
     //  新布局的绘制函数
     // ===================================================================================
 
-    /// 绘制右侧的监视面板
     fn draw_welcome_tab(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            if ui.selectable_label(self.welcome_editing.is_some(), "编辑").clicked() {
+                self.welcome_editing = match self.welcome_editing {
+                    Some(_) => None,
+                    None => Some(self.welcome_text.clone()),
+                };
+            }
+            if let Some(draft) = &mut self.welcome_editing {
+                if ui.button("保存").clicked() {
+                    let text = draft.clone();
+                    let result = AppSettings::update(|settings| settings.welcome_text = Some(text));
+                    match result {
+                        Ok(_) => {
+                            self.welcome_text = std::mem::take(draft);
+                            self.welcome_editing = None;
+                            self.push_toast("欢迎语已保存".to_string(), false);
+                        }
+                        Err(e) => self.push_toast(format!("保存欢迎语失败：{}", e), true),
+                    }
+                } else if ui.button("恢复默认").clicked() {
+                    *draft = DEFAULT_WELCOME_TEXT.to_string();
+                }
+            }
+        });
+        if let Some(draft) = &mut self.welcome_editing {
+            ui.add(
+                egui::TextEdit::multiline(draft)
+                    .desired_width(f32::INFINITY)
+                    .desired_rows(12),
+            );
+            return;
+        }
         egui::ScrollArea::vertical().show(ui, |ui| {
             ui.vertical_centered(|ui| {
                 ui.add_space(ui.available_height() * 0.1); // 顶部留白
 
-                let welcome_text = RichText::new(
-                    r#"欢迎使用旋光仪控制软件 v1.5.5
-请遵循顶部标签页的引导，按以下顺序完成实验：
-1.  设备控制: 连接并检查旋光仪电机与相机硬件。
-2.  模型训练: (首次使用或更换环境时) 录制视频并训练用于识别旋光状态的 AI 模型。
-3.  静态与动态测量: 填入实验参数，开始自动化的数据采集流程。
-4.  数据处理: 导入实验数据，动力学拟合与分析。
-
-祝实验顺利！"#,
-                )
-                .heading()
-                .line_height(Some(32.0));
+                let welcome_text = RichText::new(&self.welcome_text)
+                    .heading()
+                    .line_height(Some(32.0));
 
                 ui.label(welcome_text); // 限制文本最大宽度，使其在宽屏上更易读
+
+                ui.add_space(20.0);
+                match &self.opencv_status {
+                    Some(Ok(version)) => {
+                        ui.weak(format!("OpenCV {}", version));
+                    }
+                    Some(Err(e)) => {
+                        ui.label(
+                            RichText::new(format!(
+                                "OpenCV 未正确安装：{}\n请按说明重新安装 OpenCV 并确认其库文件在系统路径中",
+                                e
+                            ))
+                            .color(Color32::RED),
+                        );
+                    }
+                    None => {
+                        ui.weak("正在检查 OpenCV...");
+                    }
+                }
             });
         });
     }
+
+    /// 绘制右侧的监视面板
     fn draw_monitor_panel(&mut self, ui: &mut Ui) {
         // 该函数现在负责管理自己的内部布局，而不是依赖外部滚动条
         // --- 1. 顶部区域：状态清单 (固定高度) ---
@@ -504,6 +1164,21 @@ impl PolarimeterApp {
             .show_inside(ui, |ui| {
                 ui.heading("监视与状态");
                 ui.add_space(5.0);
+                let estop = egui::Button::new(
+                    RichText::new("⛔ 急停").size(18.0).strong().color(Color32::WHITE),
+                )
+                .fill(Color32::from_rgb(200, 30, 30))
+                .min_size(egui::vec2(ui.available_width(), 32.0));
+                if ui
+                    .add_enabled(self.is_serial_connected, estop)
+                    .on_hover_text("立即中断电机旋转，并停止找零点、测量与录制任务；动态测量不会自动回零")
+                    .clicked()
+                {
+                    self.cmd_tx
+                        .send(Command::General(GeneralCommand::StopAllMotion))
+                        .unwrap();
+                }
+                ui.add_space(5.0);
                 ui.label(RichText::new("准备清单").strong());
                 // ui.group(|ui| {
                 // ui.set_width(ui.available_width()-10.0); // 占满宽度
@@ -527,6 +1202,18 @@ impl PolarimeterApp {
                     RichText::new("❌ 识别模型: 未就绪").color(Color32::LIGHT_RED)
                 };
                 ui.label(model_status_text);
+                if let Some(warning) = &self.feature_config_warning {
+                    ui.label(
+                        RichText::new(format!("⚠ {}，识别可能变差，建议恢复设置或重新训练", warning))
+                            .color(Color32::from_rgb(230, 140, 0)),
+                    );
+                }
+                if self.is_camera_connected {
+                    self.draw_radius_stats(ui);
+                }
+                ui.add_space(5.0);
+                self.draw_action_readiness(ui);
+                self.draw_diagnostics(ui);
                 // });
                 ui.add_space(10.0);
             });
@@ -541,15 +1228,73 @@ impl PolarimeterApp {
                 // --- 圆圈设定 (在日志上面) ---
                 ui.add_space(10.0);
                 ui.label(RichText::new("曝光设定").strong());
-                if ui.add(
-                        // egui::Slider::new(&mut self.min_radius, 1..=self.max_radius)
-                        //     .text("最小圆半径"),
-                        egui::DragValue::new(&mut self.exposure).clamp_range(-10.0..=10.0).speed(0.5),
-                    ).changed(){
+                let exposure_drag = ui
+                    .horizontal(|ui| {
+                        let drag = ui.add(
+                            // egui::Slider::new(&mut self.min_radius, 1..=self.max_radius)
+                            //     .text("最小圆半径"),
+                            egui::DragValue::new(&mut self.exposure).clamp_range(-10.0..=10.0).speed(0.5),
+                        );
+                        if self.is_camera_connected {
+                            match self.exposure_applied {
+                                Some(applied) => ui.label(format!("实际: {:.2}", applied)),
+                                None => ui.weak("实际: --"),
+                            };
+                        }
+                        if ui
+                            .add_enabled(
+                                self.is_camera_connected
+                                    && !self.is_static_running
+                                    && !self.is_dynamic_exp_running,
+                                egui::Button::new("扫描曝光"),
+                            )
+                            .on_hover_text("逐档改变曝光并测量识别区域的平均亮度，给出建议值（约需 10 秒）")
+                            .clicked()
+                        {
+                            self.cmd_tx
+                                .send(Command::Camera(CameraCommand::SweepExposure))
+                                .unwrap();
+                        }
+                        drag
+                    })
+                    .inner;
+                if exposure_drag.changed() {
+                    self.pending_exposure = Some(std::time::Instant::now());
+                }
+                if exposure_drag.drag_released() || exposure_drag.lost_focus() {
+                    self.flush_exposure();
+                }
+                self.draw_exposure_sweep(ui);
+                ui.horizontal(|ui| {
+                    ui.label("采集帧率:");
+                    let fps = ui
+                        .add(
+                            egui::DragValue::new(&mut self.capture_fps)
+                                .speed(1.0)
+                                .suffix(" fps")
+                                .clamp_range(1.0..=120.0),
+                        )
+                        .on_hover_text("相机采集线程的目标帧率；电脑较慢时可调低以减少 CPU 占用");
+                    if fps.changed() {
+                        self.recording_fps = self.recording_fps.min(self.capture_fps);
                         self.cmd_tx
-                        .send(Command::Camera(CameraCommand::Exposure(self.exposure)))
-                        .unwrap();
+                            .send(Command::Camera(CameraCommand::SetCaptureFps(self.capture_fps)))
+                            .unwrap();
+                    }
+                    if ui
+                        .checkbox(&mut self.capture_paused, "暂停采集")
+                        .on_hover_text(
+                            "只做数据处理时可暂停以节省 CPU，相机保持连接；开始测量、录制等任务时会自动恢复",
+                        )
+                        .changed()
+                    {
+                        self.cmd_tx
+                            .send(Command::Camera(CameraCommand::SetCapturePaused(
+                                self.capture_paused,
+                            )))
+                            .unwrap();
                     }
+                });
                 ui.add_space(10.0);
                 ui.label(RichText::new("识别设定").strong()); // 占满宽度
                 if ui
@@ -562,6 +1307,16 @@ impl PolarimeterApp {
                         )))
                         .unwrap();
                 }
+                if ui
+                    .checkbox(&mut self.show_prediction_preview, "显示识别置信度")
+                    .changed()
+                {
+                    self.cmd_tx
+                        .send(Command::Camera(CameraCommand::SetPredictionPreview(
+                            self.show_prediction_preview,
+                        )))
+                        .unwrap();
+                }
                 ui.horizontal(|ui| {
                     ui.label("尺寸范围：");
                     let min_radius_slider = ui.add(
@@ -578,15 +1333,67 @@ impl PolarimeterApp {
                             .speed(5),
                     );
                     if min_radius_slider.changed() || max_radius_slider.changed() {
+                        self.pending_radius = Some(std::time::Instant::now());
+                    }
+                    if min_radius_slider.drag_released()
+                        || max_radius_slider.drag_released()
+                        || min_radius_slider.lost_focus()
+                        || max_radius_slider.lost_focus()
+                    {
+                        self.flush_radius();
+                    }
+                    if ui
+                        .add_enabled(self.is_camera_connected, egui::Button::new("自动标定半径"))
+                        .clicked()
+                    {
                         self.cmd_tx
-                            .send(Command::Camera(CameraCommand::SetHoughCircleRadius {
-                                min: self.min_radius,
-                                max: self.max_radius,
-                            }))
+                            .send(Command::Camera(CameraCommand::CalibrateRadius))
                             .unwrap();
                     }
                 });
                 ui.add_space(10.0);
+                egui::CollapsingHeader::new(RichText::new("实验记录").strong())
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(!self.experiment_log.is_empty(), egui::Button::new("导出"))
+                                .clicked()
+                            {
+                                let tx = self.file_dialog_tx.clone();
+                                thread::spawn(move || {
+                                    if let Some(path) = rfd::FileDialog::new()
+                                        .add_filter("文本", &["txt"])
+                                        .save_file()
+                                    {
+                                        tx.send(Some(FileDialogResult::ExportExperimentLog(path)))
+                                            .ok();
+                                    } else {
+                                        tx.send(None).ok();
+                                    }
+                                });
+                            }
+                            if ui.button("清空").clicked() {
+                                self.experiment_log.clear();
+                            }
+                        });
+                        Frame::group(ui.style()).show(ui, |ui| {
+                            ui.set_height(100.0);
+                            egui::ScrollArea::vertical()
+                                .id_source("experiment_log_scroll")
+                                .auto_shrink([false, false])
+                                .stick_to_bottom(true)
+                                .show(ui, |ui| {
+                                    for event in &self.experiment_log {
+                                        ui.label(format!(
+                                            "{}  {}",
+                                            event.timestamp.format("%H:%M:%S"),
+                                            event.message
+                                        ));
+                                    }
+                                });
+                        });
+                    });
                 ui.label(RichText::new("日志").strong());
                 Frame::group(ui.style()).show(ui, |ui| {
                     ui.set_height(120.0); // 可以适当增加高度
@@ -726,6 +1533,17 @@ impl PolarimeterApp {
                             .maintain_aspect_ratio(true);
 
                         ui.put(screen_rect, image);
+
+                        if let Some((class, confidence)) = self.prediction_preview {
+                            let label = if class == 0 { "MAM" } else { "AMA" };
+                            ui.painter().text(
+                                screen_rect.left_top() + Vec2::new(8.0, 8.0),
+                                egui::Align2::LEFT_TOP,
+                                format!("{} {:.3}", label, confidence),
+                                egui::FontId::proportional(18.0),
+                                Color32::YELLOW,
+                            );
+                        }
                     } else {
                         ui.centered_and_justified(|ui| {
                             ui.label("[无相机信号]");
@@ -742,11 +1560,12 @@ impl PolarimeterApp {
         // --- 标签页导航栏 ---
         // --- 根据当前标签页绘制对应内容 ---
         match self.active_tab {
+            Tab::Welcome => self.draw_welcome_tab(ui),
             Tab::DeviceControl => self.draw_device_control_tab(ui),
             Tab::ModelTraining => self.draw_model_training_tab(ui),
             Tab::StaticMeasurement => self.draw_static_measurement_tab(ui),
             Tab::DynamicMeasurement => self.draw_dynamic_measurement_tab(ui),
-            // Welcome 和 DataProcessing 在此函数外处理，这里无需匹配
+            // DataProcessing 在此函数外处理，这里无需匹配
             _ => {}
         }
     }
@@ -801,25 +1620,70 @@ impl PolarimeterApp {
                 }
             }
         });
+        ui.horizontal(|ui| {
+            ui.label("应答字符串:");
+            ui.add(egui::TextEdit::singleline(&mut self.serial_ack_token).desired_width(40.0));
+            ui.checkbox(&mut self.serial_stop_byte_enabled, "结束字节:");
+            ui.add_enabled(
+                self.serial_stop_byte_enabled,
+                egui::DragValue::new(&mut self.serial_stop_byte),
+            );
+            if ui.button("应用").clicked() {
+                self.cmd_tx
+                    .send(Command::Device(DeviceCommand::SetSerialProtocol {
+                        ack_token: self.serial_ack_token.clone(),
+                        stop_byte: if self.serial_stop_byte_enabled {
+                            Some(self.serial_stop_byte)
+                        } else {
+                            None
+                        },
+                    }))
+                    .unwrap();
+            }
+        });
+        if let Some(result) = &self.serial_test_result {
+            let text = format!(
+                "测试{}：应答 {:?}，往返 {:.1} ms",
+                if result.success { "成功" } else { "失败" },
+                result.reply,
+                result.round_trip_ms
+            );
+            let color = if result.success {
+                Color32::GREEN
+            } else {
+                Color32::LIGHT_RED
+            };
+            ui.label(RichText::new(text).color(color));
+        }
+        ui.horizontal(|ui| {
+            ui.label(format!("累计步数: {}", self.odometer));
+            if ui
+                .button("清零")
+                .on_hover_text("维护（如齿轮重新上油）后清零")
+                .clicked()
+            {
+                self.cmd_tx
+                    .send(Command::Device(DeviceCommand::ResetOdometer))
+                    .unwrap();
+            }
+        });
         ui.add_space(10.0);
 
         // --- 相机连接 ---
         ui.label(RichText::new("相机连接").strong());
         ui.horizontal(|ui| {
-            ui.add_enabled_ui(!self.is_camera_connected, |ui| {
-                let selected_text = self
-                    .camera_list
-                    .get(self.selected_camera_idx)
-                    .cloned()
-                    .unwrap_or_else(|| "N/A".to_string());
-                egui::ComboBox::from_id_source("camera_select")
-                    .selected_text(selected_text)
-                    .show_ui(ui, |ui| {
-                        for (i, cam) in self.camera_list.iter().enumerate() {
-                            ui.selectable_value(&mut self.selected_camera_idx, i, cam);
-                        }
-                    });
-            });
+            let selected_text = self
+                .camera_list
+                .get(self.selected_camera_idx)
+                .cloned()
+                .unwrap_or_else(|| "N/A".to_string());
+            egui::ComboBox::from_id_source("camera_select")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    for (i, cam) in self.camera_list.iter().enumerate() {
+                        ui.selectable_value(&mut self.selected_camera_idx, i, cam);
+                    }
+                });
 
             if ui.button("刷新").clicked() {
                 self.cmd_tx
@@ -827,12 +1691,16 @@ impl PolarimeterApp {
                     .unwrap();
             }
 
-            if self.is_camera_connected {
+            if self.connected_cameras.contains(&self.selected_camera_idx) {
                 if ui.button("断开").clicked() {
                     self.cmd_tx
-                        .send(Command::Camera(CameraCommand::Disconnect))
+                        .send(Command::Camera(CameraCommand::Disconnect {
+                            index: self.selected_camera_idx,
+                        }))
                         .unwrap();
-                    self.camera_texture = None;
+                    if self.viewed_camera == Some(self.selected_camera_idx) {
+                        self.camera_texture = None;
+                    }
                 }
             } else {
                 if ui.button("连接").clicked() {
@@ -844,7 +1712,50 @@ impl PolarimeterApp {
                 }
             }
         });
-        ui.add_space(10.0);
+        if self.connected_cameras.len() > 1 {
+            ui.horizontal(|ui| {
+                ui.label("识别主相机:");
+                let mut primary = self.primary_camera;
+                egui::ComboBox::from_id_source("primary_camera_select")
+                    .selected_text(primary.map_or("N/A".to_string(), |i| format!("Camera {}", i)))
+                    .show_ui(ui, |ui| {
+                        for &i in &self.connected_cameras {
+                            ui.selectable_value(&mut primary, Some(i), format!("Camera {}", i));
+                        }
+                    });
+                if primary != self.primary_camera {
+                    if let Some(index) = primary {
+                        self.cmd_tx
+                            .send(Command::Camera(CameraCommand::SetPrimary { index }))
+                            .unwrap();
+                    }
+                }
+                ui.label("显示画面:");
+                let mut viewed = self.viewed_camera;
+                egui::ComboBox::from_id_source("viewed_camera_select")
+                    .selected_text(viewed.map_or("N/A".to_string(), |i| format!("Camera {}", i)))
+                    .show_ui(ui, |ui| {
+                        for &i in &self.connected_cameras {
+                            ui.selectable_value(&mut viewed, Some(i), format!("Camera {}", i));
+                        }
+                    });
+                if viewed != self.viewed_camera {
+                    self.viewed_camera = viewed;
+                    self.camera_texture = None;
+                }
+            });
+        }
+        if let Some(message) = &self.camera_error {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(message).color(Color32::RED));
+                if ui.button("重新检测").clicked() {
+                    self.cmd_tx
+                        .send(Command::Camera(CameraCommand::RefreshCameras))
+                        .unwrap();
+                }
+            });
+        }
+        ui.add_space(10.0);
         ui.separator();
 
         // --- 电机参数与控制 ---
@@ -868,19 +1779,43 @@ impl PolarimeterApp {
         });
         ui.horizontal(|ui|{
             ui.label("1°=");
+            let previous = self.anglesteps;
             let anglesteps=ui.add(
                 egui::DragValue::new(&mut self.anglesteps)
                     .speed(1)
                     .suffix("步")
-                    .clamp_range(0.0..=2000.0),
+                    .clamp_range(1.0..=2000.0),
             );
             if anglesteps.changed(){
-                self.cmd_tx
-                    .send(Command::Device(DeviceCommand::SetStep(self.anglesteps)))
-                    .unwrap();
+                if is_valid_steps_per_degree(self.anglesteps) {
+                    self.cmd_tx
+                        .send(Command::Device(DeviceCommand::SetStep(self.anglesteps)))
+                        .unwrap();
+                } else {
+                    self.anglesteps = previous;
+                    self.push_toast("每度步数必须为正数，已保留原值".to_string(), true);
+                }
+            }
+            let idle = !self.is_static_running && !self.is_dynamic_exp_running;
+            if ui
+                .add_enabled(
+                    self.is_serial_connected && idle && self.step_calibration.is_none(),
+                    egui::Button::new("标定..."),
+                )
+                .clicked()
+            {
+                self.step_calibration = Some(StepCalibration::default());
             }
-            
         });
+        ui.label(
+            RichText::new(rotation_direction_summary(
+                self.rotation_direction_is_ama,
+                self.rotation_direction_reverse,
+            ))
+            .color(Color32::from_rgb(0, 110, 180))
+            .strong(),
+        );
+        self.draw_jog_settings(ui);
         // ui.horizontal(|ui| {
         //     ui.label("旋转方向:");
         //     if ui
@@ -904,7 +1839,8 @@ impl PolarimeterApp {
         ui.heading("模型");
         ui.add_space(5.0);
         ui.label(RichText::new("手动控制").strong());
-        ui.add_enabled_ui(self.is_serial_connected&&self.rotation==false, |ui| {
+        let measurement_idle = !self.is_static_running && !self.is_dynamic_exp_running;
+        ui.add_enabled_ui(self.is_serial_connected&&self.rotation==false&&measurement_idle, |ui| {
             ui.horizontal(|ui| {
                 ui.label("手动旋转");
                 ui.add(
@@ -952,6 +1888,15 @@ impl PolarimeterApp {
                         .speed(0.1)
                         .suffix("°"),
                 );
+                ui.label("帧率：");
+                ui.add_enabled(
+                    !self.is_recording,
+                    egui::DragValue::new(&mut self.recording_fps)
+                        .speed(1.0)
+                        .suffix(" fps")
+                        .clamp_range(1.0..=self.capture_fps),
+                )
+                .on_hover_text("每秒保存的训练帧数，默认与相机采集帧率一致；帧为模型输入的 20×20 灰度图");
                 if !self.is_recording && self.selected_record.is_none() {
                     if ui.button("选择路径").clicked() {
                         let tx = self.file_dialog_tx.clone();
@@ -974,6 +1919,7 @@ impl PolarimeterApp {
                                 mode: self.recording_mode.clone(),
                                 save_path: self.selected_record.as_mut().unwrap().clone(),
                                 num: (self.recording_angle * self.anglesteps).round() as i32,
+                                fps: self.recording_fps,
                             }))
                             .unwrap();
                         self.selected_record = None;
@@ -1067,37 +2013,101 @@ impl PolarimeterApp {
                 });
                 ui.label(&self.persistent_dataset_status);
                 ui.end_row();
+
+                ui.label("共享数据集:");
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.shared_dataset_source)
+                            .hint_text("网络路径或 http(s) 压缩包")
+                            .desired_width(150.0),
+                    );
+                    if ui.button("同步").clicked() && !self.shared_dataset_source.trim().is_empty()
+                    {
+                        self.dataset_path = self.shared_dataset_source.trim().to_string();
+                        self.cmd_tx
+                            .send(Command::Training(TrainingCommand::LoadSharedDataset {
+                                source: self.shared_dataset_source.trim().to_string(),
+                            }))
+                            .unwrap();
+                    }
+                });
+                ui.label("");
+                ui.end_row();
             });
 
         // ui.add_space(5.0);
 
-        // 将处理按钮放在 Grid 下方
-        // ui.horizontal(|ui| {
-        //     if ui.button("从此视频获取MAM数据").clicked() {
-        //         self.cmd_tx
-        //             .send(Command::Training(TrainingCommand::ProcessVideo {
-        //                 video_path: self.mam_video_path.clone().into(),
-        //                 mode: "MAM".to_string(),
-        //             }))
-        //             .unwrap();
-        //     }
-        //     if ui.button("从此视频获取AMA数据").clicked() {
-        //         self.cmd_tx
-        //             .send(Command::Training(TrainingCommand::ProcessVideo {
-        //                 video_path: self.ama_video_path.clone().into(),
-        //                 mode: "AMA".to_string(),
-        //             }))
-        //             .unwrap();
-        //     }
-        //     if ui.button("导入常驻数据集").clicked() {
-        //         self.cmd_tx
-        //             .send(Command::Training(TrainingCommand::LoadPersistentDataset {
-        //                 path: self.dataset_path.clone().into(),
-        //             }))
-        //             .unwrap();
-        //     }
-        // });
-        // });
+        ui.add_space(5.0);
+        egui::CollapsingHeader::new("从单个视频快速训练")
+            .default_open(false)
+            .show(ui, |ui| {
+                egui::Grid::new("quick_train_grid")
+                    .num_columns(3)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        for mode in ["MAM", "AMA"] {
+                            let (path, status) = if mode == "MAM" {
+                                (&self.mam_video_path, &self.mam_video_status)
+                            } else {
+                                (&self.ama_video_path, &self.ama_video_status)
+                            };
+                            ui.label(format!("{} 视频:", mode));
+                            ui.horizontal(|ui| {
+                                ui.with_layout(
+                                    egui::Layout::left_to_right(egui::Align::Center),
+                                    |ui| {
+                                        ui.set_max_width(150.0);
+                                        let name = path
+                                            .as_ref()
+                                            .and_then(|p| p.file_name())
+                                            .map(|n| n.to_string_lossy().into_owned())
+                                            .unwrap_or_default();
+                                        ui.add(egui::Label::new(name).truncate(true));
+                                    },
+                                );
+                                if ui.button("...").clicked() {
+                                    let tx = self.file_dialog_tx.clone();
+                                    thread::spawn(move || {
+                                        if let Some(path) = rfd::FileDialog::new()
+                                            .add_filter("视频", &["mp4", "avi", "mov", "mkv"])
+                                            .pick_file()
+                                        {
+                                            tx.send(Some(FileDialogResult::TrainingVideo {
+                                                mode: mode.to_string(),
+                                                path,
+                                            }))
+                                            .ok();
+                                        } else {
+                                            tx.send(None).ok();
+                                        }
+                                    });
+                                }
+                            });
+                            ui.label(status);
+                            ui.end_row();
+                        }
+                    });
+                if let (Some(mam_video), Some(ama_video)) =
+                    (&self.mam_video_path, &self.ama_video_path)
+                {
+                    if ui
+                        .button("处理并训练")
+                        .on_hover_text("用两段视频的全部帧替换录制数据集，再与常驻数据集一起训练")
+                        .clicked()
+                    {
+                        self.cmd_tx
+                            .send(Command::Training(TrainingCommand::QuickTrainFromVideos {
+                                mam_video: mam_video.clone(),
+                                ama_video: ama_video.clone(),
+                                show_roc: self.train_show_roc,
+                                show_cm: self.train_show_cm,
+                            }))
+                            .unwrap();
+                    }
+                } else {
+                    ui.add_enabled(false, egui::Button::new("处理并训练"));
+                }
+            });
         // --- 后续的训练、保存、加载等 UI 保持不变 ---
         ui.horizontal(|ui| {
             // ui.checkbox(&mut self.train_show_roc, "显示 ROC 曲线");
@@ -1110,7 +2120,57 @@ impl PolarimeterApp {
                     }))
                     .unwrap();
             };
+            if ui
+                .add_enabled(self.is_model_ready, egui::Button::new("保存模型"))
+                .clicked()
+            {
+                let tx = self.file_dialog_tx.clone();
+                thread::spawn(move || {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("模型", &["json"])
+                        .set_file_name("model.json")
+                        .save_file()
+                    {
+                        tx.send(Some(FileDialogResult::SaveModel(path))).ok();
+                    } else {
+                        tx.send(None).ok();
+                    }
+                });
+            }
+            if ui.button("加载模型").clicked() {
+                let tx = self.file_dialog_tx.clone();
+                thread::spawn(move || {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("模型", &["json"])
+                        .pick_file()
+                    {
+                        tx.send(Some(FileDialogResult::LoadModel(path))).ok();
+                    } else {
+                        tx.send(None).ok();
+                    }
+                });
+            }
+            let before = self.normalization;
+            ComboBox::from_label("图像归一化")
+                .selected_text(normalization_label(self.normalization))
+                .show_ui(ui, |ui| {
+                    for option in [
+                        Normalization::None,
+                        Normalization::Standardize,
+                        Normalization::HistogramEqualize,
+                    ] {
+                        ui.selectable_value(&mut self.normalization, option, normalization_label(option));
+                    }
+                })
+                .response
+                .on_hover_text("训练与识别使用同一种归一化，可减弱光照变化的影响；修改后需重新训练才生效");
+            if self.normalization != before {
+                self.cmd_tx
+                    .send(Command::Training(TrainingCommand::SetNormalization(self.normalization)))
+                    .unwrap();
+            }
         });
+        self.draw_recognition_test(ui);
 
         // ui.label(format!("状态: {}", self.training_status));
         if let Some(cm) = &self.cm_data {
@@ -1118,8 +2178,24 @@ impl PolarimeterApp {
             ui.separator();
             ui.add_space(10.0);
 
-            ui.label(RichText::new("训练结果").strong());
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("训练结果").strong());
+                if ui.button("导出评估").clicked() {
+                    let tx = self.file_dialog_tx.clone();
+                    thread::spawn(move || {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("CSV", &["csv"])
+                            .save_file()
+                        {
+                            tx.send(Some(FileDialogResult::ExportEvaluation(path))).ok();
+                        } else {
+                            tx.send(None).ok();
+                        }
+                    });
+                }
+            });
             ui.label(format!("整体准确度: {:.2}%", cm.accuracy * 100.0));
+            ui.label(&self.training_status);
 
             egui::Grid::new("cm_grid_inline").show(ui, |ui| {
                 ui.label("");
@@ -1138,6 +2214,33 @@ impl PolarimeterApp {
                 ui.end_row();
             });
         }
+        if let Some(roc) = &self.roc_data {
+            ui.add_space(10.0);
+            ui.label(format!("验证集 ROC 曲线（AMA 为阳性），AUC = {:.3}", roc.auc));
+            let curve: PlotPoints = roc.points.iter().map(|&(fpr, tpr)| [fpr, tpr]).collect();
+            let diagonal: PlotPoints = vec![[0.0, 0.0], [1.0, 1.0]].into();
+            Plot::new("roc_curve")
+                .height(220.0)
+                .width(240.0)
+                .data_aspect(1.0)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .x_axis_label("假阳性率")
+                .y_axis_label("真阳性率")
+                .include_x(0.0)
+                .include_x(1.0)
+                .include_y(0.0)
+                .include_y(1.0)
+                .show(ui, |plot_ui| {
+                    plot_ui.line(
+                        Line::new(diagonal)
+                            .color(Color32::GRAY)
+                            .style(egui_plot::LineStyle::dashed_loose()),
+                    );
+                    plot_ui.line(Line::new(curve).name("ROC"));
+                });
+        }
         ui.add_space(10.0);
         ui.separator();
         ui.add_space(10.0);
@@ -1148,11 +2251,27 @@ impl PolarimeterApp {
                 if !self.is_static_running {
                     // 借用 is_static_running 状态
 
-                    if ui.button("寻找旋光零点").clicked() {
-                        self.cmd_tx
-                            .send(Command::Device(DeviceCommand::FindZeroPoint))
-                            .unwrap();
-                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("寻找旋光零点").clicked() {
+                            self.cmd_tx
+                                .send(Command::Device(DeviceCommand::FindZeroPoint))
+                                .unwrap();
+                        }
+                        if ui
+                            .add_enabled(
+                                self.current_angle.is_some()
+                                    && !self.rotation
+                                    && !self.is_dynamic_exp_running,
+                                egui::Button::new("回到上次零点"),
+                            )
+                            .on_hover_text("按步数转回记录的零点，再在附近 ±3° 内重新识别边界，消除丢步误差")
+                            .clicked()
+                        {
+                            self.cmd_tx
+                                .send(Command::Device(DeviceCommand::ReturnToZeroBySearch))
+                                .unwrap();
+                        }
+                    });
                 } else {
                     if ui.button("停止寻找").clicked() {
                         self.cmd_tx
@@ -1162,12 +2281,53 @@ impl PolarimeterApp {
                 }
             },
         );
+        if let Some((elapsed, remaining)) = self.zero_search_progress {
+            ui.label(format!("已用时 {:.1} s，剩余超时 {:.1} s", elapsed, remaining));
+        }
         ui.add_space(10.0);
         ui.label(RichText::new("电机状态").strong());
-        if let Some(ang) = self.current_angle {
-            ui.label(format!("当前角度: {:.2}°", ang));
+        self.draw_current_angle(ui);
+    }
+
+    /// 显示当前角度，旁边的按钮可把数值复制到剪贴板
+    fn draw_current_angle(&mut self, ui: &mut Ui) {
+        let Some(ang) = self.current_angle else {
+            ui.label("没有有效零点");
+            return;
+        };
+        let text = format!("{:.*}", ANGLE_DECIMALS, ang);
+        ui.horizontal(|ui| {
+            ui.label(format!("当前角度: {}°", text));
+            if ui.small_button("📋").on_hover_text("复制当前角度").clicked() {
+                ui.output_mut(|o| o.copied_text = text.clone());
+                self.push_toast(format!("已复制角度 {}", text), false);
+            }
+        });
+    }
+
+    /// 显示零点确立的时间与步数；零点可能已失效时给出提示
+    fn draw_zero_record(&self, ui: &mut Ui) {
+        let Some(record) = &self.zero_record else {
+            return;
+        };
+        ui.label(format!(
+            "零点确立于 {}，步数 {}",
+            record.timestamp.format("%H:%M"),
+            record.raw_steps
+        ));
+        let age = chrono::Local::now() - record.timestamp;
+        let reason = if self.serial_reconnected_since_zero {
+            Some("串口已重新连接".to_string())
+        } else if age.num_minutes() >= ZERO_STALE_AFTER_MINUTES {
+            Some(format!("距零点确立已过去 {} 分钟", age.num_minutes()))
         } else {
-            ui.label(format!("没有有效零点"));
+            None
+        };
+        if let Some(reason) = reason {
+            ui.label(
+                RichText::new(format!("{}，建议重新寻找零点", reason))
+                    .color(Color32::from_rgb(230, 140, 0)),
+            );
         }
     }
 
@@ -1176,16 +2336,15 @@ impl PolarimeterApp {
         ui.heading("静态测量");
         ui.add_space(5.0);
         ui.label(RichText::new("电机状态").strong());
-        if let Some(ang) = self.current_angle {
-            ui.label(format!("当前角度: {:.2}°", ang));
-        } else {
-            ui.label(format!("没有有效零点"));
-        }
+        self.draw_current_angle(ui);
+        self.draw_zero_record(ui);
 
         ui.add_space(10.0);
         ui.label(RichText::new("手动控制").strong());
+        self.draw_jog_settings(ui);
         ui.add_enabled_ui(self.is_serial_connected, |ui| {
-            ui.add_enabled_ui(self.current_angle.is_some()&&self.rotation==false, |ui| {
+            let measurement_idle = !self.is_static_running && !self.is_dynamic_exp_running;
+            ui.add_enabled_ui(self.current_angle.is_some()&&self.rotation==false&&measurement_idle, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("手动旋转至");
                     ui.add(
@@ -1203,6 +2362,40 @@ impl PolarimeterApp {
                     }
                 });
             });
+            ui.add_enabled_ui(
+                self.rotation == false && !self.is_static_running && !self.is_dynamic_exp_running,
+                |ui| {
+                    ui.horizontal(|ui| {
+                        if self.current_angle.is_none() {
+                            ui.checkbox(&mut self.rezero_confirmed, "已手动对准");
+                        }
+                        let can_rezero = self.current_angle.is_some() || self.rezero_confirmed;
+                        if ui
+                            .add_enabled(can_rezero, egui::Button::new("设当前位置为零点"))
+                            .clicked()
+                        {
+                            self.cmd_tx
+                                .send(Command::StaticMeasure(StaticMeasureCommand::RezeroHere {
+                                    confirmed: self.rezero_confirmed,
+                                }))
+                                .unwrap();
+                            self.rezero_confirmed = false;
+                        }
+                        let can_verify = self.current_angle.is_some()
+                            && self.is_camera_connected
+                            && self.is_model_ready;
+                        if ui
+                            .add_enabled(can_verify, egui::Button::new("校验零点"))
+                            .on_hover_text("在当前位置快速识别视场，检查零点是否因丢步而漂移")
+                            .clicked()
+                        {
+                            self.cmd_tx
+                                .send(Command::StaticMeasure(StaticMeasureCommand::VerifyZero))
+                                .unwrap();
+                        }
+                    });
+                },
+            );
         });
         ui.add_space(10.0);
         ui.separator();
@@ -1224,7 +2417,11 @@ impl PolarimeterApp {
                 device_and_model_ready && !self.is_dynamic_exp_running,
                 |ui| {
                     if !self.is_static_running {
-                        if ui.button("运行精细测量").clicked() {
+                        if ui
+                            .button("运行精细测量")
+                            .on_hover_text("快捷键：Enter（没有输入框或按钮获得焦点时）")
+                            .clicked()
+                        {
                             self.cmd_tx
                                 .send(Command::StaticMeasure(
                                     StaticMeasureCommand::RunSingleMeasurement {
@@ -1244,6 +2441,41 @@ impl PolarimeterApp {
             );
             // ui.label(format!("{}", self.static_measurement_status));
         });
+        ui.add_enabled_ui(!self.is_static_running, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("判定迟滞:");
+                let margin = ui
+                    .add(egui::DragValue::new(&mut self.decision_margin).clamp_range(0..=2))
+                    .on_hover_text(format!(
+                        "最近 5 次识别中需有 {} 次翻转才判定越过零点；\n数值越大越不易在零点附近来回抖动，但判定稍慢",
+                        3 + self.decision_margin
+                    ));
+                if margin.changed() {
+                    self.cmd_tx
+                        .send(Command::StaticMeasure(StaticMeasureCommand::SetDecisionMargin(
+                            self.decision_margin,
+                        )))
+                        .unwrap();
+                }
+                if ui
+                    .small_button("回放预测...")
+                    .on_hover_text("开发者功能：选择记录的预测值 CSV（每行最后一列为 0/1/2），\n按当前设置回放判定逻辑，每一步的动作写入日志")
+                    .clicked()
+                {
+                    let tx = self.file_dialog_tx.clone();
+                    thread::spawn(move || {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("CSV", &["csv", "txt"])
+                            .pick_file()
+                        {
+                            tx.send(Some(FileDialogResult::ReplayPredictions(path))).ok();
+                        } else {
+                            tx.send(None).ok();
+                        }
+                    });
+                }
+            });
+        });
 
         ui.add_space(10.0);
         // ui.add_enabled_ui(self.is_in_measurement_mode, |ui| {
@@ -1288,6 +2520,7 @@ impl PolarimeterApp {
                 thread::spawn(move || {
                     if let Some(path) = rfd::FileDialog::new()
                         .add_filter("Excel", &["xlsx"])
+                        .add_filter("CSV", &["csv"])
                         .save_file()
                     {
                         tx.send(Some(FileDialogResult::SaveStaticResults(path)))
@@ -1302,6 +2535,7 @@ impl PolarimeterApp {
                     .send(Command::StaticMeasure(StaticMeasureCommand::ClearResults))
                     .unwrap();
             }
+            ui.toggle_value(&mut self.is_export_layout_open, "导出列设置");
         });
         ui.add_space(10.0);
         TableBuilder::new(ui)
@@ -1344,15 +2578,13 @@ impl PolarimeterApp {
         ui.heading("动态测量");
         ui.add_space(5.0);
         ui.label(RichText::new("电机状态").strong());
-        if let Some(ang) = self.current_angle {
-            ui.label(format!("当前角度: {:.2}°", ang));
-        } else {
-            ui.label(format!("没有有效零点"));
-        }
+        self.draw_current_angle(ui);
+        self.draw_zero_record(ui);
         ui.add_space(10.0);
         ui.label(RichText::new("手动控制").strong());
         ui.add_enabled_ui(self.is_serial_connected, |ui| {
-            ui.add_enabled_ui(self.current_angle.is_some()&&self.rotation==false, |ui| {
+            let measurement_idle = !self.is_static_running && !self.is_dynamic_exp_running;
+            ui.add_enabled_ui(self.current_angle.is_some()&&self.rotation==false&&measurement_idle, |ui| {
                 ui.horizontal(|ui| {
                     ui.label("手动旋转至");
                     ui.add(
@@ -1386,20 +2618,33 @@ impl PolarimeterApp {
                 ui
                     .add(egui::DragValue::new(&mut self.dynamic_params.temperature));
 
-                ui.label("蔗糖浓度 (g/mL):");
-                ui
-                    .add(egui::DragValue::new(&mut self.dynamic_params.sucrose_conc));
-                    
+                ui.label("蔗糖浓度:");
+                conc_input(
+                    ui,
+                    "sucrose_unit",
+                    &mut self.dynamic_params.sucrose_conc,
+                    &mut self.dynamic_params.sucrose_unit,
+                    &ConcUnit::SUCROSE,
+                );
 
-                ui.label("盐酸浓度 (mol/L):");
-                ui.add(egui::DragValue::new(&mut self.dynamic_params.hcl_conc));
+                ui.label("盐酸浓度:");
+                conc_input(
+                    ui,
+                    "hcl_unit",
+                    &mut self.dynamic_params.hcl_conc,
+                    &mut self.dynamic_params.hcl_unit,
+                    &ConcUnit::HCL,
+                );
 
                 ui.end_row(); // 结束第一行
 
                 // --- 第二行：2个参数 ---
                 ui.label("步进角度(°):");
-                ui
-                    .add(egui::DragValue::new(&mut self.dynamic_params.step_angle));
+                ui.add(
+                    egui::DragValue::new(&mut self.dynamic_params.step_angle)
+                        .clamp_range(0.0..=10.0)
+                        .speed(0.05),
+                );
                     
 
                 ui.label("采样点数目:");
@@ -1417,7 +2662,184 @@ impl PolarimeterApp {
                 }
                 // 第二行只填充了4列，剩下的2列会留空，自然形成了 "上三下二" 的效果
                 ui.end_row(); // 结束第二行
+
+                ui.label("样品旋光方向:");
+                ui.radio_value(
+                    &mut self.dynamic_params.sample_direction,
+                    SampleDirection::Dextro,
+                    "右旋",
+                );
+                ui.radio_value(
+                    &mut self.dynamic_params.sample_direction,
+                    SampleDirection::Levo,
+                    "左旋",
+                );
+                ui.end_row();
+
+                ui.label("采样方式:");
+                ui.radio_value(
+                    &mut self.dynamic_params.sampling_mode,
+                    SamplingMode::PredictionFlip,
+                    "按识别翻转",
+                );
+                ui.radio_value(
+                    &mut self.dynamic_params.sampling_mode,
+                    SamplingMode::Timed,
+                    "按时间",
+                );
+                ui.label("采样间隔:");
+                ui.add_enabled(
+                    self.dynamic_params.sampling_mode == SamplingMode::Timed,
+                    egui::DragValue::new(&mut self.dynamic_params.sample_interval)
+                        .clamp_range(1.0..=3600.0)
+                        .suffix(" s"),
+                );
+                ui.end_row();
+
+                ui.label("转动后等待:");
+                ui.add(
+                    egui::DragValue::new(&mut self.dynamic_params.settle_delay_ms)
+                        .clamp_range(0..=10000)
+                        .speed(10)
+                        .suffix(" ms"),
+                );
+                ui.label("轮询间隔:");
+                ui.add(
+                    egui::DragValue::new(&mut self.dynamic_params.poll_interval_ms)
+                        .clamp_range(5..=2000)
+                        .speed(5)
+                        .suffix(" ms"),
+                );
+                ui.end_row();
+
+                ui.label("翻转判定:");
+                ui.add_enabled_ui(
+                    self.dynamic_params.sampling_mode == SamplingMode::PredictionFlip,
+                    |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("最近");
+                            ui.add(
+                                egui::DragValue::new(&mut self.dynamic_params.consensus_window)
+                                    .clamp_range(1..=20),
+                            );
+                            ui.label("帧中");
+                            let max_count = self.dynamic_params.consensus_window;
+                            ui.add(
+                                egui::DragValue::new(&mut self.dynamic_params.consensus_count)
+                                    .clamp_range(1..=max_count),
+                            );
+                            ui.label("帧翻转");
+                        })
+                        .response
+                        .on_hover_text("数值越大越不易被个别误判触发，但采样会稍有延迟");
+                    },
+                );
+                ui.end_row();
+
+                ui.label("翻转确认:");
+                ui.add_enabled(
+                    self.dynamic_params.sampling_mode == SamplingMode::PredictionFlip,
+                    egui::DragValue::new(&mut self.dynamic_params.confirm_delay_ms)
+                        .clamp_range(0..=5000)
+                        .speed(10)
+                        .suffix(" ms"),
+                )
+                .on_hover_text("触发后等待该时长再复核一次，翻转仍成立才记录，否则丢弃这次误触发；0 表示不复核");
+                ui.end_row();
+
+                ui.label("最长运行:");
+                ui.add(
+                    egui::DragValue::new(&mut self.dynamic_params.max_duration_min)
+                        .clamp_range(1..=1440)
+                        .suffix(" min"),
+                )
+                .on_hover_text("从开始计时起超过该时长即停止跟踪并保存已采集的数据，\n防止无人值守时实验卡住后一直运行");
+                ui.end_row();
+
+                ui.label("预旋转:");
+                ui.checkbox(&mut self.dynamic_params.skip_pre_rotation, "跳过预旋转")
+                    .on_hover_text("样品已摆好位置时勾选，开始跟踪后直接从当前位置等待翻转，不再寻找交界");
+                ui.label("对齐判据:");
+                ui.add_enabled_ui(!self.dynamic_params.skip_pre_rotation, |ui| {
+                    ComboBox::from_id_source("pre_rotation_target")
+                        .selected_text(self.dynamic_params.pre_rotation_target.label())
+                        .show_ui(ui, |ui| {
+                            for target in [
+                                PreRotationTarget::AnyCrossing,
+                                PreRotationTarget::FromMam,
+                                PreRotationTarget::FromAma,
+                            ] {
+                                ui.selectable_value(
+                                    &mut self.dynamic_params.pre_rotation_target,
+                                    target,
+                                    target.label(),
+                                );
+                            }
+                        })
+                        .response
+                        .on_hover_text("预旋转越过交界时要求视场原先呈现的图样；\n从另一侧开始时会越过交界再折返，总是从同一侧逼近");
+                });
+                ui.end_row();
             });
+        ui.label("实验备注:");
+        let notes = ui.add(
+            egui::TextEdit::multiline(&mut self.dynamic_params.notes)
+                .desired_rows(2)
+                .desired_width(f32::INFINITY)
+                .hint_text("如：水浴温度不稳、第三次尝试……"),
+        );
+        // 备注随时可改，失去焦点时同步到后端，之后的保存都会带上
+        if notes.lost_focus() {
+            self.cmd_tx
+                .send(Command::DynamicMeasure(DynamicMeasureCommand::UpdateParams {
+                    params: self.dynamic_params.clone(),
+                }))
+                .unwrap();
+        }
+        for warning in self.param_bounds.warnings(&self.dynamic_params) {
+            ui.label(RichText::new(warning).color(Color32::from_rgb(230, 140, 0)));
+        }
+        egui::CollapsingHeader::new("参数合理范围")
+            .default_open(false)
+            .show(ui, |ui| {
+                egui::Grid::new("param_bounds_grid").num_columns(4).show(ui, |ui| {
+                    for (name, (min, max), speed) in [
+                        ("实验温度 (°C):", &mut self.param_bounds.temperature, 0.5),
+                        ("蔗糖浓度 (g/mL):", &mut self.param_bounds.sucrose_conc, 0.01),
+                        ("盐酸浓度 (mol/L):", &mut self.param_bounds.hcl_conc, 0.05),
+                    ] {
+                        ui.label(name);
+                        ui.add(egui::DragValue::new(min).speed(speed).clamp_range(f32::MIN..=*max));
+                        ui.label("~");
+                        ui.add(egui::DragValue::new(max).speed(speed).clamp_range(*min..=f32::MAX));
+                        ui.end_row();
+                    }
+                });
+            });
+        ui.add_enabled_ui(!self.is_dynamic_exp_running, |ui| {
+            if ui.button("从文件导入参数").clicked() {
+                let tx = self.file_dialog_tx.clone();
+                thread::spawn(move || {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Excel", &["xlsx"])
+                        .pick_file()
+                    {
+                        tx.send(Some(FileDialogResult::ImportDynamicParams(path))).ok();
+                    } else {
+                        tx.send(None).ok();
+                    }
+                });
+            }
+        });
+        let trend = match self.dynamic_params.sample_direction {
+            SampleDirection::Dextro => "递减",
+            SampleDirection::Levo => "递增",
+        };
+        ui.label(format!(
+            "有效方向：旋光角预期{}，每个采样点后转动 {:+.2}°",
+            trend,
+            self.dynamic_params.effective_step_angle()
+        ));
 
         ui.add_space(10.0);
         ui.label(RichText::new("动态测量控制").strong());
@@ -1435,6 +2857,7 @@ impl PolarimeterApp {
                             thread::spawn(move || {
                                 if let Some(path) = rfd::FileDialog::new()
                                     .add_filter("Excel", &["xlsx"])
+                                    .add_filter("CSV", &["csv"])
                                     .save_file()
                                 {
                                     tx.send(Some(FileDialogResult::SaveDynamicExperiment(path)))
@@ -1468,13 +2891,10 @@ impl PolarimeterApp {
                     }
                 },
             );
+            // 开始跟踪的启用条件与准备清单同出一处
+            let can_track = self.tracking_prerequisites().iter().all(|(_, ok)| *ok);
             ui.add_enabled_ui(
-                self.is_camera_connected
-                    && self.is_serial_connected
-                    && self.is_model_ready
-                    && !self.is_static_running
-                    && self.current_angle.is_some()
-                    && self.start_time.is_some(),
+                self.is_dynamic_exp_running || can_track,
                 |ui| {
                     if !self.is_dynamic_exp_running {
                         if ui.button("开始跟踪").clicked() {
@@ -1492,7 +2912,12 @@ impl PolarimeterApp {
                                 .unwrap();
                         }
                     } else {
-                        if ui.button("停止跟踪").clicked() {
+                        let stop_label = if self.dynamic_phase == DynamicPhase::PreRotating {
+                            "停止预旋转"
+                        } else {
+                            "停止跟踪"
+                        };
+                        if ui.button(stop_label).clicked() {
                             self.cmd_tx
                                 .send(Command::DynamicMeasure(DynamicMeasureCommand::Stop))
                                 .unwrap();
@@ -1500,11 +2925,60 @@ impl PolarimeterApp {
                     }
                 },
             );
+            if ui
+                .add_enabled(
+                    self.is_serial_connected
+                        && self.current_angle.is_some()
+                        && self.start_time.is_some()
+                        && !self.rotation,
+                    egui::Button::new("立即记录"),
+                )
+                .on_hover_text("自动触发漏点时，以当前时间和角度手动记录一个点")
+                .clicked()
+            {
+                self.cmd_tx
+                    .send(Command::DynamicMeasure(DynamicMeasureCommand::RecordNow))
+                    .unwrap();
+            }
         });
+        if self.dynamic_phase != DynamicPhase::Idle {
+            let color = if self.dynamic_phase.is_tracking() {
+                Color32::from_rgb(0, 160, 0)
+            } else {
+                Color32::from_rgb(230, 140, 0)
+            };
+            ui.horizontal(|ui| {
+                ui.label("阶段:");
+                Frame::none()
+                    .fill(color.gamma_multiply(0.2))
+                    .stroke(Stroke::new(1.0, color))
+                    .rounding(8.0)
+                    .inner_margin(egui::Margin::symmetric(8.0, 2.0))
+                    .show(ui, |ui| {
+                        ui.label(RichText::new(self.dynamic_phase.label()).color(color));
+                    });
+                if self.dynamic_phase == DynamicPhase::PreRotating {
+                    ui.weak("尚未开始采样");
+                }
+            });
+        }
         if let Some(time) = self.start_time {
             ui.label(format!("{:.2} s", time.elapsed().as_secs_f64()));
+            let remaining = self
+                .dynamic_params
+                .max_duration()
+                .saturating_sub(time.elapsed())
+                .as_secs();
+            ui.label(format!(
+                "距最长运行时间还剩 {}:{:02}",
+                remaining / 60,
+                remaining % 60
+            ));
             // ui.label(format!("{}", self.dynamic_measurement_status));
         }
+        if let Some(run_id) = &self.dynamic_run_id {
+            ui.label(format!("运行编号: {}", run_id));
+        }
         ui.add_space(10.0);
         // ui.label(format!("当前角度: {:.2}°", self.current_angle));
         ui.separator();
@@ -1531,29 +3005,85 @@ impl PolarimeterApp {
                     .send(Command::DynamicMeasure(DynamicMeasureCommand::ClearResults))
                     .unwrap();
             }
-        });
-        ui.add_space(10.0);
-        TableBuilder::new(ui)
-            .striped(true)
-            // .resizable(true)
-            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-            .columns(Column::auto().at_least(100.0), 4)
-            .header(20.0, |mut h| {
-                h.col(|ui| {
-                    ui.strong("序号");
-                });
-                h.col(|ui| {
-                    ui.strong("时间 (s)");
-                });
-                h.col(|ui| {
-                    ui.strong("步数");
-                });
-                h.col(|ui| {
+            if ui
+                .add_enabled(
+                    !self.dynamic_results.is_empty() && self.alpha_inf_set,
+                    egui::Button::new("导出拟合数据"),
+                )
+                .on_hover_text("按当前 α∞ 导出时间、角度、Δα、ln(Δα)、1/Δα，跳过 Δα ≤ 0 的点")
+                .on_disabled_hover_text("需要已有测量结果，并已在数据处理页设置 α∞")
+                .clicked()
+            {
+                let tx = self.file_dialog_tx.clone();
+                thread::spawn(move || {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Excel", &["xlsx"])
+                        .save_file()
+                    {
+                        tx.send(Some(FileDialogResult::ExportFitReady(path))).ok();
+                    } else {
+                        tx.send(None).ok();
+                    }
+                });
+            }
+            if ui
+                .add_enabled(!self.dynamic_results.is_empty(), egui::Button::new("另存为 CSV"))
+                .on_hover_text("按导出列设置把当前结果另存为 CSV，实验参数写在表头前的 # 注释行中")
+                .clicked()
+            {
+                let tx = self.file_dialog_tx.clone();
+                thread::spawn(move || {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("CSV", &["csv"])
+                        .save_file()
+                    {
+                        tx.send(Some(FileDialogResult::SaveDynamicCsv(path))).ok();
+                    } else {
+                        tx.send(None).ok();
+                    }
+                });
+            }
+            ui.toggle_value(&mut self.is_export_layout_open, "导出列设置");
+        });
+        ui.add_space(10.0);
+        let (intervals, median) = sample_intervals(&self.dynamic_results);
+        let long_color = Color32::from_rgb(230, 140, 0);
+        let short_color = Color32::from_rgb(80, 140, 230);
+        if let Some(median) = median {
+            let anomalies = intervals
+                .iter()
+                .flatten()
+                .filter(|&&dt| dt > median * INTERVAL_ANOMALY_RATIO || dt < median / INTERVAL_ANOMALY_RATIO)
+                .count();
+            ui.label(format!(
+                "采样间隔中位数 {:.1} s，异常间隔 {} 个（偏长为橙色，偏短为蓝色）",
+                median, anomalies
+            ));
+        }
+        TableBuilder::new(ui)
+            .striped(true)
+            // .resizable(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .columns(Column::auto().at_least(100.0), 5)
+            .header(20.0, |mut h| {
+                h.col(|ui| {
+                    ui.strong("序号");
+                });
+                h.col(|ui| {
+                    ui.strong("时间 (s)");
+                });
+                h.col(|ui| {
+                    ui.strong("间隔 (s)");
+                });
+                h.col(|ui| {
+                    ui.strong("步数");
+                });
+                h.col(|ui| {
                     ui.strong("角度 (°)");
                 });
             })
             .body(|mut body| {
-                for r in &self.dynamic_results {
+                for (r, interval) in self.dynamic_results.iter().zip(&intervals) {
                     body.row(20.0, |mut row| {
                         row.col(|ui| {
                             ui.label(r.index.to_string());
@@ -1561,6 +3091,26 @@ impl PolarimeterApp {
                         row.col(|ui| {
                             ui.label(format!("{:.2}", r.time));
                         });
+                        row.col(|ui| {
+                            let Some(dt) = *interval else {
+                                ui.label("-");
+                                return;
+                            };
+                            let text = RichText::new(format!("{:.1}", dt));
+                            match median {
+                                Some(m) if dt > m * INTERVAL_ANOMALY_RATIO => {
+                                    ui.label(text.color(long_color))
+                                        .on_hover_text("间隔明显偏长，识别可能在两点之间停滞");
+                                }
+                                Some(m) if dt < m / INTERVAL_ANOMALY_RATIO => {
+                                    ui.label(text.color(short_color))
+                                        .on_hover_text("间隔明显偏短，可能是误触发");
+                                }
+                                _ => {
+                                    ui.label(text);
+                                }
+                            }
+                        });
                         row.col(|ui| {
                             ui.label(r.steps.to_string());
                         });
@@ -1586,7 +3136,7 @@ impl PolarimeterApp {
                 let tx = self.file_dialog_tx.clone();
                 thread::spawn(move || {
                     if let Some(path) = rfd::FileDialog::new()
-                        .add_filter("Excel", &["xlsx"])
+                        .add_filter("结果文件", &["xlsx", "csv"])
                         .pick_file()
                     {
                         tx.send(Some(FileDialogResult::LoadDataProcessingFile(path)))
@@ -1596,12 +3146,44 @@ impl PolarimeterApp {
                     }
                 });
             }
+            if ui
+                .add_enabled(self.alpha_inf_set, egui::Button::new("合并导出"))
+                .on_hover_text("选择多个结果文件，合并为一个工作簿（含 k / R² 汇总表），使用当前 α∞ 与拟合模式")
+                .on_disabled_hover_text("需要先设置 α∞（输入、估算或由动态运行带入）")
+                .clicked()
+            {
+                let tx = self.file_dialog_tx.clone();
+                thread::spawn(move || {
+                    let inputs = rfd::FileDialog::new()
+                        .add_filter("结果文件", &["xlsx", "csv"])
+                        .pick_files()
+                        .unwrap_or_default();
+                    let output = if inputs.is_empty() {
+                        None
+                    } else {
+                        rfd::FileDialog::new()
+                            .add_filter("Excel", &["xlsx"])
+                            .set_file_name("合并结果.xlsx")
+                            .save_file()
+                    };
+                    match output {
+                        Some(output) => {
+                            tx.send(Some(FileDialogResult::ExportMergedRuns { inputs, output }))
+                                .ok();
+                        }
+                        None => {
+                            tx.send(None).ok();
+                        }
+                    }
+                });
+            }
             ui.add_enabled_ui(!self.raw_plot_data.is_empty(), |ui| {
                 ui.label("α∞:");
                 if ui
                     .add(DragValue::new(&mut self.alpha_inf).suffix("°").speed(0.05))
                     .changed()
                 {
+                    self.alpha_inf_set = true;
                     self.cmd_tx
                         .send(Command::DataProcessing(
                             DataProcessingCommand::SetAlphaInf {
@@ -1610,6 +3192,17 @@ impl PolarimeterApp {
                         ))
                         .unwrap();
                 }
+                if ui
+                    .button("估算α∞")
+                    .on_hover_text("按一级反应 α(t) = α∞ + (α0 − α∞)e^(−kt) 拟合参与拟合的点，估算 α∞ 并直接采用")
+                    .clicked()
+                {
+                    self.cmd_tx
+                        .send(Command::DataProcessing(DataProcessingCommand::EstimateAlphaInf))
+                        .unwrap();
+                }
+                ui.checkbox(&mut self.auto_alpha_inf, "自动带入")
+                    .on_hover_text("从动态测量页切换过来时，以已完成运行的最后一个点的角度作为 α∞");
 
                 // --- MODIFIED: Send command on change ---
                 let old_mode = self.regression_mode;
@@ -1651,6 +3244,42 @@ impl PolarimeterApp {
                         ))
                         .unwrap();
                 }
+
+                if ui
+                    .checkbox(&mut self.exclude_trend_reversals, "剔除逆趋势点")
+                    .on_hover_text("旋光角逆着样品旋光方向的预期趋势跳变时，视为误识别或打滑，不参与拟合")
+                    .changed()
+                {
+                    self.cmd_tx
+                        .send(Command::DataProcessing(
+                            DataProcessingCommand::SetExcludeTrendReversals(
+                                self.exclude_trend_reversals,
+                            ),
+                        ))
+                        .unwrap();
+                }
+
+                if ui
+                    .add_enabled(!self.raw_plot_data.is_empty(), egui::Button::new("重新拟合"))
+                    .on_hover_text("不改变 α∞ 与拟合模式，按当前数据重新计算")
+                    .clicked()
+                {
+                    self.cmd_tx
+                        .send(Command::DataProcessing(DataProcessingCommand::Recalculate))
+                        .unwrap();
+                }
+
+                if ui
+                    .add_enabled(
+                        !self.regression_summary.is_empty(),
+                        egui::Button::new("复制结果"),
+                    )
+                    .on_hover_text("以纯文本复制斜率、截距、k、t½、R² 等，每行一项")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = self.regression_summary.clone());
+                    self.push_toast("拟合结果已复制到剪贴板".to_string(), false);
+                }
             });
         });
         ui.add_space(10.0);
@@ -1716,11 +3345,88 @@ impl PolarimeterApp {
             });
     }
 
+    /// 把当前图中变换后的散点、拟合直线端点和回归方程整理为 CSV
+    fn plot_data_csv(&self) -> String {
+        let mode = match self.regression_mode {
+            RegressionMode::Linear => "Δα - t",
+            RegressionMode::Log => "lnΔα - t",
+            RegressionMode::Inverse => "1/Δα - t",
+        };
+        let mut csv = format!("# 拟合模式: {}\n# α∞: {}\n", mode, self.alpha_inf);
+        for line in self.regression_formula.lines() {
+            csv.push_str(&format!("# {}\n", line));
+        }
+        csv.push_str("series,t,y\n");
+        for (t, y) in &self.plot_scatter_points {
+            csv.push_str(&format!("scatter,{},{}\n", t, y));
+        }
+        for (t, y) in &self.plot_line_points {
+            csv.push_str(&format!("fit,{},{}\n", t, y));
+        }
+        csv
+    }
+
+    /// 供 Origin / Excel 直接作图的两列 CSV：表头 + 当前拟合模式下变换后的有效点
+    fn two_column_csv(&self) -> String {
+        let y = match self.regression_mode {
+            RegressionMode::Linear => "Δα",
+            RegressionMode::Log => "lnΔα",
+            RegressionMode::Inverse => "1/Δα",
+        };
+        let mut csv = format!("t,{}\n", y);
+        for (t, y) in &self.plot_scatter_points {
+            csv.push_str(&format!("{},{}\n", t, y));
+        }
+        csv
+    }
+
     fn ui_data_processing_plot(&mut self, ui: &mut Ui) {
         egui::TopBottomPanel::top("data_plot_top_panel")
             // .frame(egui::Frame::none())
             .show_inside(ui, |ui| {
-                ui.heading("回归结果");
+                ui.horizontal(|ui| {
+                    ui.heading("回归结果");
+                    if ui
+                        .add_enabled(
+                            !self.plot_scatter_points.is_empty(),
+                            egui::Button::new("导出绘图数据"),
+                        )
+                        .clicked()
+                    {
+                        let tx = self.file_dialog_tx.clone();
+                        thread::spawn(move || {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("CSV", &["csv"])
+                                .save_file()
+                            {
+                                tx.send(Some(FileDialogResult::ExportPlotData(path))).ok();
+                            } else {
+                                tx.send(None).ok();
+                            }
+                        });
+                    }
+                    if ui
+                        .add_enabled(
+                            !self.plot_scatter_points.is_empty(),
+                            egui::Button::new("导出两列数据"),
+                        )
+                        .on_hover_text("仅导出当前拟合模式下的 t 与变换后的 y，已剔除无效点")
+                        .clicked()
+                    {
+                        let tx = self.file_dialog_tx.clone();
+                        thread::spawn(move || {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("CSV", &["csv"])
+                                .save_file()
+                            {
+                                tx.send(Some(FileDialogResult::ExportTwoColumn(path))).ok();
+                            } else {
+                                tx.send(None).ok();
+                            }
+                        });
+                    }
+                });
+                self.draw_plot_style_controls(ui);
                 ui.add_space(5.0);
                 ui.label("双击可居中数据");
                 ui.add_space(10.0);
@@ -1728,7 +3434,19 @@ impl PolarimeterApp {
         egui::TopBottomPanel::bottom("data_plot_bottom_panel")
             // .frame(egui::Frame::none())
             .show_inside(ui, |ui| {
-                ui.label(&self.regression_formula); // 在公式和图表之间添加一点间距，更美观
+                // R² 低于警戒线时标红，提醒在提交前检查数据
+                if self.r_squared < self.min_r2 {
+                    ui.label(RichText::new(&self.regression_formula).color(Color32::LIGHT_RED))
+                        .on_hover_text(format!(
+                            "R² 低于警戒线 {}，数据可能存在误识别、α∞ 不当或拟合模式不符",
+                            self.min_r2
+                        ));
+                } else {
+                    ui.label(&self.regression_formula);
+                }
+                if !self.regression_warning.is_empty() {
+                    ui.label(RichText::new(&self.regression_warning).color(Color32::from_rgb(230, 140, 0)));
+                }
             });
         egui::CentralPanel::default()
             // .frame(Frame::none()) // 中间区域本身不需要边框
@@ -1764,8 +3482,8 @@ impl PolarimeterApp {
                                     .collect::<Vec<[f64; 2]>>(),
                             ))
                             .name("原始数据")
-                            .shape(egui_plot::MarkerShape::Cross)
-                            .radius(5.0);
+                            .shape(self.plot_style.marker)
+                            .radius(self.plot_style.marker_radius);
 
                             plot_ui.points(points);
                         }
@@ -1780,6 +3498,10 @@ impl PolarimeterApp {
                                     .collect::<Vec<[f64; 2]>>(),
                             ))
                             .name("拟合直线");
+                            let line = match self.plot_style.line_color {
+                                Some(color) => line.color(color),
+                                None => line,
+                            };
 
                             plot_ui.line(line);
                         }
@@ -1787,10 +3509,732 @@ impl PolarimeterApp {
             });
     }
 
+    /// 散点标记形状、大小与拟合线颜色；修改后立即保存
+    fn draw_plot_style_controls(&mut self, ui: &mut Ui) {
+        let before = self.plot_style;
+        ui.horizontal(|ui| {
+            ui.label("标记:");
+            ComboBox::from_id_source("plot_marker_shape")
+                .selected_text(marker_label(self.plot_style.marker))
+                .show_ui(ui, |ui| {
+                    for shape in MarkerShape::all() {
+                        ui.selectable_value(&mut self.plot_style.marker, shape, marker_label(shape));
+                    }
+                });
+            ui.add(
+                DragValue::new(&mut self.plot_style.marker_radius)
+                    .clamp_range(1.0..=12.0)
+                    .speed(0.1)
+                    .prefix("大小 "),
+            );
+            ui.separator();
+            let mut custom = self.plot_style.line_color.is_some();
+            if ui.checkbox(&mut custom, "拟合线颜色").changed() {
+                self.plot_style.line_color = custom.then_some(Color32::from_rgb(200, 60, 60));
+            }
+            if let Some(color) = &mut self.plot_style.line_color {
+                ui.color_edit_button_srgba(color);
+            }
+            if ui.button("恢复默认").clicked() {
+                self.plot_style = PlotStyle::default();
+            }
+            ui.separator();
+            let min_r2_changed = ui
+                .add(
+                    DragValue::new(&mut self.min_r2)
+                        .clamp_range(0.0..=1.0)
+                        .speed(0.001)
+                        .max_decimals(4)
+                        .prefix("R² 警戒线 "),
+                )
+                .on_hover_text("拟合的 R² 低于此值时回归方程显示为红色，提示数据可能有问题")
+                .changed();
+            if min_r2_changed {
+                let min_r2 = self.min_r2;
+                if let Err(e) = AppSettings::update(|settings| settings.min_r2 = min_r2) {
+                    self.push_toast(format!("保存 R² 警戒线失败：{}", e), true);
+                }
+            }
+        });
+        if self.plot_style != before {
+            let style = self.plot_style;
+            if let Err(e) = AppSettings::update(|settings| settings.plot_style = style) {
+                self.push_toast(format!("保存绘图样式失败：{}", e), true);
+            }
+        }
+    }
+
     // ===================================================================================
     //  独立的模型评估结果窗口 (基本不变)
     // ===================================================================================
 
+    /// 设备页和静态测量页中，没有文本框获得焦点时用 ←/→ 微调电机，Shift 加大步长
+    fn handle_jog_keys(&mut self, ctx: &egui::Context) {
+        if !matches!(self.active_tab, Tab::DeviceControl | Tab::StaticMeasurement)
+            || ctx.wants_keyboard_input()
+            || !self.is_serial_connected
+            || self.rotation
+            || self.is_static_running
+            || self.is_dynamic_exp_running
+            || self.step_calibration.is_some()
+        {
+            return;
+        }
+        let (left, right, shift) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowLeft),
+                i.key_pressed(egui::Key::ArrowRight),
+                i.modifiers.shift,
+            )
+        });
+        let direction = match (left, right) {
+            (true, false) => -1,
+            (false, true) => 1,
+            _ => return,
+        };
+        let step = if shift {
+            self.jog_large_steps
+        } else {
+            self.jog_small_steps
+        };
+        self.cmd_tx
+            .send(Command::Device(DeviceCommand::RotateMotor {
+                steps: direction * step,
+            }))
+            .unwrap();
+    }
+
+    /// 静态测量页按 Enter 运行一次精细测量，便于连续采集多组读数
+    fn handle_measure_hotkey(&mut self, ctx: &egui::Context) {
+        let ready = self.is_camera_connected
+            && self.is_serial_connected
+            && self.is_model_ready
+            && self.current_angle.is_some()
+            && !self.rotation
+            && !self.is_static_running
+            && !self.is_dynamic_exp_running;
+        // 有控件获得焦点时 Enter 属于该控件（如确认输入或点击按钮）
+        if self.active_tab != Tab::StaticMeasurement
+            || !ready
+            || ctx.wants_keyboard_input()
+            || ctx.memory(|m| m.focused().is_some())
+        {
+            return;
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
+            self.cmd_tx
+                .send(Command::StaticMeasure(StaticMeasureCommand::RunSingleMeasurement {
+                    time: self.static_times,
+                }))
+                .unwrap();
+        }
+    }
+
+    fn draw_jog_settings(&mut self, ui: &mut Ui) {
+        // 单次微调最多 1°，Shift 最多 10°，按当前标定换算成步数
+        let one_degree = self.anglesteps.round().max(1.0) as i32;
+        ui.horizontal(|ui| {
+            ui.label("方向键微调: ←/→");
+            ui.add(
+                egui::DragValue::new(&mut self.jog_small_steps)
+                    .speed(1)
+                    .suffix("步")
+                    .clamp_range(1..=one_degree),
+            );
+            ui.label("Shift+←/→");
+            ui.add(
+                egui::DragValue::new(&mut self.jog_large_steps)
+                    .speed(1)
+                    .suffix("步")
+                    .clamp_range(1..=one_degree * 10),
+            );
+            ui.weak(format!("（≈{:.3}°）", self.jog_small_steps as f32 / self.anglesteps))
+                .on_hover_text("单按一次方向键转动的角度；在文本框中输入时方向键不会转动电机");
+        });
+    }
+
+    /// 动态运行结束后，把最后一个测量点的角度带入数据处理的 α∞
+    fn carry_alpha_inf_from_dynamic(&mut self) {
+        if !self.auto_alpha_inf || self.is_dynamic_exp_running {
+            return;
+        }
+        let Some(last) = self.dynamic_results.last() else {
+            return;
+        };
+        let key = (self.dynamic_run_id.clone(), last.index);
+        if self.carried_alpha_inf.as_ref() == Some(&key) {
+            return;
+        }
+        let alpha = last.angle as f64;
+        let index = last.index;
+        self.carried_alpha_inf = Some(key);
+        self.alpha_inf = alpha;
+        self.alpha_inf_set = true;
+        self.cmd_tx
+            .send(Command::DataProcessing(DataProcessingCommand::SetAlphaInf { alpha }))
+            .unwrap();
+        self.push_toast(
+            format!("已将动态测量第 {} 个点的角度 {:.3}° 设为 α∞", index, alpha),
+            false,
+        );
+    }
+
+    /// 各项操作的先决条件及其是否满足，与对应按钮的启用条件保持一致
+    fn action_readiness(&self) -> [(&'static str, Vec<(&'static str, bool)>); 3] {
+        let devices = [
+            ("串口电机", self.is_serial_connected),
+            ("相机", self.is_camera_connected),
+            ("识别模型", self.is_model_ready),
+        ];
+        let with = |extra: &[(&'static str, bool)]| {
+            devices.iter().chain(extra).copied().collect::<Vec<_>>()
+        };
+        [
+            ("寻找零点", with(&[("无静态测量进行", !self.is_static_running)])),
+            (
+                "静态测量",
+                with(&[
+                    ("已确立零点", self.current_angle.is_some()),
+                    ("无动态测量进行", !self.is_dynamic_exp_running),
+                ]),
+            ),
+            ("动态测量", self.tracking_prerequisites()),
+        ]
+    }
+
+    /// 开始动态跟踪的先决条件，“开始跟踪”按钮按它启用
+    fn tracking_prerequisites(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            ("串口电机", self.is_serial_connected),
+            ("相机", self.is_camera_connected),
+            ("识别模型", self.is_model_ready),
+            ("已确立零点", self.current_angle.is_some()),
+            ("无静态测量进行", !self.is_static_running),
+            // 开始计时时保存路径已交给后端，界面上的路径随之清空
+            ("已选择保存路径", self.dynamic_save_path.is_some() || self.start_time.is_some()),
+            ("已开始计时", self.start_time.is_some()),
+        ]
+    }
+
+    /// 每项操作一行：可用时打勾，不可用时列出缺少的条件；悬停查看全部条件
+    fn draw_action_readiness(&self, ui: &mut Ui) {
+        for (action, prerequisites) in self.action_readiness() {
+            let missing: Vec<&str> = prerequisites
+                .iter()
+                .filter(|(_, ok)| !ok)
+                .map(|(name, _)| *name)
+                .collect();
+            let text = if missing.is_empty() {
+                RichText::new(format!("✅ {}: 可以开始", action)).color(Color32::GREEN)
+            } else {
+                RichText::new(format!("⛔ {}: 缺少 {}", action, missing.join("、")))
+                    .color(Color32::from_rgb(230, 140, 0))
+            };
+            let details = prerequisites
+                .iter()
+                .map(|(name, ok)| format!("{} {}", if *ok { "✅" } else { "❌" }, name))
+                .collect::<Vec<_>>()
+                .join("\n");
+            ui.label(text).on_hover_text(details);
+        }
+    }
+
+    /// 资源占用与各缓冲区大小，长时间运行时用于发现内存只增不减的情况
+    fn draw_diagnostics(&self, ui: &mut Ui) {
+        egui::CollapsingHeader::new("运行诊断")
+            .default_open(false)
+            .show(ui, |ui| {
+                let Some(d) = &self.diagnostics else {
+                    ui.weak("等待后端统计…");
+                    return;
+                };
+                let mb = |bytes: f64| bytes / (1024.0 * 1024.0);
+                match d.memory_bytes {
+                    Some(bytes) => ui.label(format!("进程内存: {:.1} MB", mb(bytes as f64))),
+                    None => ui.weak("进程内存: 无法获取"),
+                };
+                if let Some(cpu) = d.cpu_percent {
+                    ui.label(format!("CPU 占用: {:.1}%", cpu));
+                }
+                ui.label(format!(
+                    "训练图像: MAM {} 张 + AMA {} 张（{:.1} MB）",
+                    d.mam_images,
+                    d.ama_images,
+                    mb(d.image_bytes as f64)
+                ));
+                ui.label(format!(
+                    "动态结果: {} 点（界面 {} 点）",
+                    d.dynamic_results,
+                    self.dynamic_results.len()
+                ));
+                ui.label(format!(
+                    "数据处理: {} 点（界面 {} 点）",
+                    d.raw_data,
+                    self.raw_plot_data.len()
+                ));
+                ui.label(format!(
+                    "日志: {} 条，实验记录: {} 条",
+                    self.log_buffer.len(),
+                    self.experiment_log.len()
+                ));
+            });
+    }
+
+    /// 拖动控件的值稳定超过 SLIDER_DEBOUNCE 后才发送，避免拖动时刷屏式地发命令
+    fn flush_debounced_sliders(&mut self) {
+        if self.pending_exposure.is_some_and(|t| t.elapsed() >= SLIDER_DEBOUNCE) {
+            self.flush_exposure();
+        }
+        if self.pending_radius.is_some_and(|t| t.elapsed() >= SLIDER_DEBOUNCE) {
+            self.flush_radius();
+        }
+    }
+
+    /// 最近一段时间圆半径的波动；半径忽大忽小或经常找不到圆，说明对焦或振动有问题
+    fn draw_radius_stats(&self, ui: &mut Ui) {
+        if self.camera_lock_circle {
+            ui.weak("圆半径: 已锁定圆形位置");
+            return;
+        }
+        let Some(stats) = self.radius_stats else {
+            ui.weak("圆半径: --");
+            return;
+        };
+        let text = format!(
+            "圆半径: {} / {:.1} / {}（最小/平均/最大），稳定度 {:.0}%，检出 {}/{} 帧",
+            stats.min, stats.mean, stats.max, stats.stability, stats.detected, stats.frames
+        );
+        let unstable = stats.stability < RADIUS_STABILITY_WARN
+            || (stats.detected as f32) < stats.frames as f32 * RADIUS_DETECTION_WARN;
+        if unstable {
+            ui.label(RichText::new(text).color(Color32::from_rgb(230, 140, 0)))
+                .on_hover_text("圆检测不稳定，识别结果可能不可靠；请检查对焦、振动或半径范围");
+        } else {
+            ui.label(text);
+        }
+    }
+
+    /// 曝光扫描结果：亮度随曝光变化的曲线、目标区间与建议值
+    fn draw_exposure_sweep(&mut self, ui: &mut Ui) {
+        let Some(sweep) = &self.exposure_sweep else {
+            return;
+        };
+        let (low, high) = sweep.target;
+        Plot::new("exposure_sweep_plot")
+            .height(120.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .x_axis_label("曝光")
+            .y_axis_label("亮度")
+            .show(ui, |plot_ui| {
+                plot_ui.hline(egui_plot::HLine::new(low).color(Color32::GRAY));
+                plot_ui.hline(egui_plot::HLine::new(high).color(Color32::GRAY));
+                let points: Vec<[f64; 2]> = sweep.points.iter().map(|&(e, b)| [e, b]).collect();
+                plot_ui.line(Line::new(PlotPoints::from(points.clone())));
+                plot_ui.points(Points::new(PlotPoints::from(points)).radius(3.0));
+            });
+        let suggested = sweep.suggested;
+        ui.horizontal(|ui| {
+            match suggested {
+                Some(value) => {
+                    ui.label(format!("建议曝光: {}（目标亮度 {:.0}~{:.0}）", value, low, high));
+                    if ui.button("采用").clicked() {
+                        self.exposure = value;
+                        self.pending_exposure = Some(std::time::Instant::now());
+                        self.flush_exposure();
+                    }
+                }
+                None => {
+                    ui.weak("没有可用的建议值");
+                }
+            }
+            if ui.button("关闭").clicked() {
+                self.exposure_sweep = None;
+            }
+        });
+    }
+
+    /// 退出时写入设置的设备参数与主题，其余字段保持文件中的值
+    fn store_settings(&self, settings: &mut AppSettings) {
+        settings.exposure = self.exposure;
+        settings.min_radius = self.min_radius;
+        settings.max_radius = self.max_radius;
+        settings.lock_circle = self.camera_lock_circle;
+        settings.rotation_direction_is_ama = self.rotation_direction_is_ama;
+        settings.rotation_direction_need_reverse = self.rotation_direction_reverse;
+        settings.steps_per_degree = self.anglesteps;
+        settings.theme = self.theme;
+    }
+
+    fn flush_exposure(&mut self) {
+        if self.pending_exposure.take().is_some() {
+            self.cmd_tx
+                .send(Command::Camera(CameraCommand::Exposure(self.exposure)))
+                .unwrap();
+        }
+    }
+
+    fn flush_radius(&mut self) {
+        if self.pending_radius.take().is_some() {
+            self.cmd_tx
+                .send(Command::Camera(CameraCommand::SetHoughCircleRadius {
+                    min: self.min_radius,
+                    max: self.max_radius,
+                }))
+                .unwrap();
+        }
+    }
+
+    fn push_toast(&mut self, message: String, is_error: bool) {
+        self.toasts.push_back(Toast {
+            message,
+            is_error,
+            created: std::time::Instant::now(),
+        });
+        while self.toasts.len() > MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+    }
+
+    /// 在窗口右下角绘制自动消失的通知气泡
+    fn show_toasts(&mut self, ctx: &egui::Context) {
+        self.toasts.retain(|t| t.created.elapsed() < TOAST_DURATION);
+        if self.toasts.is_empty() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("toast_area"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, Vec2::new(-10.0, -10.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                for toast in &self.toasts {
+                    // 最后一秒逐渐淡出
+                    let remaining = TOAST_DURATION.saturating_sub(toast.created.elapsed()).as_secs_f32();
+                    let alpha = remaining.min(1.0);
+                    let color = if toast.is_error {
+                        Color32::from_rgb(255, 80, 80)
+                    } else {
+                        Color32::from_rgb(0, 192, 255)
+                    };
+                    Frame::popup(ui.style())
+                        .multiply_with_opacity(alpha)
+                        .show(ui, |ui| {
+                            ui.set_max_width(320.0);
+                            ui.label(RichText::new(&toast.message).color(color));
+                        });
+                }
+            });
+    }
+
+    fn show_exit_confirm_window(&mut self, ctx: &egui::Context) {
+        if !self.show_exit_confirm {
+            return;
+        }
+        egui::Window::new("退出确认")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                let unsaved = match (self.static_results_dirty, self.dynamic_results_dirty) {
+                    (true, true) => "静态与动态测量结果",
+                    (false, true) => "动态测量结果",
+                    _ => "静态测量结果",
+                };
+                ui.label(format!("{}尚未保存，确定要退出吗？", unsaved));
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("先保存").clicked() {
+                        self.show_exit_confirm = false;
+                        self.exit_after_save = true;
+                        let save_static = self.static_results_dirty;
+                        let save_dynamic = self.dynamic_results_dirty;
+                        let tx = self.file_dialog_tx.clone();
+                        thread::spawn(move || {
+                            if save_static {
+                                match rfd::FileDialog::new()
+                                    .set_title("保存静态测量结果")
+                                    .add_filter("Excel", &["xlsx"])
+                                    .add_filter("CSV", &["csv"])
+                                    .save_file()
+                                {
+                                    Some(path) => {
+                                        tx.send(Some(FileDialogResult::SaveStaticResults(path)))
+                                            .ok();
+                                    }
+                                    None => {
+                                        tx.send(None).ok();
+                                        return;
+                                    }
+                                }
+                            }
+                            // 动态结果的自动保存失败时才会走到这里，另存为 CSV
+                            if save_dynamic {
+                                match rfd::FileDialog::new()
+                                    .set_title("保存动态测量结果")
+                                    .add_filter("CSV", &["csv"])
+                                    .save_file()
+                                {
+                                    Some(path) => {
+                                        tx.send(Some(FileDialogResult::SaveDynamicCsv(path))).ok();
+                                    }
+                                    None => {
+                                        tx.send(None).ok();
+                                    }
+                                }
+                            }
+                        });
+                    }
+                    if ui.button("不保存并退出").clicked() {
+                        self.show_exit_confirm = false;
+                        self.allow_close = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                    if ui.button("取消").clicked() {
+                        self.show_exit_confirm = false;
+                    }
+                });
+            });
+    }
+
+    /// 每度步数标定：转动已知步数，由用户输入刻度盘读出的实际角度，算出实际的步数/度
+    fn show_step_calibration_window(&mut self, ctx: &egui::Context) {
+        let Some(calib) = &mut self.step_calibration else {
+            return;
+        };
+        let mut close = false;
+        // 标定时转动 1° ~ 40°（按默认传动比），当前标定可能本身就不准，不用它换算
+        let steps_range = (DEFAULT_STEPS_PER_DEGREE.round() as i32)..=((DEFAULT_STEPS_PER_DEGREE * 40.0).round() as i32);
+        let can_rotate = self.is_serial_connected
+            && !self.rotation
+            && !self.is_static_running
+            && !self.is_dynamic_exp_running;
+        egui::Window::new("每度步数标定")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("1. 记下刻度盘当前读数，然后转动已知步数");
+                ui.horizontal(|ui| {
+                    ui.label("转动步数:");
+                    ui.add_enabled(
+                        !calib.rotated,
+                        egui::DragValue::new(&mut calib.steps)
+                            .speed(10)
+                            .suffix("步")
+                            .clamp_range(steps_range),
+                    );
+                    if ui
+                        .add_enabled(can_rotate && !calib.rotated, egui::Button::new("旋转"))
+                        .clicked()
+                    {
+                        self.cmd_tx
+                            .send(Command::Device(DeviceCommand::RotateMotor {
+                                steps: calib.steps,
+                            }))
+                            .unwrap();
+                        calib.rotated = true;
+                    }
+                });
+                ui.add_space(5.0);
+                ui.add_enabled_ui(calib.rotated && !self.rotation, |ui| {
+                    ui.label("2. 输入刻度盘读出的实际转角");
+                    ui.add(
+                        egui::DragValue::new(&mut calib.measured_angle)
+                            .speed(0.05)
+                            .suffix("°")
+                            .clamp_range(0.0..=360.0),
+                    );
+                });
+                let measured = (calib.rotated && calib.measured_angle > 0.0)
+                    .then(|| calib.steps as f32 / calib.measured_angle);
+                ui.add_space(5.0);
+                match measured {
+                    Some(value) => ui.label(format!(
+                        "实测 1° = {:.1} 步（当前 {:.1} 步，偏差 {:+.2}%）",
+                        value,
+                        self.anglesteps,
+                        (value / self.anglesteps - 1.0) * 100.0
+                    )),
+                    None => ui.weak("实测 1° = -- 步"),
+                };
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            measured.is_some_and(|v| is_valid_steps_per_degree(v) && v <= 2000.0),
+                            egui::Button::new("保存"),
+                        )
+                        .clicked()
+                    {
+                        if let Some(value) = measured {
+                            self.anglesteps = value;
+                            self.cmd_tx
+                                .send(Command::Device(DeviceCommand::SetStep(value)))
+                                .unwrap();
+                            self.status_message = format!("每度步数已标定为 {:.1}", value);
+                        }
+                        close = true;
+                    }
+                    if ui
+                        .add_enabled(can_rotate && calib.rotated, egui::Button::new("转回原位"))
+                        .on_hover_text("反向转动相同步数")
+                        .clicked()
+                    {
+                        self.cmd_tx
+                            .send(Command::Device(DeviceCommand::RotateMotor {
+                                steps: -calib.steps,
+                            }))
+                            .unwrap();
+                        calib.rotated = false;
+                        calib.measured_angle = 0.0;
+                    }
+                    if ui.button("取消").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        if close {
+            self.step_calibration = None;
+        }
+    }
+
+    fn show_sheet_picker_window(&mut self, ctx: &egui::Context) {
+        let Some(picker) = &mut self.sheet_picker else {
+            return;
+        };
+        let mut close = false;
+        egui::Window::new("选择工作表")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} 包含 {} 张工作表，请选择要导入的一张：",
+                    picker
+                        .path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    picker.sheets.len()
+                ));
+                ui.add_space(5.0);
+                for (i, name) in picker.sheets.iter().enumerate() {
+                    ui.radio_value(&mut picker.selected, i, name);
+                }
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("导入").clicked() {
+                        self.cmd_tx
+                            .send(Command::DataProcessing(DataProcessingCommand::LoadData {
+                                path: picker.path.clone(),
+                                sheet: Some(picker.sheets[picker.selected].clone()),
+                            }))
+                            .unwrap();
+                        close = true;
+                    }
+                    if ui.button("取消").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        if close {
+            self.sheet_picker = None;
+        }
+    }
+
+    /// 训练后的识别自检：持续识别实时画面但不转动电机，遮挡或放开光路时观察结果是否随之变化
+    fn draw_recognition_test(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            let enabled = self.is_model_ready && self.is_camera_connected;
+            let response = ui
+                .add_enabled(
+                    enabled,
+                    egui::SelectableLabel::new(self.show_prediction_preview, "测试识别"),
+                )
+                .on_hover_text("持续识别实时画面并显示结果，不转动电机；可遮挡/放开光路检验模型")
+                .on_disabled_hover_text("需要已训练的模型和已连接的相机");
+            if response.clicked() {
+                self.show_prediction_preview = !self.show_prediction_preview;
+                self.cmd_tx
+                    .send(Command::Camera(CameraCommand::SetPredictionPreview(
+                        self.show_prediction_preview,
+                    )))
+                    .unwrap();
+            }
+            if self.show_prediction_preview {
+                match self.prediction_preview {
+                    Some((class, confidence)) => {
+                        let (label, color) = if class == 0 {
+                            ("MAM", Color32::from_rgb(80, 140, 230))
+                        } else {
+                            ("AMA", Color32::from_rgb(230, 140, 0))
+                        };
+                        ui.label(RichText::new(label).strong().size(18.0).color(color));
+                        ui.label(format!("置信度 {:.3}", confidence));
+                    }
+                    None => {
+                        ui.weak("等待画面…");
+                    }
+                }
+            }
+        });
+    }
+
+    fn show_export_layout_window(&mut self, ctx: &egui::Context) {
+        let mut layout = self.export_layout.clone();
+        egui::Window::new("导出列设置")
+            .open(&mut self.is_export_layout_open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("勾选要写入 xlsx 结果文件的列，用箭头调整顺序：");
+                ui.add_space(5.0);
+                let count = layout.columns.len();
+                let mut swap = None;
+                for i in 0..count {
+                    ui.horizontal(|ui| {
+                        let (column, on) = &mut layout.columns[i];
+                        ui.checkbox(on, column.label());
+                        if ui.add_enabled(i > 0, egui::Button::new("↑")).clicked() {
+                            swap = Some((i - 1, i));
+                        }
+                        if ui.add_enabled(i + 1 < count, egui::Button::new("↓")).clicked() {
+                            swap = Some((i, i + 1));
+                        }
+                    });
+                }
+                if let Some((a, b)) = swap {
+                    layout.columns.swap(a, b);
+                }
+                ui.checkbox(&mut layout.include_params, "写入实验参数与每度步数（F/G 列）");
+                ui.add_space(5.0);
+                ui.weak("静态结果不写入时间列；有效列写入时全部为 1，可在 Excel 中改为 0 以剔除坏点");
+                let importable = layout.enabled().any(|c| c == ExportColumn::Time)
+                    && layout
+                        .enabled()
+                        .any(|c| matches!(c, ExportColumn::Steps | ExportColumn::Angle));
+                if !importable {
+                    ui.colored_label(
+                        Color32::from_rgb(230, 140, 0),
+                        "未包含时间以及步数或角度，动态结果将无法再导入数据处理",
+                    );
+                }
+                if ui.button("恢复默认").clicked() {
+                    layout = ExportLayout::default();
+                }
+            });
+        if layout != self.export_layout && !layout.has_data_column() {
+            // 取消最后一个数据列时保留原布局
+            self.push_toast("至少需要保留时间、步数、角度中的一列".to_string(), true);
+        } else if layout != self.export_layout {
+            self.cmd_tx
+                .send(Command::General(GeneralCommand::SetExportLayout(layout.clone())))
+                .unwrap();
+            self.export_layout = layout;
+        }
+    }
+
     fn show_doc_window(&mut self, ctx: &egui::Context) {
         // 这个窗口由后端数据驱动，当有新结果时 is_plots_window_open 会被设为 true
         egui::Window::new("文档")
@@ -1805,10 +4249,22 @@ impl PolarimeterApp {
             });
     }
 }
+/// 根据“正值对应”和“旋转方向”两项设置，给出正角度转动时的实际效果
+/// 正值对应 MAM：零点正侧视场为明暗明，向正角度转过零点时中间条带由明变暗
+fn rotation_direction_summary(is_ama: bool, reverse: bool) -> String {
+    let motion = if reverse { "逆时针" } else { "顺时针" };
+    let field = if is_ama {
+        "中间 暗→明 (MAM→AMA)"
+    } else {
+        "中间 明→暗 (AMA→MAM)"
+    };
+    format!("正角度 → {}，{}", motion, field)
+}
+
 /// 这是一个兼容旧版 egui 的辅助函数，
 /// 它使用 horizontal 布局来将多个 RichText 放在同一行。
 fn draw_log_message(ui: &mut Ui, log: &LogMessage) {
-    let (level_str, color) = level_to_style(log.level);
+    let (level_str, color) = level_to_style(log.level, ui.visuals().dark_mode);
 
     let layout_response = ui
         .horizontal_wrapped(|ui| {
@@ -1831,7 +4287,11 @@ fn draw_log_message(ui: &mut Ui, log: &LogMessage) {
             ui.label(RichText::new(&log.message).monospace());
             ui.label(
                 RichText::new(format!("({})", &log.target))
-                    .color(Color32::from_rgb(100, 160, 180)) // 柔和的青色
+                    .color(if ui.visuals().dark_mode {
+                        Color32::from_rgb(100, 160, 180) // 柔和的青色
+                    } else {
+                        Color32::from_rgb(40, 110, 130)
+                    })
                     .monospace(),
             );
         })
@@ -1845,13 +4305,18 @@ fn draw_log_message(ui: &mut Ui, log: &LogMessage) {
     ));
 }
 
-// level_to_style 函数保持不变
-fn level_to_style(level: Level) -> (&'static str, Color32) {
-    match level {
-        Level::ERROR => ("ERROR", Color32::from_rgb(255, 80, 80)),
-        Level::WARN => ("WARN", Color32::from_rgb(255, 215, 0)),
-        Level::INFO => ("INFO", Color32::from_rgb(0, 192, 255)),
-        Level::DEBUG => ("DEBUG", Color32::from_rgb(128, 128, 128)),
-        Level::TRACE => ("TRACE", Color32::from_rgb(150, 100, 200)),
+// 浅色背景上改用更深的颜色，保证各级别都看得清
+fn level_to_style(level: Level, dark: bool) -> (&'static str, Color32) {
+    match (level, dark) {
+        (Level::ERROR, true) => ("ERROR", Color32::from_rgb(255, 80, 80)),
+        (Level::ERROR, false) => ("ERROR", Color32::from_rgb(200, 30, 30)),
+        (Level::WARN, true) => ("WARN", Color32::from_rgb(255, 215, 0)),
+        (Level::WARN, false) => ("WARN", Color32::from_rgb(170, 110, 0)),
+        (Level::INFO, true) => ("INFO", Color32::from_rgb(0, 192, 255)),
+        (Level::INFO, false) => ("INFO", Color32::from_rgb(0, 100, 190)),
+        (Level::DEBUG, true) => ("DEBUG", Color32::from_rgb(128, 128, 128)),
+        (Level::DEBUG, false) => ("DEBUG", Color32::from_rgb(95, 95, 95)),
+        (Level::TRACE, true) => ("TRACE", Color32::from_rgb(150, 100, 200)),
+        (Level::TRACE, false) => ("TRACE", Color32::from_rgb(110, 60, 160)),
     }
 }
@@ -1,12 +1,32 @@
 use super::{Arc, BackendState, Mutex};
-use crate::communication::{DeviceUpdate, Update};
+use crate::communication::{DeviceUpdate, ExposureSweep, RadiusStats, Update};
 use anyhow::{Error, Result};
 use crossbeam_channel::Sender;
 use opencv::{core, imgproc, prelude::*, videoio};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::thread;
 use std::time::{Duration, Instant};
-const TARGET_FRAME_DURATION: Duration = Duration::from_millis(33);
+// 采集帧率的默认值与允许范围；慢机器上可调低以减少 CPU 占用
+pub const DEFAULT_CAPTURE_FPS: f32 = 30.0;
+pub const MIN_CAPTURE_FPS: f32 = 1.0;
+pub const MAX_CAPTURE_FPS: f32 = 120.0;
+// 半径自动标定时采集的帧数
+const CALIBRATION_FRAMES: usize = 15;
+// 暂停采集时检查是否恢复的间隔
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// 新打开的相机须在此时间内送出第一帧，否则视为连接失败
+const FIRST_FRAME_TIMEOUT: Duration = Duration::from_secs(3);
+// 曝光扫描的起点、终点与步长，与界面上曝光的取值方式一致
+const EXPOSURE_SWEEP: (f64, f64, f64) = (-10.0, 0.0, 1.0);
+// 每次改变曝光后等待相机生效的时间，以及每个曝光值取平均的帧数
+const EXPOSURE_SETTLE: Duration = Duration::from_millis(400);
+const EXPOSURE_SAMPLES: usize = 3;
+// 圆半径统计的窗口帧数与推送间隔（帧）
+const RADIUS_WINDOW: usize = 60;
+const RADIUS_REPORT_EVERY: usize = 15;
+// 识别区域平均亮度 (0~255) 的目标区间，亮度居中时 MAM/AMA 的明暗对比最好
+const BRIGHTNESS_TARGET: (f64, f64) = (90.0, 150.0);
 use tracing::{error, info};
 
 // #[cfg(target_os = "macos")]
@@ -80,7 +100,7 @@ pub fn set_camera_exposure(
     _camera_index: i32, // _camera_index 在非 macOS 上未使用
     exposure_value: f64,
     cam: &mut videoio::VideoCapture,
-) -> Result<()> {
+) -> Result<f64> {
     // 禁用自动曝光
     if cam.set(videoio::CAP_PROP_AUTO_EXPOSURE, 0.0).is_err() {
         tracing::warn!("无法禁用自动曝光");
@@ -90,8 +110,9 @@ pub fn set_camera_exposure(
         // 使用 anyhow::bail! 来创建一个错误并返回
         anyhow::bail!("通过 OpenCV 设置曝光失败");
     }
-    // tracing::info!("爆");
-    Ok(())
+    // 很多摄像头会截断或忽略曝光值，读回驱动实际生效的值
+    let applied = cam.get(videoio::CAP_PROP_EXPOSURE)?;
+    Ok(applied)
 }
 
 #[derive(Clone, Debug, Default)]
@@ -101,12 +122,29 @@ pub struct CameraSettings {
     pub locked_circle: Option<(i32, i32, i32)>,
     pub min_radius: i32,
     pub max_radius: i32,
+    pub capture_fps: f32, // 采集线程的目标帧率
+    pub paused: bool,     // 暂停采集：相机保持打开，但不读帧也不推送画面
+}
+
+impl CameraSettings {
+    /// 采集循环每帧的目标间隔
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.capture_fps.clamp(MIN_CAPTURE_FPS, MAX_CAPTURE_FPS))
+    }
 }
 
 pub struct CameraManager {
     thread_handle: Option<thread::JoinHandle<()>>,
     stop_signal: Arc<AtomicBool>,
     pub latest_frame: Arc<Mutex<Option<Mat>>>,
+    // 是否为驱动识别的主相机；只有主相机会回写锁定的圆形位置
+    is_primary: Arc<AtomicBool>,
+}
+
+impl CameraManager {
+    pub fn set_primary(&self, primary: bool) {
+        self.is_primary.store(primary, Ordering::Relaxed);
+    }
 }
 
 impl CameraManager {
@@ -118,44 +156,56 @@ impl CameraManager {
         let stop_signal = Arc::new(AtomicBool::new(false));
         let thread_stop_signal = stop_signal.clone();
         let latest_frame = Arc::new(Mutex::new(None));
+        let is_primary = Arc::new(AtomicBool::new(false));
+        let thread_is_primary = is_primary.clone();
+
+        // 在启动捕获线程前先打开相机，打开失败时直接返回错误，不留下半初始化的 manager
+        let mut cam = videoio::VideoCapture::new(camera_index, videoio::CAP_ANY)
+            .map_err(|e| anyhow::anyhow!("无法创建相机 {} 的捕获对象：{}", camera_index, e))?;
+        if !cam.is_opened().unwrap_or(false) {
+            anyhow::bail!("相机 {} 无法打开，可能已被其他程序占用", camera_index);
+        }
+        info!("相机 {} 已成功打开", camera_index);
 
         let thread_handle = {
             let thread_latest_frame = latest_frame.clone();
             thread::spawn(move || {
-                let mut cam = match videoio::VideoCapture::new(camera_index, videoio::CAP_ANY) {
-                    Ok(cam) => {
-                        if !cam.is_opened().unwrap_or(false) {
-                            error!("无法打开相机索引 {}", camera_index);
-                            let _ = update_tx
-                                .send(Update::Device(DeviceUpdate::CameraConnectionStatus(false)));
-                            return;
-                        }
-                        info!("相机 {} 已成功在捕获线程中打开", camera_index);
-                        let _ = update_tx
-                            .send(Update::Device(DeviceUpdate::CameraConnectionStatus(true)));
-                        cam
-                    }
-                    Err(e) => {
-                        error!("后端：创建VideoCapture失败：{}", e);
-                        let _ = update_tx
-                            .send(Update::Device(DeviceUpdate::CameraConnectionStatus(false)));
-                        return;
-                    }
-                };
-                
                 let mut expo_old = f64::NAN;
+                let mut was_primary = false;
+                let mut radius_history = RadiusHistory::default();
                 // let mut consecutive_read_errors = 0;
                 while !thread_stop_signal.load(Ordering::Relaxed) {
                     let mut frame = Mat::default();
                     let start_time = Instant::now();
-                    let expo = { settings.lock().exposure };
+                    let (expo, frame_duration, paused) = {
+                        let settings = settings.lock();
+                        (settings.exposure, settings.frame_duration(), settings.paused)
+                    };
+                    if paused {
+                        thread::sleep(PAUSED_POLL_INTERVAL);
+                        continue;
+                    }
+                    let primary = thread_is_primary.load(Ordering::Relaxed);
+                    if primary && !was_primary {
+                        // 刚成为主相机时重新设置一次，让前端显示这一路的实际曝光
+                        expo_old = f64::NAN;
+                    }
+                    was_primary = primary;
 
                     // 如果曝光值有变化，则调用我们的平台抽象函数来设置
                     if expo_old != expo {
                         match set_camera_exposure(camera_index, expo, &mut cam) {
-                            Ok(_) => {
-                                info!("成功设置相机 {} 的曝光为 {}", camera_index, expo);
+                            Ok(applied) => {
+                                info!(
+                                    "成功设置相机 {} 的曝光为 {}，实际生效 {}",
+                                    camera_index, expo, applied
+                                );
                                 expo_old = expo;
+                                if primary {
+                                    let _ = update_tx.send(Update::Device(
+                                        DeviceUpdate::ExposureApplied(applied),
+                                    ));
+                                }
                             }
                             Err(e) => {
                                 error!("设置曝光失败: {}", e);
@@ -173,40 +223,23 @@ impl CameraManager {
                             *thread_latest_frame.lock() = None;
                             continue;
                         }
-                        let mut processed_frame = frame.clone();
-
-                        *thread_latest_frame.lock() = Some(frame.clone());
-                        let (lock_circle, min_radius, max_radius, mut circle) = {
-                            let s = settings.lock();
-                            (s.lock_circle, s.min_radius, s.max_radius, s.locked_circle)
-                        };
-                        let res = detect_and_draw_circle(
-                            &frame,
-                            &mut processed_frame,
-                            min_radius,
-                            max_radius,
-                            circle,
-                            lock_circle,
+                        publish_frame(
+                            camera_index as usize,
+                            frame,
+                            &thread_latest_frame,
+                            &settings,
+                            &thread_is_primary,
+                            &mut radius_history,
+                            &update_tx,
                         );
-                        if let Ok(cir) = res {
-                            circle = cir;
-                            let mut s = settings.lock();
-                            s.locked_circle = circle;
-                            
-                        }
-                        if let Some(color_image) = mat_to_color_image(processed_frame) {
-                                let _ = update_tx.send(Update::Device(
-                                    DeviceUpdate::NewCameraFrame(Arc::new(color_image)),
-                                ));
-                            }
                     } else {
                         // info!("相机断开3");
                         *thread_latest_frame.lock() = None;
                     }
                     let elapsed = start_time.elapsed();
-                    if elapsed < TARGET_FRAME_DURATION {
+                    if elapsed < frame_duration {
                         // 只休眠剩余的时间
-                        thread::sleep(TARGET_FRAME_DURATION - elapsed);
+                        thread::sleep(frame_duration - elapsed);
                     }
                 }
 
@@ -218,10 +251,172 @@ impl CameraManager {
             thread_handle: Some(thread_handle),
             stop_signal,
             latest_frame,
+            is_primary,
+        })
+    }
+}
+
+impl CameraManager {
+    /// 模拟模式：用合成的三分视场代替真实相机，亮暗随模拟电机角度变化
+    pub fn new_simulated(
+        sim_steps: Arc<AtomicI32>,
+        update_tx: Sender<Update>,
+        settings: Arc<Mutex<CameraSettings>>,
+    ) -> Result<Self> {
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        let thread_stop_signal = stop_signal.clone();
+        let latest_frame = Arc::new(Mutex::new(None));
+        let thread_latest_frame = latest_frame.clone();
+        let is_primary = Arc::new(AtomicBool::new(false));
+        let thread_is_primary = is_primary.clone();
+
+        let thread_handle = thread::spawn(move || {
+            info!("模拟相机已启动");
+            let mut expo_old = f64::NAN;
+            let mut radius_history = RadiusHistory::default();
+            while !thread_stop_signal.load(Ordering::Relaxed) {
+                let start_time = Instant::now();
+                // 模拟相机原样接受曝光值
+                let (expo, frame_duration, paused) = {
+                    let settings = settings.lock();
+                    (settings.exposure, settings.frame_duration(), settings.paused)
+                };
+                if paused {
+                    thread::sleep(PAUSED_POLL_INTERVAL);
+                    continue;
+                }
+                if expo_old != expo {
+                    expo_old = expo;
+                    let _ = update_tx.send(Update::Device(DeviceUpdate::ExposureApplied(expo)));
+                }
+                match super::simulation::synthetic_frame(sim_steps.load(Ordering::Relaxed)) {
+                    Ok(frame) => publish_frame(
+                        0,
+                        frame,
+                        &thread_latest_frame,
+                        &settings,
+                        &thread_is_primary,
+                        &mut radius_history,
+                        &update_tx,
+                    ),
+                    Err(e) => error!("生成模拟画面失败: {}", e),
+                }
+                let elapsed = start_time.elapsed();
+                if elapsed < frame_duration {
+                    thread::sleep(frame_duration - elapsed);
+                }
+            }
+            info!("模拟相机已停止");
+        });
+
+        Ok(Self {
+            thread_handle: Some(thread_handle),
+            stop_signal,
+            latest_frame,
+            is_primary,
+        })
+    }
+}
+
+/// 最近若干帧检测到的圆半径（None 表示该帧没找到圆）
+#[derive(Default)]
+struct RadiusHistory {
+    samples: VecDeque<Option<i32>>,
+    frames_since_report: usize,
+}
+
+impl RadiusHistory {
+    /// 记录一帧的检测结果，每隔 RADIUS_REPORT_EVERY 帧返回一次统计
+    fn push(&mut self, radius: Option<i32>) -> Option<Option<RadiusStats>> {
+        if self.samples.len() == RADIUS_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(radius);
+        self.frames_since_report += 1;
+        if self.frames_since_report < RADIUS_REPORT_EVERY {
+            return None;
+        }
+        self.frames_since_report = 0;
+        Some(self.stats())
+    }
+
+    /// 锁定圆形位置后半径固定，清空历史并通知前端
+    fn clear(&mut self) -> Option<Option<RadiusStats>> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        self.samples.clear();
+        self.frames_since_report = 0;
+        Some(None)
+    }
+
+    fn stats(&self) -> Option<RadiusStats> {
+        let radii: Vec<i32> = self.samples.iter().flatten().copied().collect();
+        let (&min, &max) = (radii.iter().min()?, radii.iter().max()?);
+        let n = radii.len() as f32;
+        let mean = radii.iter().sum::<i32>() as f32 / n;
+        let variance = radii.iter().map(|&r| (r as f32 - mean).powi(2)).sum::<f32>() / n;
+        Some(RadiusStats {
+            min,
+            max,
+            mean,
+            stability: (100.0 * (1.0 - variance.sqrt() / mean)).clamp(0.0, 100.0),
+            detected: radii.len(),
+            frames: self.samples.len(),
         })
     }
 }
 
+/// 保存最新原始帧，并把画好识别圆的画面发送给前端
+fn publish_frame(
+    camera_index: usize,
+    frame: Mat,
+    latest_frame: &Arc<Mutex<Option<Mat>>>,
+    settings: &Arc<Mutex<CameraSettings>>,
+    is_primary: &AtomicBool,
+    radius_history: &mut RadiusHistory,
+    update_tx: &Sender<Update>,
+) {
+    let mut processed_frame = frame.clone();
+
+    *latest_frame.lock() = Some(frame.clone());
+    let (lock_circle, min_radius, max_radius, mut circle) = {
+        let s = settings.lock();
+        (s.lock_circle, s.min_radius, s.max_radius, s.locked_circle)
+    };
+    let res = detect_and_draw_circle(
+        &frame,
+        &mut processed_frame,
+        min_radius,
+        max_radius,
+        circle,
+        lock_circle,
+    );
+    if is_primary.load(Ordering::Relaxed) {
+        let report = if lock_circle {
+            radius_history.clear()
+        } else {
+            radius_history.push(res.as_ref().ok().copied().flatten().map(|c| c.2))
+        };
+        if let Some(stats) = report {
+            let _ = update_tx.send(Update::Device(DeviceUpdate::RadiusStats(stats)));
+        }
+    }
+    if let Ok(cir) = res {
+        if is_primary.load(Ordering::Relaxed) {
+            circle = cir;
+            let mut s = settings.lock();
+            s.locked_circle = circle;
+        }
+    }
+    if let Some(color_image) = mat_to_color_image(processed_frame) {
+        let _ = update_tx.send(Update::Device(DeviceUpdate::NewCameraFrame(
+            camera_index,
+            Arc::new(color_image),
+        )));
+    }
+}
+
 impl Drop for CameraManager {
     fn drop(&mut self) {
         info!("正在关闭 CameraManager...");
@@ -236,35 +431,214 @@ impl Drop for CameraManager {
     }
 }
 
+/// 测量或录制中途失去相机时发给前端的状态
+pub fn lost_status(message: &str) -> Update {
+    Update::Device(DeviceUpdate::CameraStatus {
+        connected: false,
+        message: Some(message.to_string()),
+    })
+}
+
+/// 当前已连接的相机列表与主相机
+pub fn cameras_changed(devices: &super::DeviceState) -> Update {
+    Update::Device(DeviceUpdate::CamerasChanged {
+        connected: devices.cameras.keys().cloned().collect(),
+        primary: devices.primary_camera,
+    })
+}
+
+/// 通知前端当前已连接的相机列表、主相机及其设置；message 为本次操作失败的原因
+fn send_camera_state(
+    devices: &super::DeviceState,
+    tx: &Sender<Update>,
+    message: Option<String>,
+) -> Result<()> {
+    tx.send(cameras_changed(devices))?;
+    let settings = devices.camera_settings().lock().clone();
+    tx.send(Update::Device(DeviceUpdate::PrimaryCameraSettings {
+        exposure: settings.exposure,
+        min_radius: settings.min_radius.max(0) as u32,
+        max_radius: settings.max_radius.max(0) as u32,
+        lock_circle: settings.lock_circle,
+        capture_fps: settings.capture_fps,
+    }))?;
+    tx.send(Update::Device(DeviceUpdate::CameraStatus {
+        connected: devices.camera_manager().is_some(),
+        message,
+    }))?;
+    Ok(())
+}
+
+/// 等待新打开的相机送出第一帧；采集暂停时不等待
+fn wait_first_frame(manager: &CameraManager) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < FIRST_FRAME_TIMEOUT {
+        if manager.latest_frame.lock().is_some() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+/// 指定用于识别的主相机
+fn apply_primary(devices: &mut super::DeviceState, primary: Option<usize>) {
+    devices.primary_camera = primary.filter(|i| devices.cameras.contains_key(i));
+    for (index, manager) in &devices.cameras {
+        manager.set_primary(Some(*index) == devices.primary_camera);
+    }
+}
+
 pub fn connect_camera(
     state: &Arc<Mutex<BackendState>>,
     index: usize,
     tx: &Sender<Update>,
 ) -> Result<()> {
-    // 必须显式 drop 旧的 manager，以确保旧线程停止
-    let mut state_guard = state.lock();
-    state_guard.devices.camera_manager = None;
+    let (manager, paused) = {
+        // 同一路相机重连时必须先 drop 旧的 manager，以确保旧线程停止
+        let mut state_guard = state.lock();
+        state_guard.devices.cameras.remove(&index);
+
+        // 每路相机有自己的设置，第一次连接时从当前主相机（或默认设置）复制，
+        // 锁定的圆形位置只对原来那路相机有效，不复制
+        let devices = &mut state_guard.devices;
+        let inherited = devices.camera_settings().lock().clone();
+        let settings_clone = Arc::clone(devices.per_camera_settings.entry(index).or_insert_with(|| {
+            Arc::new(Mutex::new(CameraSettings {
+                locked_circle: None,
+                ..inherited
+            }))
+        }));
+        // 暂停采集对所有相机生效
+        let paused = {
+            let mut settings = settings_clone.lock();
+            settings.paused = devices.default_camera_settings.lock().paused;
+            settings.paused
+        };
 
-    // camera_settings 是主状态的一部分，但 camera_manager 不是
-    // 这里我们为相机线程创建一个独立的 settings Arc，它在 manager 启动时初始化
-    let settings_clone = Arc::clone(&state_guard.devices.camera_settings);
+        let manager = match &state_guard.devices.simulation {
+            Some(sim_steps) => CameraManager::new_simulated(sim_steps.clone(), tx.clone(), settings_clone),
+            None => CameraManager::new(index as i32, tx.clone(), settings_clone),
+        };
+        (manager, paused)
+    };
+    // 能打开却一直取不到画面（驱动异常、被占用后只返回空帧）同样视为连接失败；等待时不持有状态锁
+    let manager = manager.and_then(|manager| {
+        if paused || wait_first_frame(&manager) {
+            Ok(manager)
+        } else {
+            Err(anyhow::anyhow!(
+                "相机 {} 已打开，但 {} 秒内没有收到画面",
+                index,
+                FIRST_FRAME_TIMEOUT.as_secs()
+            ))
+        }
+    });
+    let mut state_guard = state.lock();
+    let devices = &mut state_guard.devices;
+    match manager {
+        Ok(manager) => {
+            devices.cameras.insert(index, manager);
+            // 第一路连接的相机自动成为主相机
+            let primary = devices.primary_camera.filter(|i| devices.cameras.contains_key(i));
+            apply_primary(devices, primary.or(Some(index)));
+            send_camera_state(devices, tx, None)?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("连接相机 {} 失败：{}", index, e);
+            let primary = devices.primary_camera;
+            apply_primary(devices, primary);
+            send_camera_state(devices, tx, Some(e.to_string()))?;
+            Err(e)
+        }
+    }
+}
 
-    let manager = CameraManager::new(index as i32, tx.clone(), settings_clone)?;
-    state_guard.devices.camera_manager = Some(manager);
-    Ok(())
+pub fn disconnect_camera(
+    state: &Arc<Mutex<BackendState>>,
+    index: usize,
+    tx: &Sender<Update>,
+) -> Result<()> {
+    let mut state_guard = state.lock();
+    let devices = &mut state_guard.devices;
+    devices.cameras.remove(&index);
+    // 主相机被断开时，由剩余的第一路相机接替
+    let primary = devices
+        .primary_camera
+        .filter(|i| devices.cameras.contains_key(i))
+        .or_else(|| devices.cameras.keys().next().cloned());
+    apply_primary(devices, primary);
+    send_camera_state(devices, tx, None)
 }
 
-pub fn disconnect_camera(state: &Arc<Mutex<BackendState>>) -> Result<()> {
-    state.lock().devices.camera_manager = None;
+pub fn set_primary_camera(
+    state: &Arc<Mutex<BackendState>>,
+    index: usize,
+    tx: &Sender<Update>,
+) -> Result<()> {
+    let mut state_guard = state.lock();
+    let devices = &mut state_guard.devices;
+    if !devices.cameras.contains_key(&index) {
+        anyhow::bail!("相机 {} 未连接", index);
+    }
+    apply_primary(devices, Some(index));
+    info!("已将相机 {} 设为识别主相机", index);
+    send_camera_state(devices, tx, None)
+}
+/// 暂停或恢复所有相机的采集。暂停时相机与 CameraManager 都保留，
+/// 最近一帧也不清空，恢复后采集线程直接继续
+pub fn set_capture_paused(
+    state: &Arc<Mutex<BackendState>>,
+    paused: bool,
+    tx: &Sender<Update>,
+) -> Result<()> {
+    {
+        let s = state.lock();
+        let devices = &s.devices;
+        if devices.default_camera_settings.lock().paused == paused {
+            return Ok(());
+        }
+        for settings in devices.per_camera_settings.values().chain([&devices.default_camera_settings]) {
+            settings.lock().paused = paused;
+        }
+    }
+    info!("相机采集已{}", if paused { "暂停" } else { "恢复" });
+    tx.send(Update::Device(DeviceUpdate::CapturePaused(paused)))?;
+    if !paused {
+        // 等采集线程醒来并写入新的一帧，避免紧接着的识别任务用到暂停前的旧画面
+        thread::sleep(PAUSED_POLL_INTERVAL * 2);
+    }
     Ok(())
 }
+
 // pub fn set_hough(state: &Arc<Mutex<BackendState>>) -> Result<()> {
 //     state.lock().devices.camera_manager = None;
 //     Ok(())
 // }
 
-pub fn refresh_cameras(update_tx: &Sender<Update>) -> Result<()> {
+/// 启动自检：读取链接的 OpenCV 版本并尝试创建一个 VideoCapture，
+/// 库安装不完整时在启动时就给出明确提示，而不是等到相机连接失败
+pub fn check_opencv(update_tx: &Sender<Update>) -> Result<()> {
+    let status = core::get_version_string()
+        .and_then(|version| videoio::VideoCapture::default().map(|_| version))
+        .map_err(|e| e.to_string());
+    match &status {
+        Ok(version) => info!("OpenCV 自检通过，版本 {}", version),
+        Err(e) => error!("OpenCV 未正确安装：{}", e),
+    }
+    update_tx.send(Update::Device(DeviceUpdate::OpenCvStatus(status)))?;
+    Ok(())
+}
+
+pub fn refresh_cameras(state: &Arc<Mutex<BackendState>>, update_tx: &Sender<Update>) -> Result<()> {
     info!("正在刷新相机列表...");
+    if state.lock().devices.simulation.is_some() {
+        update_tx.send(Update::Device(DeviceUpdate::CameraList(vec![
+            "模拟相机".to_string(),
+        ])))?;
+        return Ok(());
+    }
     let mut devices = Vec::new();
     // 尝试前10个索引，与Python代码逻辑一致
     for i in 0..10 {
@@ -277,12 +651,183 @@ pub fn refresh_cameras(update_tx: &Sender<Update>) -> Result<()> {
         }
     }
     info!("发现的相机: {:?}", devices);
+    if devices.is_empty() {
+        tracing::warn!("未发现任何相机");
+        update_tx.send(Update::Device(DeviceUpdate::CameraError(
+            "未发现任何相机，请检查 USB 连接后点击“重新检测”".to_string(),
+        )))?;
+    }
     update_tx
         .send(Update::Device(DeviceUpdate::CameraList(devices)))
         .unwrap();
     Ok(())
 }
 
+/// 在整幅画面上执行霍夫圆检测，返回检测到的第一个圆 (x, y, r)
+fn find_circle(input: &Mat, min_radius: i32, max_radius: i32) -> Result<Option<(i32, i32, i32)>> {
+    let mut gray = Mat::default();
+    imgproc::cvt_color(
+        input,
+        &mut gray,
+        imgproc::COLOR_BGR2GRAY,
+        0,
+        core::AlgorithmHint::ALGO_HINT_DEFAULT,
+    )?;
+    find_circle_gray(&gray, min_radius, max_radius)
+}
+
+/// 在灰度图上执行霍夫圆检测。画面显示、半径标定与模型裁剪共用这一组参数
+pub fn find_circle_gray(gray: &Mat, min_radius: i32, max_radius: i32) -> Result<Option<(i32, i32, i32)>> {
+    let mut circles = core::Vector::<core::Vec3f>::new();
+    imgproc::hough_circles(
+        gray,
+        &mut circles,
+        imgproc::HOUGH_GRADIENT,
+        1.0,        // dp
+        30.0,       // minDist
+        40.0,       // param1 (Canny a)
+        10.0,       // param2 (Canny b)
+        min_radius, // minRadius
+        max_radius, // maxRadius
+    )?;
+    if circles.is_empty() {
+        return Ok(None);
+    }
+    // 只取第一个检测到的圆
+    let c = circles.get(0)?;
+    Ok(Some((
+        c[0].round() as i32,
+        c[1].round() as i32,
+        c[2].round() as i32,
+    )))
+}
+
+/// 自动标定霍夫圆半径：采集若干帧，用宽范围检测，取最稳定圆半径的中位数并收紧范围
+pub fn calibrate_radius(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Result<()> {
+    info!("开始自动标定半径...");
+    let mut circles = Vec::new();
+    for _ in 0..CALIBRATION_FRAMES {
+        let frame = {
+            let s = state.lock();
+            match s.devices.camera_manager() {
+                Some(manager) => manager.latest_frame.lock().clone(),
+                None => anyhow::bail!("相机未连接"),
+            }
+        };
+        if let Some(frame) = frame {
+            let max_radius = frame.rows().min(frame.cols()) / 2;
+            if let Ok(Some(circle)) = find_circle(&frame, 5, max_radius) {
+                circles.push(circle);
+            }
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    if circles.len() < 3 {
+        anyhow::bail!("检测到的圆太少（{} 帧），无法标定", circles.len());
+    }
+
+    let median = |mut v: Vec<i32>| {
+        v.sort_unstable();
+        v[v.len() / 2]
+    };
+    // 以圆心中位数为参照，只保留位置稳定的检测结果
+    let cx = median(circles.iter().map(|c| c.0).collect());
+    let cy = median(circles.iter().map(|c| c.1).collect());
+    let stable: Vec<i32> = circles
+        .iter()
+        .filter(|c| (c.0 - cx).abs() <= 15 && (c.1 - cy).abs() <= 15)
+        .map(|c| c.2)
+        .collect();
+    if stable.is_empty() {
+        anyhow::bail!("检测到的圆位置不稳定，无法标定");
+    }
+    let radius = median(stable);
+    let band = (radius / 10).max(3);
+    let min = (radius - band).max(1) as u32;
+    let max = (radius + band) as u32;
+    info!("半径标定完成：中位半径 {}，范围 {}~{}", radius, min, max);
+    tx.send(Update::Device(DeviceUpdate::RadiusCalibrated { min, max }))?;
+    Ok(())
+}
+
+/// 在一组 (曝光, 平均亮度) 中选出亮度最接近目标区间中点的曝光
+fn suggest_exposure(points: &[(f64, f64)], target: (f64, f64)) -> Option<f64> {
+    let center = (target.0 + target.1) / 2.0;
+    points
+        .iter()
+        .min_by(|a, b| (a.1 - center).abs().total_cmp(&(b.1 - center).abs()))
+        .map(|p| p.0)
+}
+
+/// 逐档扫描曝光，测量识别区域（与模型输入相同的裁剪）的平均亮度，给出建议曝光。
+/// 扫描结束后恢复原曝光，是否采用建议值由用户决定
+pub fn sweep_exposure(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Result<()> {
+    let original = state.lock().devices.camera_settings().lock().exposure;
+    info!("开始曝光扫描，当前曝光 {}", original);
+    let (start, end, step) = EXPOSURE_SWEEP;
+    let mut points = Vec::new();
+    let mut exposure = start;
+    let result = (|| -> Result<()> {
+        while exposure <= end + f64::EPSILON {
+            let settings = {
+                let s = state.lock();
+                let mut settings = s.devices.camera_settings().lock();
+                settings.exposure = exposure;
+                settings.clone()
+            };
+            thread::sleep(EXPOSURE_SETTLE);
+            let circle = if settings.lock_circle { settings.locked_circle } else { None };
+            let mut brightness = Vec::new();
+            for _ in 0..EXPOSURE_SAMPLES {
+                let frame = {
+                    let s = state.lock();
+                    match s.devices.camera_manager() {
+                        Some(manager) => manager.latest_frame.lock().clone(),
+                        None => anyhow::bail!("相机未连接"),
+                    }
+                };
+                let crop = frame.and_then(|frame| {
+                    super::model::process_frame_for_ml(
+                        &frame,
+                        settings.min_radius,
+                        settings.max_radius,
+                        circle,
+                    )
+                    .ok()
+                });
+                if let Some(pixels) = crop.filter(|p| !p.is_empty()) {
+                    let sum: f64 = pixels.iter().map(|&p| p as f64).sum();
+                    brightness.push(sum / pixels.len() as f64);
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+            // 过暗或过亮时可能找不到圆，这一档直接跳过
+            if !brightness.is_empty() {
+                let mean = brightness.iter().sum::<f64>() / brightness.len() as f64;
+                info!("曝光 {}：平均亮度 {:.1}", exposure, mean);
+                points.push((exposure, mean));
+            }
+            exposure += step;
+        }
+        Ok(())
+    })();
+    state.lock().devices.camera_settings().lock().exposure = original;
+    result?;
+    if points.is_empty() {
+        anyhow::bail!("扫描过程中始终找不到圆，请先调整半径或锁定圆形位置");
+    }
+    let suggested = suggest_exposure(&points, BRIGHTNESS_TARGET);
+    if let Some(value) = suggested {
+        info!("曝光扫描完成，建议曝光 {}", value);
+    }
+    tx.send(Update::Device(DeviceUpdate::ExposureSwept(ExposureSweep {
+        points,
+        target: BRIGHTNESS_TARGET,
+        suggested,
+    })))?;
+    Ok(())
+}
+
 fn detect_and_draw_circle(
     input: &Mat,
     output: &mut Mat,
@@ -301,47 +846,13 @@ fn detect_and_draw_circle(
         imgproc::circle(output, center, radius, color, 2, imgproc::LINE_AA, 0).unwrap_or(());
         Ok(cir)
     } else {
-        let mut gray = Mat::default();
-        imgproc::cvt_color(
-            input,
-            &mut gray,
-            imgproc::COLOR_BGR2GRAY,
-            0,
-            core::AlgorithmHint::ALGO_HINT_DEFAULT,
-        )?;
-
-        let mut circles = core::Vector::<core::Vec3f>::new();
-        imgproc::hough_circles(
-            &gray,
-            &mut circles,
-            imgproc::HOUGH_GRADIENT,
-            1.0,        // dp
-            30.0,       // minDist
-            40.0,       // param1 (Canny a)
-            10.0,       // param2 (Canny b)
-            min_radius, // minRadius
-            max_radius, // maxRadius
-        )?;
-
-        if circles.len() > 0 {
-            // 只取第一个检测到的圆
-            let circle_params = circles.get(0).unwrap();
-            let center = core::Point::new(
-                circle_params[0].round() as i32,
-                circle_params[1].round() as i32,
-            );
-            let radius = circle_params[2].round() as i32;
-
+        let found = find_circle(input, min_radius, max_radius)?;
+        if let Some((x, y, radius)) = found {
             let color = core::Scalar::new(0.0, 255.0, 0.0, 255.0); // Green for unlocked
-            imgproc::circle(output, center, radius, color, 2, imgproc::LINE_AA, 0).unwrap_or(());
-            Ok(Some((
-                circle_params[0].round() as i32,
-                circle_params[1].round() as i32,
-                circle_params[2].round() as i32,
-            )))
-        } else {
-            Ok(None)
+            imgproc::circle(output, core::Point::new(x, y), radius, color, 2, imgproc::LINE_AA, 0)
+                .unwrap_or(());
         }
+        Ok(found)
     }
 }
 
@@ -1,7 +1,6 @@
 use super::{Arc, BackendState, CancellationToken, Mutex};
 use crate::communication::*;
 use anyhow::Result;
-use calamine::{DataType, Reader};
 use crossbeam_channel::Sender;
 use std::sync::atomic::Ordering;
 use tracing::info;
@@ -11,16 +10,53 @@ fn send_status<S: Into<String>>(tx: &Sender<Update>, msg: S) -> Result<()> {
     Ok(())
 }
 
+/// 静态或动态测量任务正在运行时返回 true
+fn measurement_running(state: &Arc<Mutex<BackendState>>) -> bool {
+    let s = state.lock();
+    s.measurement.static_task_token.is_some() || s.measurement.dynamic_task_token.is_some()
+}
+
 pub fn handle_general(
     cmd: GeneralCommand,
-    _state: Arc<Mutex<BackendState>>,
-    _tx: &Sender<Update>,
+    state: Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
     _token: CancellationToken,
 ) -> Result<()> {
     match cmd {
         GeneralCommand::Shutdown => {
             info!("收到关闭指令 (逻辑待实现)");
         }
+        GeneralCommand::StopActiveTask => {
+            let s = state.lock();
+            let active = [
+                ("静态测量/找零点", &s.measurement.static_task_token),
+                ("动态测量", &s.measurement.dynamic_task_token),
+                ("录制", &s.recording.cancellation_token),
+            ];
+            let mut stopped = false;
+            for (name, token) in active {
+                if let Some(token) = token {
+                    token.store(true, Ordering::Relaxed);
+                    info!("已向{}任务发送停止信号", name);
+                    stopped = true;
+                }
+            }
+            if !stopped {
+                info!("没有正在运行的任务");
+            }
+        }
+        GeneralCommand::StopAllMotion => super::measurement::stop_all_motion(&state),
+        GeneralCommand::SetExportLayout(layout) => {
+            if !layout.has_data_column() {
+                return Err(BackendError::NotReady(
+                    "至少需要保留时间、步数、角度中的一列".to_string(),
+                )
+                .into());
+            }
+            super::measurement::save_export_layout(&layout);
+            state.lock().measurement.export_layout = layout.clone();
+            tx.send(Update::General(GeneralUpdate::ExportLayout(layout)))?;
+        }
     }
     Ok(())
 }
@@ -33,7 +69,7 @@ pub fn handle_device(
 ) -> Result<()> {
     match cmd {
         DeviceCommand::RefreshSerialPorts => {
-            let ports = super::serial::get_available_ports(token);
+            let ports = super::serial::get_available_ports(&state, token);
             tx.send(Update::Device(DeviceUpdate::SerialPortsList(ports)))?;
         }
         DeviceCommand::ConnectSerial { port, baud_rate } => {
@@ -47,13 +83,28 @@ pub fn handle_device(
         DeviceCommand::TestSerial => {
             super::serial::test(&state, &tx)?;
         }
+        DeviceCommand::SetSerialProtocol { ack_token, stop_byte } => {
+            super::serial::set_protocol(&state, ack_token, stop_byte)?;
+        }
         DeviceCommand::RotateMotor { steps } => {
             // let reverse={state.lock().rotation_direction_need_reverse};
+            if measurement_running(&state) {
+                tx.send(Update::General(GeneralUpdate::Error(
+                    BackendError::NotReady("测量任务进行中，无法手动旋转".to_string()),
+                )))?;
+                return Ok(());
+            }
             super::measurement::precision_rotate(&state, tx, steps)?;
         }
         DeviceCommand::RotateTo { steps } => {
             // super::serial::rotate_motor(&state, angle)?;
             // let reverse={state.lock().rotation_direction_need_reverse};
+            if measurement_running(&state) {
+                tx.send(Update::General(GeneralUpdate::Error(
+                    BackendError::NotReady("测量任务进行中，无法手动旋转".to_string()),
+                )))?;
+                return Ok(());
+            }
             super::measurement::precision_rotate_to(&state, tx, steps)?;
         }
         DeviceCommand::SetRotationDirection(is_ama) => {
@@ -68,6 +119,7 @@ pub fn handle_device(
             mode,
             save_path,
             num,
+            fps,
         } => {
             // --- 这是命令处理线程，它现在将成为录制线程 ---
 
@@ -92,7 +144,12 @@ pub fn handle_device(
 
             // 4. 直接、阻塞地调用录制循环。
             //    这个 command-thread 会在这里暂停，直到录制结束或被取消。
-            super::recording::record_video_loop(&state, &tx, save_path, mode, num, token)?;
+            let result = super::camera::set_capture_paused(&state, false, tx).and_then(|_| {
+                super::recording::record_video_loop(&state, &tx, save_path, mode, num, fps, token)
+            });
+            // 录制提前出错时同样释放令牌，否则之后的开始录制会被静默忽略
+            state.lock().recording.cancellation_token = None;
+            result?;
         }
         DeviceCommand::StopRecording => {
             // let mut state_guard = state.lock();
@@ -115,24 +172,45 @@ pub fn handle_device(
             }
         }
         DeviceCommand::FindZeroPoint => {
-            super::measurement::static_measurement(&state, &tx, token, true,1)?;
+            super::camera::set_capture_paused(&state, false, tx)?;
+            super::model::check_feature_config(&state, tx)?;
+            super::measurement::static_measurement(&state, &tx, token, true, 1, None)?;
         }
         DeviceCommand::ReturnToZero => {
             // send_status(&tx, "正在返回零点...")?;
-            if state.lock().measurement.static_task_token.is_none()
-                && state.lock().measurement.dynamic_task_token.is_none()
-            {
+            if !measurement_running(&state) {
                 super::measurement::return_to_zero(&state, &tx)?;
             } else {
-                tx.send(Update::General(GeneralUpdate::Error(format!(
-                    "请先停止测量任务"
-                ))))?;
+                tx.send(Update::General(GeneralUpdate::Error(
+                    BackendError::NotReady("请先停止测量任务".to_string()),
+                )))?;
             }
 
             // send_status(&tx, "已返回零点")?;
         }
+        DeviceCommand::ReturnToZeroBySearch => {
+            if measurement_running(&state) {
+                tx.send(Update::General(GeneralUpdate::Error(
+                    BackendError::NotReady("请先停止测量任务".to_string()),
+                )))?;
+                return Ok(());
+            }
+            super::camera::set_capture_paused(&state, false, tx)?;
+            super::measurement::return_to_zero_by_search(&state, &tx, token)?;
+        }
+        DeviceCommand::ResetOdometer => {
+            super::odometer::reset(&state, tx)?;
+        }
         DeviceCommand::SetStep(anglestpes)=>{
-            state.lock().devices.angle_steps=anglestpes
+            let mut s = state.lock();
+            if !is_valid_steps_per_degree(anglestpes) {
+                return Err(anyhow::anyhow!(
+                    "每度步数必须为正数（收到 {}），已保留原值 {}",
+                    anglestpes,
+                    s.devices.angle_steps
+                ));
+            }
+            s.devices.angle_steps = anglestpes;
         }
         _ => info!("收到未实现的 DeviceCommand"),
     }
@@ -143,42 +221,95 @@ pub fn handle_camera(
     cmd: CameraCommand,
     state: Arc<Mutex<BackendState>>,
     tx: &Sender<Update>,
-    _token: CancellationToken,
+    token: CancellationToken,
 ) -> Result<()> {
     match cmd {
         CameraCommand::Connect { index } => {
             info!("正在连接相机 {}...", index);
             super::camera::connect_camera(&state, index, tx)?;
         }
-        CameraCommand::Disconnect => {
-            info!("正在断开相机...");
-            super::camera::disconnect_camera(&state)?;
-            tx.send(Update::Device(DeviceUpdate::CameraConnectionStatus(false)))?;
+        CameraCommand::Disconnect { index } => {
+            info!("正在断开相机 {}...", index);
+            super::camera::disconnect_camera(&state, index, tx)?;
+        }
+        CameraCommand::SetPrimary { index } => {
+            super::camera::set_primary_camera(&state, index, tx)?;
         }
         CameraCommand::RefreshCameras => {
-            super::camera::refresh_cameras(tx)?;
+            super::camera::refresh_cameras(&state, tx)?;
             // tx.send(Update::Device(DeviceUpdate::CameraConnectionStatus(false)))?;
         }
         CameraCommand::SetHoughCircleRadius { min, max } => {
             // --- 实时更新逻辑 ---
-            let state_guard = state.lock();
-            let mut settings = state_guard.devices.camera_settings.lock();
-            settings.min_radius = min as i32;
-            settings.max_radius = max as i32;
-            // info!("霍夫圆半径已更新为: min={}, max={}", min, max);
+            {
+                let state_guard = state.lock();
+                let mut settings = state_guard.devices.camera_settings().lock();
+                settings.min_radius = min as i32;
+                settings.max_radius = max as i32;
+                // info!("霍夫圆半径已更新为: min={}, max={}", min, max);
+            }
+            super::model::check_feature_config(&state, tx)?;
         }
         CameraCommand::SetLock(value) => {
             // --- 实时更新逻辑 ---
-            let state_guard = state.lock();
-            let mut settings = state_guard.devices.camera_settings.lock();
-            settings.lock_circle = value;
-            info!("圆锁定状态已更新为: {}", value);
+            {
+                let state_guard = state.lock();
+                let mut settings = state_guard.devices.camera_settings().lock();
+                settings.lock_circle = value;
+                info!("圆锁定状态已更新为: {}", value);
+            }
+            super::model::check_feature_config(&state, tx)?;
         } //_ => info!("收到未实现的 CameraCommand"),
+        CameraCommand::SetCaptureFps(fps) => {
+            let state_guard = state.lock();
+            let mut settings = state_guard.devices.camera_settings().lock();
+            settings.capture_fps = fps;
+            info!("相机采集帧率已更新为: {}", fps);
+        }
         CameraCommand::Exposure(value)=>{
             let state_guard = state.lock();
-            let mut settings = state_guard.devices.camera_settings.lock();
+            let mut settings = state_guard.devices.camera_settings().lock();
             settings.exposure = value;
         }
+        CameraCommand::SetCapturePaused(paused) => {
+            let busy = {
+                let s = state.lock();
+                s.recording.cancellation_token.is_some()
+                    || s.devices.prediction_preview_token.is_some()
+            };
+            if paused && (busy || measurement_running(&state)) {
+                tx.send(Update::Device(DeviceUpdate::CapturePaused(false)))?;
+                return Err(anyhow::anyhow!("测量、录制或识别预览进行中，无法暂停相机采集"));
+            }
+            super::camera::set_capture_paused(&state, paused, tx)?;
+        }
+        CameraCommand::SweepExposure => {
+            if measurement_running(&state) || state.lock().recording.cancellation_token.is_some() {
+                return Err(anyhow::anyhow!("测量或录制进行中，无法扫描曝光"));
+            }
+            super::camera::set_capture_paused(&state, false, tx)?;
+            super::camera::sweep_exposure(&state, tx)?;
+        }
+        CameraCommand::CalibrateRadius => {
+            super::camera::set_capture_paused(&state, false, tx)?;
+            super::camera::calibrate_radius(&state, tx)?;
+        }
+        CameraCommand::SetPredictionPreview(enabled) => {
+            if enabled {
+                {
+                    let mut s = state.lock();
+                    if s.devices.prediction_preview_token.is_some() {
+                        return Ok(());
+                    }
+                    s.devices.prediction_preview_token = Some(token.clone());
+                }
+                super::camera::set_capture_paused(&state, false, tx)?;
+                // 与录制相同，命令线程直接成为预览线程
+                super::model::run_prediction_preview(&state, tx, token)?;
+            } else if let Some(preview_token) = &state.lock().devices.prediction_preview_token {
+                preview_token.store(true, Ordering::Relaxed);
+            }
+        }
     }
     Ok(())
 }
@@ -190,9 +321,11 @@ pub fn handle_training(
     token: CancellationToken,
 ) -> Result<()> {
     match cmd {
-        // TrainingCommand::ProcessVideo { video_path, mode } => {
-        //     super::model::process_video_for_training(&state, &video_path, &mode, &tx, token)?;
-        // }
+        TrainingCommand::QuickTrainFromVideos { mam_video, ama_video, show_roc, show_cm } => {
+            super::model::process_video_for_training(&state, &mam_video, "MAM", tx, &token)?;
+            super::model::process_video_for_training(&state, &ama_video, "AMA", tx, &token)?;
+            super::model::train_model(&state, show_roc, show_cm, &tx)?;
+        }
         TrainingCommand::LoadRecordedDataset { path } => {
             super::model::load_recorded_dataset(&state, &path, &tx)?;
         }
@@ -202,6 +335,16 @@ pub fn handle_training(
         TrainingCommand::LoadPersistentDataset { path } => {
             super::model::load_persistent_dataset(&state, &path, &tx)?;
         }
+        TrainingCommand::ExportEvaluation { path } => {
+            super::model::export_evaluation(&state, &path)?;
+        }
+        TrainingCommand::LoadSharedDataset { source } => {
+            super::dataset_repo::load_shared_dataset(&state, &source, &tx)?;
+        }
+        TrainingCommand::SetNormalization(normalization) => {
+            state.lock().training.normalization = normalization;
+            info!("下次训练将使用归一化方式 {:?}", normalization);
+        }
         TrainingCommand::ResetModel => {
             super::model::reset_model(&state, &tx)?;
         }
@@ -215,14 +358,13 @@ pub fn handle_training(
             state.lock().training.ama_images.clear();
             info!("录制数据集已重置");
         }
-        // TrainingCommand::LoadModel { path } => {
-        //     if let Some(model)=state.lock().training.fitted_model{
-        //        let x=bincode::serialize(&model);
-        //     }
-        // }
-        // TrainingCommand::SaveModel { path } =>{
-
-        // }
+        TrainingCommand::SaveModel { path } => {
+            super::model::save_model(&state, &path)?;
+            send_status(tx, format!("模型已保存到 {}", path.display()))?;
+        }
+        TrainingCommand::LoadModel { path } => {
+            super::model::load_model(&state, &path, &tx)?;
+        }
         _ => info!("收到未实现的 TrainingCommand"),
     }
     Ok(())
@@ -236,7 +378,10 @@ pub fn handle_static_measure(
 ) -> Result<()> {
     match cmd {
         StaticMeasureCommand::RunSingleMeasurement{time} => {
-            if super::measurement::static_measurement(&state, &tx, token, false,time).is_err() {
+            // 需要识别画面的任务开始前自动恢复采集，并提醒识别设置是否与训练时一致
+            super::camera::set_capture_paused(&state, false, tx)?;
+            super::model::check_feature_config(&state, tx)?;
+            if super::measurement::static_measurement(&state, &tx, token, false, time, None).is_err() {
                 state.lock().measurement.static_task_token = None;
                 tx.send(Update::Measurement(MeasurementUpdate::StaticRunning(false)))?;
             }
@@ -251,7 +396,6 @@ pub fn handle_static_measure(
         }
         StaticMeasureCommand::SaveResults { path } => {
             super::measurement::save_static(&state, path, &tx)?;
-            info!("静态测量结果已储存")
         }
         StaticMeasureCommand::Stop => {
             if let Some(stoptoken) = &state.lock().measurement.static_task_token {
@@ -260,6 +404,31 @@ pub fn handle_static_measure(
             } else {
                 info!("没有正在运行的静态实验");
             }
+        }
+        StaticMeasureCommand::RezeroHere { confirmed } => {
+            if measurement_running(&state) {
+                tx.send(Update::General(GeneralUpdate::Error(
+                    BackendError::NotReady("请先停止测量任务".to_string()),
+                )))?;
+                return Ok(());
+            }
+            super::measurement::rezero_here(&state, tx, confirmed)?;
+        }
+        StaticMeasureCommand::VerifyZero => {
+            if measurement_running(&state) {
+                tx.send(Update::General(GeneralUpdate::Error(
+                    BackendError::NotReady("请先停止测量任务".to_string()),
+                )))?;
+                return Ok(());
+            }
+            super::camera::set_capture_paused(&state, false, tx)?;
+            super::measurement::verify_zero(&state, tx)?;
+        }
+        StaticMeasureCommand::SetDecisionMargin(margin) => {
+            state.lock().measurement.decision_margin = margin;
+        }
+        StaticMeasureCommand::ReplayPredictions { path } => {
+            super::measurement::replay_predictions(&state, tx, &path)?;
         } //_ => info!("收到未实现的 StaticMeasureCommand"),
     }
     Ok(())
@@ -275,6 +444,8 @@ pub fn handle_dynamic_measure(
         DynamicMeasureCommand::Start  => {
             // let token = Arc::new(AtomicBool::new(false));
             // state.lock().measurement.dynamic_task_token = Some(token.clone());
+            super::camera::set_capture_paused(&state, false, tx)?;
+            super::model::check_feature_config(&state, tx)?;
             // 这个函数是阻塞的，但它运行在自己的线程里
             super::measurement::run_dynamic_experiment_loop(&state, &tx, token)?;
         }
@@ -295,13 +466,16 @@ pub fn handle_dynamic_measure(
             if s.measurement.dynamic_task_token.is_none() {
                 s.measurement.dynamic_results.clear();
                 s.measurement.dynamic_time = Some(std::time::Instant::now());
+                let run_id = super::measurement::new_run_id();
+                s.measurement.dynamic_run_id = Some(run_id.clone());
                 tx.send(Update::Measurement(MeasurementUpdate::DynamicResults(
                     s.measurement.dynamic_results.clone(),
                 )))?;
                 tx.send(Update::Measurement(MeasurementUpdate::StartTime(
                     s.measurement.dynamic_time.clone(),
                 )))?;
-                info!("开始新动态试验");
+                tx.send(Update::Measurement(MeasurementUpdate::RunId(Some(run_id.clone()))))?;
+                super::record_event(tx, format!("开始新动态试验，运行编号 {}", run_id));
             } else {
                 info!("请先关闭动态追踪");
             }
@@ -314,6 +488,31 @@ pub fn handle_dynamic_measure(
             )))?;
             info!("动态测量结果已清除");
         }
+        DynamicMeasureCommand::ImportParams { path } => {
+            super::measurement::import_dynamic_params(&state, path, tx)?;
+        }
+        DynamicMeasureCommand::RecordNow => {
+            super::measurement::record_dynamic_now(&state, tx)?;
+        }
+        DynamicMeasureCommand::ExportFitReady { path } => {
+            let (results, alpha_inf) = {
+                let s = state.lock();
+                (s.measurement.dynamic_results.clone(), s.data_processing.alpha_inf)
+            };
+            let alpha_inf = alpha_inf.ok_or_else(|| anyhow::anyhow!("请先在数据处理页设置 α∞"))?;
+            let (written, skipped) = super::data::export_fit_ready(&path, &results, alpha_inf)?;
+            info!("已导出 {} 个拟合用数据点到 {}", written, path.display());
+            let message = if skipped > 0 {
+                format!("已导出 {} 个点，跳过 {} 个 Δα ≤ 0 的点", written, skipped)
+            } else {
+                format!("已导出 {} 个点", written)
+            };
+            send_status(tx, message)?;
+        }
+        DynamicMeasureCommand::SaveResultsCsv { path } => {
+            let count = super::measurement::save_dynamic_csv(&state, tx, &path)?;
+            send_status(tx, format!("已导出 {} 个点到 {}", count, path.display()))?;
+        }
     }
     Ok(())
 }
@@ -327,34 +526,91 @@ pub fn handle_data_processing(
     let mut state_guard = state.lock();
 
     match cmd {
-        DataProcessingCommand::LoadData { path } => {
-            info!("正在加载数据");
-            let mut workbook: calamine::Xlsx<_> = calamine::open_workbook(path)?;
-
-            if let Some(Ok(range)) = workbook.worksheet_range_at(0) {
-                let mut data: Vec<(f64, i32, f64, bool)> = Vec::new();
-                for row in range.rows().skip(1) {
-                    // 改进后的方式
-                    let time_opt = row.get(1).and_then(|c| c.get_float());
-                    let steps_opt = row.get(2).and_then(|c| c.get_float());
-                    let angle_opt = row.get(3).and_then(|c| c.get_float());
-                    // info!("{:?} {:?} {:?}",time_opt,steps_opt,angle_opt);
-                    if let (Some(time), Some(steps), Some(angle)) = (time_opt, steps_opt, angle_opt)
-                    {
-                        data.push((time, steps.round() as i32, angle, false));
-                    }
+        DataProcessingCommand::LoadData { path, sheet } => {
+            if sheet.is_none() && !super::data::is_csv(&path) {
+                let sheets = super::data::sheet_names(&path)?;
+                if sheets.len() > 1 {
+                    // 多张工作表时由用户选择，避免默默导入了错误的一次实验
+                    drop(state_guard);
+                    tx.send(Update::DataProcessing(DataProcessingUpdate::SheetChoices {
+                        path,
+                        sheets,
+                    }))?;
+                    return Ok(());
                 }
-                // Update the state
-                state_guard.data_processing.raw_data = Some(data);
-                info!("数据加载成功");
             }
+            info!("正在加载数据");
+            let steps_per_degree = state_guard.devices.angle_steps as f64;
+            let run = super::data::load_run(&path, sheet.as_deref(), steps_per_degree)?;
+            if let Some(message) = &run.header_warning {
+                tracing::warn!("{}", message);
+                send_status(tx, message.clone())?;
+            }
+            if run.derived_angles > 0 {
+                let message = format!(
+                    "文件缺少角度，{} 个点的角度由步数换算（1° = {} 步）",
+                    run.derived_angles, run.steps_per_degree
+                );
+                tracing::warn!("{}", message);
+                send_status(tx, message)?;
+            }
+            if run.malformed > 0 {
+                let message = format!("{} 行无法解析，已标记为无效点", run.malformed);
+                tracing::warn!("{}", message);
+                send_status(tx, message)?;
+            }
+            // 文件中记录了旋光方向时以文件为准，否则沿用当前动态测量的设置
+            state_guard.data_processing.sample_direction = match run.param("样品旋光方向") {
+                Some("左旋") => SampleDirection::Levo,
+                Some(_) => SampleDirection::Dextro,
+                None => state_guard.measurement.dynamic_params.sample_direction,
+            };
+            state_guard.data_processing.raw_data = Some(
+                run.data
+                    .into_iter()
+                    .zip(&run.valid)
+                    .map(|((time, steps, angle), &valid)| (time, steps, angle, valid))
+                    .collect(),
+            );
+            state_guard.data_processing.file_valid = run.valid;
+            info!("数据加载成功");
+        }
+        DataProcessingCommand::ExportMerged { inputs, output } => {
+            // 合并导出不改变当前数据，无需重新计算
+            let alpha_inf = state_guard
+                .data_processing
+                .alpha_inf
+                .ok_or_else(|| anyhow::anyhow!("请先在数据处理页设置 α∞"))?;
+            let mode = state_guard.data_processing.regression_mode;
+            let steps_per_degree = state_guard.devices.angle_steps as f64;
+            drop(state_guard);
+            let count =
+                super::data::export_merged(&inputs, &output, alpha_inf, mode, steps_per_degree)?;
+            info!("已将 {} 次运行合并导出到 {}", count, output.display());
+            send_status(tx, format!("已合并 {} 次运行", count))?;
+            return Ok(());
         }
         DataProcessingCommand::SetAlphaInf { alpha } => {
-            state_guard.data_processing.alpha_inf = alpha;
+            state_guard.data_processing.alpha_inf = Some(alpha);
+        }
+        DataProcessingCommand::EstimateAlphaInf => {
+            let fit = super::data::estimate_alpha_inf(&mut state_guard)?;
+            let message = format!(
+                "估算 α∞ = {:.4}°（α0 = {:.4}°，k = {:.4e}，R² = {:.4}）",
+                fit.alpha_inf, fit.alpha0, fit.k, fit.r2
+            );
+            info!("{}", message);
+            send_status(tx, message)?;
         }
         DataProcessingCommand::SetRegressionMode { mode } => {
             state_guard.data_processing.regression_mode = mode;
         }
+        DataProcessingCommand::SetExcludeTrendReversals(exclude) => {
+            state_guard.data_processing.exclude_trend_reversals = exclude;
+        }
+        DataProcessingCommand::Recalculate => {
+            info!("按当前数据重新拟合");
+        }
     }
 
     // After ANY state change, recalculate and push a full update
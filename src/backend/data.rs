@@ -2,62 +2,75 @@ use super::{BackendState};
 use anyhow::Result;
 
 use crate::communication::*;
+use calamine::{DataType, Reader};
 use crossbeam_channel::Sender;
+use rust_xlsxwriter::{Format, Workbook};
+use std::path::{Path, PathBuf};
 use ndarray::{Array1,Axis};
 use linfa::traits::{Fit, Predict};
 use linfa_linear::{FittedLinearRegression, LinearRegression};
 use linfa::prelude::*;
-pub fn recalculate_and_update(state: &mut BackendState, tx: &Sender<Update>) -> Result<()> {
-    let dp_state = &mut state.data_processing;
-    dp_state.plot_scatter_points.clear();
-    dp_state.plot_line_points.clear();
-    dp_state.regression_formula.clear();
-    // If there's no data, clear results and send an update
-    let Some(raw_data) = &mut dp_state.raw_data else {
-        // 没有数据，发送一个清空的状态
-        tx.send(Update::DataProcessing(DataProcessingUpdate::FullState(dp_state.clone().into())))?;
-        return Ok(());
+
+/// 拟合直线 y = slope·t + intercept
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    pub r2: f64,
+    // 残差标准误 sqrt(SSR / (n - 2))，只有两个点时没有自由度，为 NaN
+    pub rse: f64,
+    // 直线在 t 最小值和最大值处的两个端点，用于绘图
+    pub endpoints: Vec<(f64, f64)>,
+}
+
+/// 回归计算结果，不依赖任何通道或共享状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegressionFit {
+    // 变换后的有效点 (t, y)
+    pub points: Vec<(f64, f64)>,
+    // 与输入一一对应，false 表示该点变换后无效、未参与拟合
+    pub valid: Vec<bool>,
+    // 有效点不足 2 个或 t 全部相同时无法拟合
+    pub line: Option<LinearFit>,
+}
+
+/// 按回归模式对 Δα 做 y 轴变换，无法变换时返回 None
+pub fn transform(diff: f64, mode: RegressionMode) -> Option<f64> {
+    let y = match mode {
+        RegressionMode::Linear => diff,
+        RegressionMode::Log => if diff > 1e-9 { diff.ln() } else { f64::NAN },
+        RegressionMode::Inverse => if diff > 1e-9 { 1.0 / diff } else { f64::NAN },
     };
-    if raw_data.is_empty() {
-        tx.send(Update::DataProcessing(DataProcessingUpdate::FullState(dp_state.clone().into())))?;
-        return Ok(());
-    }
+    y.is_finite().then_some(y)
+}
 
-    // --- 1. 计算用于绘图的散点坐标 (y-axis transformation) ---
-    dp_state.plot_scatter_points = raw_data.iter_mut().filter_map(|point| {
-        let diff = point.2 - dp_state.alpha_inf;
-        let y_val = match dp_state.regression_mode {
-            RegressionMode::Linear => diff,
-            RegressionMode::Log => if diff > 1e-9 { diff.ln() } else { f64::NAN },
-            RegressionMode::Inverse => if diff > 1e-9 { 1.0 / diff } else { f64::NAN },
-        };
-        if y_val.is_finite() { 
-            point.3=true;
-            Some((point.0, y_val)) 
-        } else { 
-            point.3=false;
-            None 
-        }
-    }).collect();
+/// 回归的核心计算：输入 (t, Δα) 序列与回归模式，返回变换后的点和拟合结果
+pub fn fit_regression(data: &[(f64, f64)], mode: RegressionMode) -> Result<RegressionFit> {
+    let transformed: Vec<Option<f64>> = data.iter().map(|&(_, diff)| transform(diff, mode)).collect();
+    let valid: Vec<bool> = transformed.iter().map(Option::is_some).collect();
+    let points: Vec<(f64, f64)> = data
+        .iter()
+        .zip(&transformed)
+        .filter_map(|(&(t, _), y)| y.map(|y| (t, y)))
+        .collect();
 
-    if dp_state.plot_scatter_points.is_empty() {
-        // 如果变换后没有有效数据点，也发送清空的状态
-        tx.send(Update::DataProcessing(DataProcessingUpdate::FullState(dp_state.clone().into())))?;
-        return Ok(());
+    let (x_data, y_data): (Vec<f64>, Vec<f64>) = points.iter().cloned().unzip();
+    let x_min = x_data.iter().cloned().fold(f64::INFINITY, f64::min);
+    let x_max = x_data.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if points.len() < 2 || x_max - x_min < 1e-12 {
+        return Ok(RegressionFit { points, valid, line: None });
     }
 
-    // --- 2. 准备 linfa 数据集 ---
-    let (x_data, y_data): (Vec<f64>, Vec<f64>) = dp_state.plot_scatter_points.iter().cloned().unzip();
-    let x_arr = Array1::from(x_data.clone());
+    let x_arr = Array1::from(x_data);
     let y_arr = Array1::from(y_data);
     let dataset = Dataset::new(x_arr.insert_axis(Axis(1)), y_arr);
-    let model:FittedLinearRegression<f64> = LinearRegression::new().fit(&dataset)?;
-    
-    let params = model.params();
+    let model: FittedLinearRegression<f64> = LinearRegression::new().fit(&dataset)?;
+
+    let slope = model.params()[0];
     let intercept = model.intercept();
     let predicted_y = model.predict(&dataset);
     let y_true = dataset.targets();
-    
+
     // 计算 SST (Total Sum of Squares)
     let y_mean = y_true.mean().unwrap();
     let sst = y_true.iter().map(|y| (y - y_mean).powi(2)).sum::<f64>();
@@ -71,19 +84,1018 @@ pub fn recalculate_and_update(state: &mut BackendState, tx: &Sender<Update>) ->
     } else {
         1.0 - (ssr / sst)
     };
-    // Update state with new results
-    let sign = if intercept >= 0.0 { "+" } else { "-" };
-    dp_state.regression_formula = format!("y = {:.4}x {} {:.4}\nR² = {:.6}", params[0], sign, intercept.abs(), r2);
-    
-    let x_min = x_data.iter().cloned().fold(f64::INFINITY, f64::min);
-    let x_max = x_data.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    let y_min = params[0] * x_min + intercept;
-    let y_max = params[0] * x_max + intercept;
-    dp_state.plot_line_points = vec![(x_min, y_min), (x_max, y_max)];
 
-    // --- 5. 发送完整的、包含所有绘图数据的状态更新 ---
+    let dof = points.len() as f64 - 2.0;
+    let rse = if dof > 0.0 { (ssr / dof).sqrt() } else { f64::NAN };
+
+    let endpoints = vec![
+        (x_min, slope * x_min + intercept),
+        (x_max, slope * x_max + intercept),
+    ];
+    Ok(RegressionFit {
+        points,
+        valid,
+        line: Some(LinearFit { slope, intercept, r2, rse, endpoints }),
+    })
+}
+
+/// 找出逆着预期趋势跳变的点：右旋样品的旋光角应随时间递减，左旋样品应递增。
+/// 取符合趋势的最长子序列（等长时优先保留较早的点），不在其中的点即为逆趋势点，
+/// 因此单个离群点无论偏向哪一侧都只标记它自己。返回的第 i 项为 true 表示第 i 个点逆趋势
+pub fn trend_reversals(angles: &[f64], direction: SampleDirection) -> Vec<bool> {
+    let in_order = |earlier: f64, later: f64| match direction {
+        SampleDirection::Dextro => later <= earlier,
+        SampleDirection::Levo => later >= earlier,
+    };
+    let n = angles.len();
+    // longest[i]：从第 i 个点开始、符合趋势的最长子序列长度
+    let mut longest = vec![1usize; n];
+    for i in (0..n).rev() {
+        for j in i + 1..n {
+            if in_order(angles[i], angles[j]) {
+                longest[i] = longest[i].max(longest[j] + 1);
+            }
+        }
+    }
+    let mut reversed = vec![true; n];
+    let mut remaining = longest.iter().copied().max().unwrap_or(0);
+    let mut last: Option<usize> = None;
+    for i in 0..n {
+        if remaining == 0 {
+            break;
+        }
+        let follows = match last {
+            Some(l) => in_order(angles[l], angles[i]),
+            None => true,
+        };
+        if follows && longest[i] == remaining {
+            reversed[i] = false;
+            remaining -= 1;
+            last = Some(i);
+        }
+    }
+    reversed
+}
+
+/// 一级反应 α(t) = α∞ + (α0 − α∞)·e^(−kt) 的拟合结果，α0 为模型在 t = 0 处的值
+#[derive(Debug, Clone, PartialEq)]
+pub struct FirstOrderFit {
+    pub alpha_inf: f64,
+    pub alpha0: f64,
+    pub k: f64,
+    pub r2: f64,
+}
+
+// 搜索 k 时 k·(时间跨度) 的范围：低于下限时曲线近似直线，高于上限时几乎瞬间到达终点，都无法确定 α∞
+const FIRST_ORDER_KT_RANGE: (f64, f64) = (0.05, 50.0);
+const FIRST_ORDER_GRID: usize = 200;
+
+/// k 固定时模型对 α∞ 与 (α0 − α∞) 是线性的，直接最小二乘求解；返回 (α∞, 系数, 残差平方和)
+fn first_order_given_k(data: &[(f64, f64)], t0: f64, k: f64) -> Option<(f64, f64, f64)> {
+    let n = data.len() as f64;
+    let xs: Vec<f64> = data.iter().map(|&(t, _)| (-k * (t - t0)).exp()).collect();
+    let x_mean = xs.iter().sum::<f64>() / n;
+    let y_mean = data.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = xs.iter().map(|x| (x - x_mean).powi(2)).sum();
+    if sxx < 1e-12 {
+        return None;
+    }
+    let sxy: f64 = xs.iter().zip(data).map(|(x, p)| (x - x_mean) * (p.1 - y_mean)).sum();
+    let b = sxy / sxx;
+    let a = y_mean - b * x_mean;
+    let ssr = xs.iter().zip(data).map(|(x, p)| (p.1 - a - b * x).powi(2)).sum();
+    Some((a, b, ssr))
+}
+
+/// 对 (t, α) 做三参数一级反应拟合：先在 ln k 上做网格搜索，再用黄金分割细化。
+/// 有效点少于 3 个，或最优 k 落在搜索范围边缘（数据弯曲不足或变化过快）时无法估算 α∞
+pub fn fit_first_order(data: &[(f64, f64)]) -> Result<FirstOrderFit> {
+    if data.len() < 3 {
+        anyhow::bail!("至少需要 3 个有效点才能估算 α∞，当前只有 {} 个", data.len());
+    }
+    let t0 = data.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let span = data.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max) - t0;
+    if span < 1e-9 {
+        anyhow::bail!("所有点的时间相同，无法估算 α∞");
+    }
+    let y_mean = data.iter().map(|p| p.1).sum::<f64>() / data.len() as f64;
+    let sst: f64 = data.iter().map(|p| (p.1 - y_mean).powi(2)).sum();
+    if sst < 1e-12 {
+        anyhow::bail!("所有点的角度相同，无法估算 α∞");
+    }
+
+    let (lo, hi) = ((FIRST_ORDER_KT_RANGE.0 / span).ln(), (FIRST_ORDER_KT_RANGE.1 / span).ln());
+    let grid = |i: usize| lo + (hi - lo) * i as f64 / (FIRST_ORDER_GRID - 1) as f64;
+    let ssr_at = |ln_k: f64| first_order_given_k(data, t0, ln_k.exp()).map_or(f64::INFINITY, |r| r.2);
+    let best = (0..FIRST_ORDER_GRID)
+        .map(|i| (i, ssr_at(grid(i))))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(i, _)| i);
+    if best == 0 || best == FIRST_ORDER_GRID - 1 {
+        anyhow::bail!("数据的弯曲程度不足以确定 α∞，请测量更长时间后再估算");
+    }
+
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut a, mut b) = (grid(best - 1), grid(best + 1));
+    for _ in 0..60 {
+        let m1 = b - ratio * (b - a);
+        let m2 = a + ratio * (b - a);
+        if ssr_at(m1) < ssr_at(m2) {
+            b = m2;
+        } else {
+            a = m1;
+        }
+    }
+    let k = ((a + b) / 2.0).exp();
+    let (alpha_inf, coef, ssr) =
+        first_order_given_k(data, t0, k).ok_or_else(|| anyhow::anyhow!("拟合失败，无法估算 α∞"))?;
+    Ok(FirstOrderFit {
+        alpha_inf,
+        alpha0: alpha_inf + coef * (k * t0).exp(),
+        k,
+        r2: 1.0 - ssr / sst,
+    })
+}
+
+/// 文件中标为无效的点始终不参与拟合；开启剔除时逆趋势的点也不参与。
+/// 返回 (逆趋势标记, 剔除标记)，均与 raw_data 一一对应
+fn excluded_points(
+    raw_data: &[(f64, i32, f64, bool)],
+    file_valid: &[bool],
+    direction: SampleDirection,
+    exclude_reversals: bool,
+) -> (Vec<bool>, Vec<bool>) {
+    let file_valid = |i: usize| file_valid.get(i).copied().unwrap_or(true);
+    let kept: Vec<usize> = (0..raw_data.len()).filter(|&i| file_valid(i)).collect();
+    let kept_angles: Vec<f64> = kept.iter().map(|&i| raw_data[i].2).collect();
+    let mut reversals = vec![false; raw_data.len()];
+    for (&i, reversed) in kept.iter().zip(trend_reversals(&kept_angles, direction)) {
+        reversals[i] = reversed;
+    }
+    let excluded = (0..raw_data.len())
+        .map(|i| !file_valid(i) || (exclude_reversals && reversals[i]))
+        .collect();
+    (reversals, excluded)
+}
+
+/// 用当前参与拟合的点估算 α∞，结果写回状态
+pub fn estimate_alpha_inf(state: &mut BackendState) -> Result<FirstOrderFit> {
+    let dp_state = &mut state.data_processing;
+    let raw_data = dp_state.raw_data.as_deref().unwrap_or_default();
+    let (_, excluded) = excluded_points(
+        raw_data,
+        &dp_state.file_valid,
+        dp_state.sample_direction,
+        dp_state.exclude_trend_reversals,
+    );
+    let points: Vec<(f64, f64)> = raw_data
+        .iter()
+        .zip(&excluded)
+        .filter(|(_, excluded)| !**excluded)
+        .map(|(point, _)| (point.0, point.2))
+        .collect();
+    let fit = fit_first_order(&points)?;
+    dp_state.alpha_inf = Some(fit.alpha_inf);
+    Ok(fit)
+}
+
+pub fn recalculate_and_update(state: &mut BackendState, tx: &Sender<Update>) -> Result<()> {
+    let dp_state = &mut state.data_processing;
+    dp_state.plot_scatter_points.clear();
+    dp_state.plot_line_points.clear();
+    dp_state.regression_formula.clear();
+    dp_state.regression_summary.clear();
+    dp_state.regression_warning.clear();
+    dp_state.r_squared = f64::NAN;
+    // If there's no data, clear results and send an update
+    let Some(raw_data) = &mut dp_state.raw_data else {
+        // 没有数据，发送一个清空的状态
+        tx.send(Update::DataProcessing(DataProcessingUpdate::FullState(dp_state.clone().into())))?;
+        return Ok(());
+    };
+    if raw_data.is_empty() {
+        tx.send(Update::DataProcessing(DataProcessingUpdate::FullState(dp_state.clone().into())))?;
+        return Ok(());
+    }
+
+    // --- 1. 检查单调性，逆趋势的点通常是误识别或机械打滑；文件中标为无效的点不参与比较 ---
+    let exclude = dp_state.exclude_trend_reversals;
+    let (reversals, excluded_flags) =
+        excluded_points(raw_data, &dp_state.file_valid, dp_state.sample_direction, exclude);
+    let reversed: Vec<usize> = (0..reversals.len()).filter(|&i| reversals[i]).collect();
+    let excluded = |i: usize| excluded_flags[i];
+
+    // --- 2. 变换并拟合；尚未设置 α∞ 时按 0 预览 ---
+    let alpha_inf = dp_state.alpha_inf.unwrap_or(0.0);
+    let diffs: Vec<(f64, f64)> = raw_data
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !excluded(*i))
+        .map(|(_, point)| (point.0, point.2 - alpha_inf))
+        .collect();
+    let fit = fit_regression(&diffs, dp_state.regression_mode)?;
+    let mut fit_valid = fit.valid.iter();
+    for (i, point) in raw_data.iter_mut().enumerate() {
+        point.3 = if excluded(i) { false } else { *fit_valid.next().unwrap_or(&false) };
+    }
+    dp_state.plot_scatter_points = fit.points;
+    let mut warnings = Vec::new();
+    let marked_invalid = dp_state.file_valid.iter().filter(|valid| !**valid).count();
+    if marked_invalid > 0 {
+        warnings.push(format!("{} 个点在文件中标记为无效，未参与拟合", marked_invalid));
+    }
+    if !reversed.is_empty() {
+        let indices: Vec<String> = reversed.iter().map(|i| (i + 1).to_string()).collect();
+        warnings.push(format!(
+            "第 {} 个点逆着{}样品的预期趋势跳变，可能是误识别或机械打滑{}",
+            indices.join("、"),
+            match dp_state.sample_direction {
+                SampleDirection::Dextro => "右旋",
+                SampleDirection::Levo => "左旋",
+            },
+            if exclude { "，已从拟合中剔除" } else { "" }
+        ));
+    }
+    // 对数 / 倒数模式下 Δα ≤ 0 的点无法变换，通常是 α∞ 设得过大
+    let skipped = fit.valid.iter().filter(|v| !**v).count();
+    if skipped > 0 {
+        warnings.push(format!(
+            "{} 个点的 α - α∞ ≤ 0，已从拟合中剔除，请检查 α∞ 是否设置过大",
+            skipped
+        ));
+    }
+    dp_state.regression_warning = warnings.join("\n");
+
+    // --- 3. 更新回归方程与拟合直线 ---
+    if let Some(line) = fit.line {
+        dp_state.regression_formula = regression_formula(&line, dp_state.regression_mode);
+        dp_state.r_squared = line.r2;
+        dp_state.regression_summary = fit_summary(
+            &line,
+            dp_state.regression_mode,
+            alpha_inf,
+            dp_state.plot_scatter_points.len(),
+        );
+        dp_state.plot_line_points = line.endpoints;
+    }
+
+    // --- 4. 发送完整的、包含所有绘图数据的状态更新 ---
     tx.send(Update::DataProcessing(DataProcessingUpdate::FullState(dp_state.clone().into())))?;
 
 
     Ok(())
 }
+
+/// 从动态测量保存的 xlsx 中读出的一次运行
+pub struct LoadedRun {
+    pub name: String,
+    pub data: Vec<(f64, i32, f64)>,  // time, steps, angle
+    pub params: Vec<(String, String)>, // F/G 列中的实验参数
+    pub derived_angles: usize, // 角度列缺失、由步数换算得到角度的点数
+    pub valid: Vec<bool>, // 与 data 一一对应；旧文件没有有效性列，全部为 true
+    pub steps_per_degree: f64, // 换算角度所用的每度步数：文件中有记录时以文件为准
+    pub malformed: usize, // CSV 中无法解析、保留为无效点的行数
+    pub header_warning: Option<String>, // CSV 表头无法识别、按固定列读取时的提示
+}
+
+impl LoadedRun {
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+/// 列出工作簿中的全部工作表名称
+pub fn sheet_names(path: &Path) -> Result<Vec<String>> {
+    let workbook: calamine::Xlsx<_> = calamine::open_workbook(path)?;
+    Ok(workbook.sheet_names())
+}
+
+/// 由一行的时间、步数与角度得到数据点 (time, steps, angle)；
+/// 角度缺失时按每度步数由步数换算，第二项表示角度是否为换算所得
+fn data_point(
+    time: Option<f64>,
+    steps: Option<f64>,
+    angle: Option<f64>,
+    steps_per_degree: f64,
+) -> Option<((f64, i32, f64), bool)> {
+    let (time, steps) = (time?, steps?);
+    let steps = steps.round() as i32;
+    match angle {
+        Some(angle) => Some(((time, steps, angle), false)),
+        None if steps_per_degree > 0.0 => Some(((time, steps, steps as f64 / steps_per_degree), true)),
+        None => None,
+    }
+}
+
+/// 有效性单元格（valid 列，保存时全部写为 1）：手动改为 0 / false / 否 即可将该点排除在拟合之外，
+/// 空白或无法识别时视为有效
+fn cell_is_valid(cell: Option<&calamine::Data>) -> bool {
+    match cell {
+        Some(calamine::Data::Bool(b)) => *b,
+        Some(calamine::Data::Float(v)) => *v != 0.0,
+        Some(calamine::Data::Int(v)) => *v != 0,
+        Some(calamine::Data::String(s)) => text_is_valid(s),
+        _ => true,
+    }
+}
+
+fn text_is_valid(text: &str) -> bool {
+    !matches!(text.trim(), "0" | "false" | "FALSE" | "否" | "无效")
+}
+
+// 旧版文件固定的时间、步数、角度列（B、C、D）
+const DEFAULT_DATA_COLUMNS: [Option<usize>; 3] = [Some(1), Some(2), Some(3)];
+// 没有可识别表头的 CSV 按前三列（A、B、C）读取时间、步数、角度
+const CSV_DATA_COLUMNS: [Option<usize>; 3] = [Some(0), Some(1), Some(2)];
+
+/// 按表头（不区分大小写）查找时间、步数、角度列
+fn find_data_columns(header: &[String]) -> [Option<usize>; 3] {
+    let find = |column: ExportColumn| {
+        header.iter().position(|h| h.trim().eq_ignore_ascii_case(column.header()))
+    };
+    [find(ExportColumn::Time), find(ExportColumn::Steps), find(ExportColumn::Angle)]
+}
+
+/// 按表头定位时间、步数、角度列，导出列顺序可调；一列都找不到时按旧版固定列读取
+fn data_columns(header: &[String]) -> [Option<usize>; 3] {
+    let found = find_data_columns(header);
+    if found.iter().all(Option::is_none) {
+        DEFAULT_DATA_COLUMNS
+    } else {
+        found
+    }
+}
+
+/// 结果文件按扩展名区分格式：.csv 为 UTF-8 纯文本，其余按 xlsx 处理
+pub fn is_csv(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+/// 文件记录了保存时的每度步数时按它换算，保证与当时的角度列一致
+fn recorded_steps_per_degree(params: &[(String, String)], fallback: f64) -> f64 {
+    params
+        .iter()
+        .find(|(k, _)| k == "每度步数")
+        .and_then(|(_, v)| v.parse::<f64>().ok())
+        .filter(|v| v.is_finite() && *v > 0.0)
+        .unwrap_or(fallback)
+}
+
+/// 读取动态测量结果文件：数据列按表头定位，F/G 列为实验参数；.csv 文件交给 CSV 解析。
+/// `sheet` 为 None 时读取第一张工作表。只有时间和步数的文件按文件记录的每度步数换算角度，
+/// 没有记录时使用 `steps_per_degree`
+pub fn load_run(path: &Path, sheet: Option<&str>, steps_per_degree: f64) -> Result<LoadedRun> {
+    if is_csv(path) {
+        return load_run_csv(path, steps_per_degree);
+    }
+    let mut workbook: calamine::Xlsx<_> = calamine::open_workbook(path)?;
+    let range = match sheet {
+        Some(name) => workbook.worksheet_range(name)?,
+        None => workbook
+            .worksheet_range_at(0)
+            .ok_or_else(|| anyhow::anyhow!("{} 中没有工作表", path.display()))??,
+    };
+    let header: Vec<String> = range
+        .rows()
+        .next()
+        .map(|row| row.iter().map(|c| c.to_string()).collect())
+        .unwrap_or_default();
+    let [time_col, steps_col, angle_col] = data_columns(&header);
+    let valid_col = header.iter().position(|h| h.trim() == ExportColumn::Valid.header());
+    let cell = |row: &[calamine::Data], col: Option<usize>| {
+        col.and_then(|i| row.get(i)).and_then(|c| c.get_float())
+    };
+    let mut params = Vec::new();
+    for row in range.rows().skip(1) {
+        if let (Some(key), Some(value)) = (row.get(5), row.get(6)) {
+            if !key.is_empty() && !value.is_empty() {
+                params.push((key.to_string(), value.to_string()));
+            }
+        }
+    }
+    let steps_per_degree = recorded_steps_per_degree(&params, steps_per_degree);
+    let mut data = Vec::new();
+    let mut derived_angles = 0;
+    let mut valid = Vec::new();
+    for row in range.rows().skip(1) {
+        let time_opt = cell(row, time_col);
+        let steps_opt = cell(row, steps_col);
+        let angle_opt = cell(row, angle_col);
+        if let Some((point, derived)) = data_point(time_opt, steps_opt, angle_opt, steps_per_degree) {
+            data.push(point);
+            derived_angles += derived as usize;
+            valid.push(cell_is_valid(valid_col.and_then(|i| row.get(i))));
+        }
+    }
+    let name = file_name(path);
+    Ok(LoadedRun {
+        name,
+        data,
+        params,
+        derived_angles,
+        valid,
+        steps_per_degree,
+        malformed: 0,
+        header_warning: None,
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn load_run_csv(path: &Path, steps_per_degree: f64) -> Result<LoadedRun> {
+    let text = std::fs::read_to_string(path)?;
+    parse_run_csv(file_name(path), &text, steps_per_degree)
+}
+
+/// 解析 CSV 结果：首个非注释行为表头，按表头（不区分大小写）定位时间、步数、角度列；
+/// "# 标签: 值" 注释行为实验参数。表头一列都认不出时按 A、B、C 列读取并给出提示
+/// （首行本身就是数字时视为没有表头）；认出了部分列却缺少时间列或步数、角度两列时报错。
+/// 无法解析的行保留为无效点（缺失的数值记为 NaN），不中断加载，条数记在 `malformed` 中
+fn parse_run_csv(name: String, text: &str, steps_per_degree: f64) -> Result<LoadedRun> {
+    let mut params = Vec::new();
+    let mut rows = Vec::new();
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        if let Some(comment) = line.trim_start().strip_prefix('#') {
+            if let Some((key, value)) = comment.split_once(':') {
+                params.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        } else if !line.trim().is_empty() {
+            rows.push(line.split(',').map(str::trim).collect::<Vec<_>>());
+        }
+    }
+    let mut rows = rows.into_iter().peekable();
+    let header: Vec<String> = rows
+        .peek()
+        .map(|row| row.iter().map(|s| s.to_string()).collect())
+        .unwrap_or_default();
+    let found = find_data_columns(&header);
+    let mut header_warning = None;
+    let [time_col, steps_col, angle_col] = if found.iter().any(Option::is_some) {
+        if found[0].is_none() || (found[1].is_none() && found[2].is_none()) {
+            anyhow::bail!(
+                "CSV 表头“{}”缺少时间列或步数、角度列（应为 time、steps、angle）",
+                header.join(",")
+            );
+        }
+        rows.next();
+        found
+    } else {
+        let numeric = header.len() >= 3 && header[..3].iter().all(|h| h.parse::<f64>().is_ok());
+        if !numeric {
+            header_warning = Some(format!(
+                "未识别 CSV 表头“{}”，已按 A、B、C 列读取时间、步数、角度",
+                header.join(",")
+            ));
+            rows.next();
+        }
+        CSV_DATA_COLUMNS
+    };
+    let valid_col = header.iter().position(|h| h.trim() == ExportColumn::Valid.header());
+    let steps_per_degree = recorded_steps_per_degree(&params, steps_per_degree);
+
+    let mut data = Vec::new();
+    let mut valid = Vec::new();
+    let mut derived_angles = 0;
+    let mut malformed = 0;
+    for row in rows {
+        // 空白单元格为 None，有内容却不是数字时为 Err
+        let field = |col: Option<usize>| match col.and_then(|i| row.get(i)) {
+            Some(text) if !text.is_empty() => text.parse::<f64>().map(Some).map_err(|_| ()),
+            _ => Ok(None),
+        };
+        let (time, steps, angle) = (field(time_col), field(steps_col), field(angle_col));
+        let parsed = match (time, steps, angle) {
+            (Ok(time), Ok(steps), Ok(angle)) => data_point(time, steps, angle, steps_per_degree),
+            _ => None,
+        };
+        match parsed {
+            Some((point, derived)) => {
+                data.push(point);
+                derived_angles += derived as usize;
+                valid.push(valid_col.and_then(|i| row.get(i)).map(|t| text_is_valid(t)).unwrap_or(true));
+            }
+            None => {
+                let number = |value: Result<Option<f64>, ()>| value.ok().flatten();
+                data.push((
+                    number(time).unwrap_or(f64::NAN),
+                    number(steps).map_or(0, |s| s.round() as i32),
+                    number(angle).unwrap_or(f64::NAN),
+                ));
+                valid.push(false);
+                malformed += 1;
+            }
+        }
+    }
+    Ok(LoadedRun {
+        name,
+        data,
+        params,
+        derived_angles,
+        valid,
+        steps_per_degree,
+        malformed,
+        header_warning,
+    })
+}
+
+/// 由拟合斜率换算速率常数：lnΔα 与 Δα 随时间下降取负号，1/Δα 随时间上升取正号
+pub fn rate_constant(slope: f64, mode: RegressionMode) -> f64 {
+    match mode {
+        RegressionMode::Linear | RegressionMode::Log => -slope,
+        RegressionMode::Inverse => slope,
+    }
+}
+
+/// 由拟合直线估算半衰期：一级反应为 ln2/k，零级与二级反应以截距作为初始值
+pub fn half_life(line: &LinearFit, mode: RegressionMode) -> f64 {
+    let k = rate_constant(line.slope, mode);
+    match mode {
+        RegressionMode::Log => std::f64::consts::LN_2 / k,
+        RegressionMode::Linear => line.intercept / (2.0 * k),
+        RegressionMode::Inverse => line.intercept / k,
+    }
+}
+
+/// 图表上方显示的回归方程；一级反应（lnΔα - t）另给出速率常数与半衰期，时间单位与数据相同（秒）
+pub fn regression_formula(line: &LinearFit, mode: RegressionMode) -> String {
+    let sign = if line.intercept >= 0.0 { "+" } else { "-" };
+    let mut formula = format!(
+        "y = {:.4}x {} {:.4}\nR² = {:.6}",
+        line.slope,
+        sign,
+        line.intercept.abs(),
+        line.r2
+    );
+    // 残差标准误与变换后的 y 同单位，可用于比较同一拟合模式下不同数据的离散程度
+    if line.rse.is_finite() {
+        formula.push_str(&format!("\n残差标准误 = {:.4}", line.rse));
+    }
+    if mode == RegressionMode::Log {
+        let k = rate_constant(line.slope, mode);
+        if k > 0.0 {
+            formula.push_str(&format!(
+                "\nk = {:.4e} s⁻¹\nt½ = ln2/k = {:.1} s",
+                k,
+                half_life(line, mode)
+            ));
+        } else {
+            formula.push_str("\nk ≤ 0：lnΔα 未随时间下降，请检查 α∞ 与数据");
+        }
+    }
+    formula
+}
+
+pub fn mode_label(mode: RegressionMode) -> &'static str {
+    match mode {
+        RegressionMode::Linear => "Δα - t",
+        RegressionMode::Log => "lnΔα - t",
+        RegressionMode::Inverse => "1/Δα - t",
+    }
+}
+
+// 每行一项的纯文本结果，便于粘贴到笔记或表格
+fn fit_summary(line: &LinearFit, mode: RegressionMode, alpha_inf: f64, points: usize) -> String {
+    [
+        format!("拟合模式: {}", mode_label(mode)),
+        format!("斜率: {}", line.slope),
+        format!("截距: {}", line.intercept),
+        format!("k: {}", rate_constant(line.slope, mode)),
+        format!("t½: {}", half_life(line, mode)),
+        format!("R²: {}", line.r2),
+        format!("点数: {}", points),
+        format!("α∞: {}", alpha_inf),
+    ]
+    .join("\n")
+}
+
+// 工作表名不能超过 31 个字符，也不能含有 []:*?/\
+fn sheet_name(index: usize, name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .collect();
+    format!("{}_{}", index + 1, cleaned).chars().take(31).collect()
+}
+
+/// 把多次运行合并为一个工作簿：第一张为汇总表（k / R² / 实验条件），其后每次运行一张
+pub fn export_merged(
+    inputs: &[PathBuf],
+    output: &Path,
+    alpha_inf: f64,
+    mode: RegressionMode,
+    steps_per_degree: f64,
+) -> Result<usize> {
+    let runs = inputs
+        .iter()
+        .map(|path| load_run(path, None, steps_per_degree))
+        .collect::<Result<Vec<_>>>()?;
+    if runs.is_empty() {
+        anyhow::bail!("没有可合并的数据");
+    }
+
+    // CSV 中无法解析的行没有可用的数值，不写入合并结果
+    let usable = |run: &LoadedRun| -> Vec<(f64, i32, f64)> {
+        run.data.iter().copied().filter(|p| p.0.is_finite() && p.2.is_finite()).collect()
+    };
+
+    let bold = Format::new().set_bold();
+    let mut workbook = Workbook::new();
+
+    let summary = workbook.add_worksheet().set_name("汇总")?;
+    summary.write_string_with_format(0, 0, "拟合模式", &bold)?;
+    summary.write_string(0, 1, mode_label(mode))?;
+    summary.write_string_with_format(1, 0, "α∞", &bold)?;
+    summary.write_number(1, 1, alpha_inf)?;
+    let headers = [
+        "文件", "运行编号", "实验温度 (°C)", "蔗糖浓度 (g/mL)", "盐酸浓度 (mol/L)", "点数", "斜率", "截距", "k", "R²",
+    ];
+    for (col, header) in headers.iter().enumerate() {
+        summary.write_string_with_format(3, col as u16, *header, &bold)?;
+    }
+    for (i, run) in runs.iter().enumerate() {
+        let row = 4 + i as u32;
+        summary.write_string(row, 0, &run.name)?;
+        summary.write_string(row, 1, run.param("运行编号").unwrap_or(""))?;
+        // 旧版文件的浓度键不带单位
+        for (col, key, legacy) in [
+            (2, "实验温度 (°C)", "实验温度 (°C)"),
+            (3, "蔗糖浓度 (g/mL)", "蔗糖浓度"),
+            (4, "盐酸浓度 (mol/L)", "盐酸浓度"),
+        ] {
+            if let Some(value) = run.param(key).or_else(|| run.param(legacy)) {
+                match value.parse::<f64>() {
+                    Ok(number) => summary.write_number(row, col, number)?,
+                    Err(_) => summary.write_string(row, col, value)?,
+                };
+            }
+        }
+        let data = usable(run);
+        summary.write_number(row, 5, data.len() as u32)?;
+        let diffs: Vec<(f64, f64)> = data.iter().map(|p| (p.0, p.2 - alpha_inf)).collect();
+        if let Some(line) = fit_regression(&diffs, mode)?.line {
+            summary.write_number(row, 6, line.slope)?;
+            summary.write_number(row, 7, line.intercept)?;
+            summary.write_number(row, 8, rate_constant(line.slope, mode))?;
+            summary.write_number(row, 9, line.r2)?;
+        }
+    }
+
+    for (i, run) in runs.iter().enumerate() {
+        let sheet = workbook.add_worksheet().set_name(sheet_name(i, &run.name))?;
+        sheet.write_row(0, 0, ["time", "steps", "angle", "Δα = angle - α∞"])?;
+        for (j, &(time, steps, angle)) in usable(run).iter().enumerate() {
+            sheet.write_number(j as u32 + 1, 0, time)?;
+            sheet.write_number(j as u32 + 1, 1, steps)?;
+            sheet.write_number(j as u32 + 1, 2, angle)?;
+            sheet.write_number(j as u32 + 1, 3, angle - alpha_inf)?;
+        }
+        sheet.write_string_with_format(0, 4, "实验参数", &bold)?;
+        for (j, (key, value)) in run.params.iter().enumerate() {
+            sheet.write_string(j as u32 + 1, 4, key)?;
+            sheet.write_string(j as u32 + 1, 5, value)?;
+        }
+    }
+
+    workbook.save(output)?;
+    Ok(runs.len())
+}
+
+/// 可直接拟合的一行：时间、角度、Δα、ln(Δα)、1/Δα
+pub type FitReadyRow = (f64, f64, f64, f64, f64);
+
+/// 按当前 α∞ 计算各点的 Δα 及其变换，变换与拟合所用的 `transform` 一致；
+/// Δα ≤ 0 的点无法取对数和倒数，直接跳过，第二项为跳过的点数
+pub fn fit_ready_rows(results: &[DynamicResult], alpha_inf: f64) -> (Vec<FitReadyRow>, usize) {
+    let rows: Vec<FitReadyRow> = results
+        .iter()
+        .filter_map(|r| {
+            let angle = r.angle as f64;
+            let diff = angle - alpha_inf;
+            let ln = transform(diff, RegressionMode::Log)?;
+            let inverse = transform(diff, RegressionMode::Inverse)?;
+            Some((r.time, angle, diff, ln, inverse))
+        })
+        .collect();
+    let skipped = results.len() - rows.len();
+    (rows, skipped)
+}
+
+/// 导出可直接拟合的动态结果，返回 (写入点数, 跳过点数)
+pub fn export_fit_ready(
+    path: &Path,
+    results: &[DynamicResult],
+    alpha_inf: f64,
+) -> Result<(usize, usize)> {
+    let (rows, skipped) = fit_ready_rows(results, alpha_inf);
+    let bold = Format::new().set_bold();
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.write_row(0, 0, ["time", "angle", "Δα = angle - α∞", "ln(Δα)", "1/Δα"])?;
+    for (i, &(time, angle, diff, ln, inverse)) in rows.iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_row(row, 0, [time, angle, diff, ln, inverse])?;
+    }
+    sheet.write_string_with_format(0, 6, "α∞", &bold)?;
+    sheet.write_number(0, 7, alpha_inf)?;
+    sheet.write_string_with_format(1, 6, "跳过的点 (Δα ≤ 0)", &bold)?;
+    sheet.write_number(1, 7, skipped as u32)?;
+    workbook.save(path)?;
+    Ok((rows.len(), skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f64 = 1e-6;
+
+    #[test]
+    fn fit_ready_rows_skip_non_positive_delta() {
+        let result = |time: f64, angle: f32| DynamicResult { index: 0, time, steps: 0, angle };
+        let results = [result(0.0, 10.0), result(60.0, 2.0), result(120.0, 1.0)];
+        let (rows, skipped) = fit_ready_rows(&results, 2.0);
+        assert_eq!(skipped, 2);
+        assert_eq!(rows.len(), 1);
+        let (time, angle, diff, ln, inverse) = rows[0];
+        assert_eq!((time, angle, diff), (0.0, 10.0, 8.0));
+        assert!((ln - 8.0f64.ln()).abs() < EPS);
+        assert!((inverse - 0.125).abs() < EPS);
+    }
+
+    fn write_sheet(name: &str, rows: &[&[&str]]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("polarimeter_{}_{}.xlsx", name, std::process::id()));
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        for (r, row) in rows.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                match value.parse::<f64>() {
+                    Ok(number) => sheet.write_number(r as u32, c as u16, number),
+                    Err(_) => sheet.write_string(r as u32, c as u16, *value),
+                }
+                .unwrap();
+            }
+        }
+        workbook.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn load_run_defaults_legacy_points_to_valid() {
+        let path = write_sheet(
+            "legacy",
+            &[&["index", "time", "steps", "angle"], &["1", "0", "3730", "5"], &["2", "60", "3357", "4.5"]],
+        );
+        let run = load_run(&path, None, 746.0).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(run.data, vec![(0.0, 3730, 5.0), (60.0, 3357, 4.5)]);
+        assert_eq!(run.valid, vec![true, true]);
+    }
+
+    #[test]
+    fn load_run_honors_valid_column() {
+        let path = write_sheet(
+            "valid",
+            &[&["time", "steps", "angle", "valid"], &["0", "3730", "5", "1"], &["60", "3357", "4.5", "0"]],
+        );
+        let run = load_run(&path, None, 746.0).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(run.valid, vec![true, false]);
+    }
+
+    #[test]
+    fn csv_run_keeps_malformed_rows_as_invalid() {
+        let text = "\u{feff}# 每度步数: 800\n# 样品旋光方向: 左旋\nindex,time,steps,angle\n\
+                    1,0,4000,5\n2,60,abc,4.5\n\n3,120,3200,\n4,,3000,3.5\n";
+        let run = parse_run_csv("run".to_string(), text, 746.0).unwrap();
+        assert_eq!(run.steps_per_degree, 800.0);
+        assert_eq!(run.param("样品旋光方向"), Some("左旋"));
+        assert_eq!(run.malformed, 2);
+        assert_eq!(run.derived_angles, 1);
+        assert_eq!(run.valid, vec![true, false, true, false]);
+        assert_eq!(run.data[0], (0.0, 4000, 5.0));
+        // 步数无法解析的行保留时间与角度，角度缺失的行按每度步数换算
+        assert_eq!((run.data[1].0, run.data[1].2), (60.0, 4.5));
+        assert_eq!(run.data[2], (120.0, 3200, 4.0));
+        assert!(run.data[3].0.is_nan());
+    }
+
+    #[test]
+    fn csv_header_is_case_insensitive_and_falls_back_to_first_columns() {
+        let run = parse_run_csv("run".to_string(), "Time,Steps,Angle\n0,4000,5\n", 746.0).unwrap();
+        assert_eq!(run.data, vec![(0.0, 4000, 5.0)]);
+        assert!(run.header_warning.is_none());
+        // 认不出的表头按 A、B、C 列读取并给出提示
+        let run = parse_run_csv("run".to_string(), "t,s,a\n0,4000,5\n60,3800,4.5\n", 746.0).unwrap();
+        assert_eq!(run.data, vec![(0.0, 4000, 5.0), (60.0, 3800, 4.5)]);
+        assert!(run.header_warning.is_some());
+        // 没有表头时第一行也是数据
+        let run = parse_run_csv("run".to_string(), "0,4000,5\n60,3800,4.5\n", 746.0).unwrap();
+        assert_eq!(run.data.len(), 2);
+        assert!(run.header_warning.is_none());
+        // 认出部分列却缺少时间列时报错，不按错位的列读取
+        assert!(parse_run_csv("run".to_string(), "t,steps,angle\n0,4000,5\n", 746.0).is_err());
+    }
+
+    #[test]
+    fn first_order_fit_recovers_alpha_inf() {
+        let data: Vec<(f64, f64)> = (0..16)
+            .map(|i| {
+                let t = 60.0 + 20.0 * i as f64;
+                (t, -3.0 + 15.0 * (-0.004 * t).exp())
+            })
+            .collect();
+        let fit = fit_first_order(&data).unwrap();
+        assert!((fit.alpha_inf + 3.0).abs() < 1e-4);
+        assert!((fit.alpha0 - 12.0).abs() < 1e-3);
+        assert!((fit.k - 0.004).abs() < 1e-6);
+        assert!(fit.r2 > 0.999999);
+    }
+
+    #[test]
+    fn first_order_fit_rejects_too_few_or_straight_points() {
+        assert!(fit_first_order(&[(0.0, 10.0), (60.0, 8.0)]).is_err());
+        let straight: Vec<(f64, f64)> = (0..10).map(|i| (i as f64, 10.0 - i as f64)).collect();
+        assert!(fit_first_order(&straight).is_err());
+    }
+
+    #[test]
+    fn trend_reversals_flag_jumps_against_direction() {
+        let falling = [10.0, 8.0, 9.5, 7.0, 7.0, 6.0];
+        assert_eq!(
+            trend_reversals(&falling, SampleDirection::Dextro),
+            [false, false, true, false, false, false]
+        );
+        let rising = [-3.0, -2.0, -2.5, -1.0];
+        assert_eq!(
+            trend_reversals(&rising, SampleDirection::Levo),
+            [false, false, true, false]
+        );
+    }
+
+    #[test]
+    fn overshooting_outlier_flags_only_itself() {
+        // 顺着趋势过冲的单个离群点不能让其后的正常点都被判为逆趋势
+        let falling = [10.0, 9.0, 3.0, 8.0, 7.0, 6.0];
+        assert_eq!(
+            trend_reversals(&falling, SampleDirection::Dextro),
+            [false, false, true, false, false, false]
+        );
+        let rising = [-5.0, -4.0, 2.0, -3.0, -2.0];
+        assert_eq!(
+            trend_reversals(&rising, SampleDirection::Levo),
+            [false, false, true, false, false]
+        );
+    }
+
+    #[test]
+    fn data_columns_follow_header_order() {
+        let header = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            data_columns(&header(&["angle", "time", "index"])),
+            [Some(1), None, Some(0)]
+        );
+        assert_eq!(data_columns(&header(&["a", "b", "c", "d"])), DEFAULT_DATA_COLUMNS);
+    }
+
+    fn line(data: &[(f64, f64)], mode: RegressionMode) -> LinearFit {
+        fit_regression(data, mode).unwrap().line.expect("应能拟合出直线")
+    }
+
+    #[test]
+    fn linear_fit_recovers_exact_line() {
+        let data = [(0.0, 1.0), (1.0, 3.0), (2.0, 5.0), (3.0, 7.0)];
+        let fit = line(&data, RegressionMode::Linear);
+        assert!((fit.slope - 2.0).abs() < EPS);
+        assert!((fit.intercept - 1.0).abs() < EPS);
+        assert!((fit.r2 - 1.0).abs() < EPS);
+        assert_eq!(fit.endpoints.len(), 2);
+        assert!((fit.endpoints[1].1 - 7.0).abs() < EPS);
+    }
+
+    #[test]
+    fn log_fit_recovers_first_order_decay() {
+        // Δα = 2·e^(-0.5t)  =>  ln Δα = ln2 - 0.5t
+        let data: Vec<(f64, f64)> = (0..5)
+            .map(|t| (t as f64, 2.0 * (-0.5 * t as f64).exp()))
+            .collect();
+        let fit = line(&data, RegressionMode::Log);
+        assert!((fit.slope + 0.5).abs() < EPS);
+        assert!((fit.intercept - 2f64.ln()).abs() < EPS);
+        assert!((fit.r2 - 1.0).abs() < EPS);
+    }
+
+    #[test]
+    fn inverse_fit_recovers_second_order_decay() {
+        // 1/Δα = 1 + 2t
+        let data: Vec<(f64, f64)> = (0..5)
+            .map(|t| (t as f64, 1.0 / (1.0 + 2.0 * t as f64)))
+            .collect();
+        let fit = line(&data, RegressionMode::Inverse);
+        assert!((fit.slope - 2.0).abs() < EPS);
+        assert!((fit.intercept - 1.0).abs() < EPS);
+    }
+
+    #[test]
+    fn residual_standard_error_needs_three_points() {
+        let fit = line(&[(0.0, 0.0), (1.0, 1.0), (2.0, 4.0)], RegressionMode::Linear);
+        // 拟合 y = 2x - 1/3，残差 1/3、-2/3、1/3，SSR = 2/3，自由度 1
+        assert!((fit.rse - (2.0f64 / 3.0).sqrt()).abs() < EPS);
+        assert!(line(&[(0.0, 0.0), (1.0, 1.0)], RegressionMode::Linear).rse.is_nan());
+        assert!(regression_formula(&fit, RegressionMode::Linear).contains("残差标准误 = 0.8165"));
+    }
+
+    #[test]
+    fn noisy_data_has_r2_below_one() {
+        let data = [(0.0, 0.0), (1.0, 2.0), (2.0, 1.0), (3.0, 3.0)];
+        let fit = line(&data, RegressionMode::Linear);
+        assert!(fit.r2 < 1.0 && fit.r2 > 0.0);
+    }
+
+    #[test]
+    fn all_equal_angles_give_flat_line() {
+        let data = [(0.0, 4.0), (1.0, 4.0), (2.0, 4.0)];
+        let fit = line(&data, RegressionMode::Linear);
+        assert!(fit.slope.abs() < EPS);
+        assert!((fit.intercept - 4.0).abs() < EPS);
+        assert!((fit.r2 - 1.0).abs() < EPS);
+    }
+
+    #[test]
+    fn single_point_has_no_line() {
+        let fit = fit_regression(&[(1.0, 2.0)], RegressionMode::Linear).unwrap();
+        assert_eq!(fit.points, vec![(1.0, 2.0)]);
+        assert_eq!(fit.valid, vec![true]);
+        assert!(fit.line.is_none());
+    }
+
+    #[test]
+    fn empty_input_has_no_line() {
+        let fit = fit_regression(&[], RegressionMode::Log).unwrap();
+        assert!(fit.points.is_empty());
+        assert!(fit.line.is_none());
+    }
+
+    #[test]
+    fn non_positive_diff_is_invalid_in_log_and_inverse() {
+        let data = [(0.0, 2.0), (1.0, 0.0), (2.0, -1.0), (3.0, 0.5)];
+        for mode in [RegressionMode::Log, RegressionMode::Inverse] {
+            let fit = fit_regression(&data, mode).unwrap();
+            assert_eq!(fit.valid, vec![true, false, false, true]);
+            assert_eq!(fit.points.len(), 2);
+            assert!(fit.points.iter().all(|(_, y)| y.is_finite()));
+            assert!(fit.line.is_some());
+        }
+        // 线性模式下负值同样有效
+        let fit = fit_regression(&data, RegressionMode::Linear).unwrap();
+        assert!(fit.valid.iter().all(|&v| v));
+    }
+
+    #[test]
+    fn half_life_matches_reaction_order() {
+        // 一级：lnΔα = ln10 - 0.1t，t½ = ln2 / 0.1
+        let data: Vec<(f64, f64)> = (0..5).map(|i| (i as f64, 10.0 * (-0.1 * i as f64).exp())).collect();
+        let fit = line(&data, RegressionMode::Log);
+        assert!((half_life(&fit, RegressionMode::Log) - std::f64::consts::LN_2 / 0.1).abs() < 1e-4);
+        // 零级：Δα = 10 - 2t，t½ = 2.5
+        let data = [(0.0, 10.0), (1.0, 8.0), (2.0, 6.0)];
+        let fit = line(&data, RegressionMode::Linear);
+        assert!((half_life(&fit, RegressionMode::Linear) - 2.5).abs() < EPS);
+        // 二级：1/Δα = 0.1 + 0.05t，t½ = 1 / (0.05 × 10) = 2
+        let data: Vec<(f64, f64)> = (0..4).map(|i| (i as f64, 1.0 / (0.1 + 0.05 * i as f64))).collect();
+        let fit = line(&data, RegressionMode::Inverse);
+        assert!((half_life(&fit, RegressionMode::Inverse) - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn log_formula_reports_rate_constant_and_half_life() {
+        let data: Vec<(f64, f64)> = (0..5)
+            .map(|i| (60.0 * i as f64, 10.0 * (-0.002 * 60.0 * i as f64).exp()))
+            .collect();
+        let formula = regression_formula(&line(&data, RegressionMode::Log), RegressionMode::Log);
+        assert!(formula.contains("k = 2.0000e-3 s⁻¹"), "{}", formula);
+        assert!(formula.contains("t½ = ln2/k = 346.6 s"), "{}", formula);
+        // 其他模式只给出方程
+        let formula = regression_formula(&line(&data, RegressionMode::Linear), RegressionMode::Linear);
+        assert!(!formula.contains("k ="));
+    }
+
+    #[test]
+    fn data_point_derives_missing_angle_from_steps() {
+        assert_eq!(
+            data_point(Some(10.0), Some(1492.0), Some(2.5), 746.0),
+            Some(((10.0, 1492, 2.5), false))
+        );
+        assert_eq!(
+            data_point(Some(10.0), Some(1492.0), None, 746.0),
+            Some(((10.0, 1492, 2.0), true))
+        );
+        // 缺少时间或步数的行，以及每度步数无效时都跳过
+        assert_eq!(data_point(None, Some(1492.0), None, 746.0), None);
+        assert_eq!(data_point(Some(10.0), None, Some(2.5), 746.0), None);
+        assert_eq!(data_point(Some(10.0), Some(1492.0), None, 0.0), None);
+    }
+}
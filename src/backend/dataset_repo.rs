@@ -0,0 +1,152 @@
+// src/backend/dataset_repo.rs
+//
+// 多台旋光仪共用的常驻数据集：支持网络共享路径（UNC / 挂载盘）或 HTTP 压缩包，
+// 同步后缓存在本地，离线时直接使用缓存。
+
+use super::{Arc, BackendState, Mutex};
+use crate::communication::{GeneralUpdate, Update};
+use anyhow::{anyhow, Result};
+use crossbeam_channel::Sender;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+// 下载共享数据集时的连接与读取超时，网络不通时尽快回退到本地缓存
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 本地缓存目录：放在用户的应用数据目录下，重启后仍可离线使用
+fn cache_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("RustPolarimeter")
+        .join("shared_dataset")
+}
+
+fn is_http(source: &str) -> bool {
+    let lower = source.to_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// 下载 zip 压缩包并解压到 target
+fn download_archive(url: &str, target: &Path) -> Result<()> {
+    info!("正在下载共享数据集: {}", url);
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(CONNECT_TIMEOUT)
+        .timeout_read(READ_TIMEOUT)
+        .build();
+    let mut bytes = Vec::new();
+    agent
+        .get(url)
+        .call()?
+        .into_reader()
+        .read_to_end(&mut bytes)?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    archive.extract(target)?;
+    Ok(())
+}
+
+/// 将网络路径下的 dataset0 / dataset1 复制到 target
+fn copy_from_path(source: &Path, target: &Path) -> Result<()> {
+    info!("正在从共享路径复制数据集: {:?}", source);
+    for sub in ["dataset0", "dataset1"] {
+        let from = source.join(sub);
+        let to = target.join(sub);
+        std::fs::create_dir_all(&to)?;
+        for entry in std::fs::read_dir(&from)?.flatten() {
+            if entry.file_type()?.is_file() {
+                std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 压缩包可能多包了一层目录，找到包含 dataset0 的那一级
+fn find_dataset_root(dir: &Path) -> Option<PathBuf> {
+    if dir.join("dataset0").is_dir() {
+        return Some(dir.to_path_buf());
+    }
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| p.join("dataset0").is_dir())
+}
+
+/// 用同步好的 staging 替换缓存：旧缓存先改名留作备份，换入失败时恢复
+fn swap_in(staging: &Path, cache: &Path) -> Result<()> {
+    let backup = cache.with_extension("old");
+    if backup.exists() {
+        std::fs::remove_dir_all(&backup)?;
+    }
+    if cache.exists() {
+        std::fs::rename(cache, &backup)?;
+    }
+    if let Err(e) = std::fs::rename(staging, cache) {
+        if backup.exists() {
+            let _ = std::fs::rename(&backup, cache);
+        }
+        return Err(e.into());
+    }
+    if backup.exists() {
+        let _ = std::fs::remove_dir_all(&backup);
+    }
+    Ok(())
+}
+
+/// 缓存上次同步的时间，用于提示用户正在使用的是哪一份旧缓存
+fn cache_synced_at(cache: &Path) -> Option<String> {
+    let modified = std::fs::metadata(cache).ok()?.modified().ok()?;
+    let modified: chrono::DateTime<chrono::Local> = modified.into();
+    Some(modified.format("%Y-%m-%d %H:%M").to_string())
+}
+
+/// 同步共享数据集到本地缓存并加载为常驻数据集；同步失败时回退到已有缓存
+pub fn load_shared_dataset(
+    state: &Arc<Mutex<BackendState>>,
+    source: &str,
+    tx: &Sender<Update>,
+) -> Result<()> {
+    let cache = cache_dir();
+    let staging = cache.with_extension("staging");
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::fs::create_dir_all(&staging)?;
+
+    let synced = if is_http(source) {
+        download_archive(source, &staging)
+    } else {
+        copy_from_path(Path::new(source), &staging)
+    };
+
+    let stale = match synced.and_then(|()| swap_in(&staging, &cache)) {
+        Ok(()) => {
+            info!("共享数据集已同步到本地缓存 {:?}", cache);
+            None
+        }
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&staging);
+            warn!("同步共享数据集失败（{}），尝试使用本地缓存", e);
+            Some(e)
+        }
+    };
+
+    let root = find_dataset_root(&cache).ok_or_else(|| match &stale {
+        Some(e) => anyhow!("同步共享数据集失败（{}），且没有可用的本地缓存", e),
+        None => anyhow!("没有可用的共享数据集缓存"),
+    })?;
+    super::model::load_persistent_dataset(state, &root, tx)?;
+    if let Some(e) = stale {
+        let synced_at = cache_synced_at(&cache)
+            .map(|t| format!("（同步于 {}）", t))
+            .unwrap_or_default();
+        tx.send(Update::General(GeneralUpdate::StatusMessage(format!(
+            "同步共享数据集失败：{}。正在使用旧的本地缓存{}",
+            e, synced_at
+        ))))?;
+    }
+    Ok(())
+}
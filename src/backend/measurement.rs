@@ -1,12 +1,17 @@
-use super::model::predict_from_frame;
-use super::{Arc, BackendState, CancellationToken, Mutex};
+use super::data::is_csv;
+use super::model::{predict_from_frame, Prediction};
+use super::serial::SerialProtocol;
+use super::{record_event, Arc, BackendState, CancellationToken, Mutex};
 use crate::communication::*;
+use crate::settings::AppSettings;
 use anyhow::{anyhow, Result};
+use calamine::{DataType, Reader};
 use crossbeam_channel::Sender;
+use linfa_logistic::FittedLogisticRegression;
 use rust_xlsxwriter::{Format, Workbook, XlsxError};
 use std::io::{self, BufRead, BufReader};
-use std::path::PathBuf;
-use std::sync::atomic::Ordering;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 use std::{
     collections::VecDeque,
@@ -14,78 +19,255 @@ use std::{
 };
 use tracing::{error, info};
 
+// 测量中相机异常时告知前端的原因
+const CAMERA_GONE: &str = "测量中主相机已断开";
+const CAMERA_NO_FRAME: &str = "测量中相机没有返回画面，可能已被拔出或被其他程序占用";
+// 单次双向逼近（含找零点、预旋转）的超时时间
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(90);
+// 找零点进度的推送间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+// 高频循环中过程状态的最短推送间隔（约 10 Hz），结束与出错状态不受限制
+const STATUS_INTERVAL: Duration = Duration::from_millis(100);
+// 按记录的零点重新搜索时允许偏离的范围（度）
+const ZERO_RESEARCH_RANGE_DEG: f32 = 3.0;
+// 校验零点时距零点的最小距离（度），太靠近零点时视场本身处于翻转区，无法判定
+const ZERO_CHECK_OFFSET_DEG: f32 = 0.3;
+// 校验零点时参与多数表决的识别次数
+const ZERO_CHECK_SAMPLES: usize = 5;
+
 mod file_saver {
     use super::*;
 
-    pub fn save_static_results(path: &PathBuf, results: &[StaticResult]) -> Result<(), XlsxError> {
+    // 静态结果没有时间，按布局中其余列的顺序写入
+    fn static_columns(layout: &ExportLayout) -> Vec<ExportColumn> {
+        layout
+            .enabled()
+            .filter(|c| *c != ExportColumn::Time)
+            .collect()
+    }
+
+    fn dynamic_value(column: ExportColumn, result: &DynamicResult) -> f64 {
+        match column {
+            ExportColumn::Index => result.index as f64,
+            ExportColumn::Time => result.time,
+            ExportColumn::Steps => result.steps as f64,
+            ExportColumn::Angle => result.angle as f64,
+            ExportColumn::Valid => 1.0,
+        }
+    }
+
+    enum ParamValue {
+        Number(f32),
+        Text(String),
+    }
+
+    /// 动态实验的参数块：(xlsx 中的行号, 标签, 值)，行号与导入时读取的位置无关，只为保持旧版排布
+    fn dynamic_param_rows(
+        params: &DynamicExpParams,
+        run_id: Option<&str>,
+        steps_per_degree: f32,
+    ) -> Vec<(u32, &'static str, ParamValue)> {
+        use ParamValue::{Number, Text};
+        let mut rows = vec![
+            (2, "实验温度 (°C)", Number(params.temperature)),
+            // 浓度统一以标准单位保存，输入时所选单位另行记录
+            (3, "蔗糖浓度 (g/mL)", Number(params.sucrose_conc)),
+            (4, "盐酸浓度 (mol/L)", Number(params.hcl_conc)),
+            (5, "初始旋光角", Number(params.pre_rotation_angle)),
+            (6, "步进角", Number(params.step_angle)),
+            (7, "采样点数", Number(params.sample_points as f32)),
+            (
+                8,
+                "样品旋光方向",
+                Text(
+                    match params.sample_direction {
+                        SampleDirection::Dextro => "右旋",
+                        SampleDirection::Levo => "左旋",
+                    }
+                    .to_string(),
+                ),
+            ),
+        ];
+        match params.sampling_mode {
+            SamplingMode::PredictionFlip => rows.push((9, "采样方式", Text("按识别翻转".to_string()))),
+            SamplingMode::Timed => {
+                rows.push((9, "采样方式", Text("按时间".to_string())));
+                rows.push((10, "采样间隔 (s)", Number(params.sample_interval)));
+            }
+        }
+        let pre_rotation = if params.skip_pre_rotation {
+            "跳过"
+        } else {
+            params.pre_rotation_target.label()
+        };
+        rows.push((11, "预旋转", Text(pre_rotation.to_string())));
+        if let Some(run_id) = run_id {
+            rows.push((12, "运行编号", Text(run_id.to_string())));
+        }
+        rows.push((13, "蔗糖浓度输入单位", Text(params.sucrose_unit.label().to_string())));
+        rows.push((14, "盐酸浓度输入单位", Text(params.hcl_unit.label().to_string())));
+        rows.push((15, "最长运行时间 (min)", Number(params.max_duration_min as f32)));
+        if !params.notes.trim().is_empty() {
+            rows.push((17, "备注", Text(params.notes.trim().to_string())));
+        }
+        rows.push((18, "每度步数", Number(steps_per_degree)));
+        if params.sampling_mode == SamplingMode::PredictionFlip {
+            rows.push((19, "翻转复核延时 (ms)", Number(params.confirm_delay_ms as f32)));
+        }
+        rows
+    }
+
+    fn csv_header(columns: &[ExportColumn]) -> String {
+        let header: Vec<&str> = columns.iter().map(|c| c.header()).collect();
+        format!("{}\n", header.join(","))
+    }
+
+    pub fn save_static_results(
+        path: &PathBuf,
+        results: &[StaticResult],
+        layout: &ExportLayout,
+        steps_per_degree: f32,
+    ) -> Result<(), XlsxError> {
         let mut workbook = Workbook::new();
         let worksheet = workbook.add_worksheet();
-        worksheet.write_row(0, 0, ["index", "steps", "angle"])?;
-        for (i, result) in results.iter().enumerate() {
-            worksheet.write(i as u32 + 1, 0, result.index as i32)?;
-            worksheet.write(i as u32 + 1, 1, result.steps as i32)?;
-            worksheet.write(i as u32 + 1, 2, result.angle as f64)?;
+        for (col, column) in static_columns(layout).into_iter().enumerate() {
+            let col = col as u16;
+            worksheet.write_string(0, col, column.header())?;
+            for (i, result) in results.iter().enumerate() {
+                let row = i as u32 + 1;
+                match column {
+                    ExportColumn::Index => worksheet.write(row, col, result.index as i32)?,
+                    ExportColumn::Steps => worksheet.write(row, col, result.steps as i32)?,
+                    ExportColumn::Valid => worksheet.write(row, col, 1)?,
+                    _ => worksheet.write(row, col, result.angle as f64)?,
+                };
+            }
+        }
+        // 与动态结果相同，F/G 列写参数块，首行为标题；导入时从第二行起读取参数
+        if layout.include_params {
+            let bold_format = Format::new().set_bold();
+            worksheet.write_string_with_format(0, 5, "实验参数", &bold_format)?;
+            worksheet.write_string(1, 5, "每度步数")?;
+            worksheet.write_number(1, 6, steps_per_degree)?;
         }
         workbook.save(path)?;
         Ok(())
     }
 
+    /// 与 xlsx 相同的列，参数以 "# 标签: 值" 注释行写在表头之前
+    pub fn static_results_csv(
+        results: &[StaticResult],
+        layout: &ExportLayout,
+        steps_per_degree: f32,
+    ) -> String {
+        let columns = static_columns(layout);
+        let mut csv = String::new();
+        if layout.include_params {
+            csv.push_str(&format!("# 每度步数: {}\n", steps_per_degree));
+        }
+        csv.push_str(&csv_header(&columns));
+        for result in results {
+            let row: Vec<String> = columns
+                .iter()
+                .map(|column| match column {
+                    ExportColumn::Index => result.index.to_string(),
+                    ExportColumn::Steps => result.steps.to_string(),
+                    ExportColumn::Valid => "1".to_string(),
+                    _ => result.angle.to_string(),
+                })
+                .collect();
+            csv.push_str(&format!("{}\n", row.join(",")));
+        }
+        csv
+    }
+
     pub fn save_dynamic_results(
         path: &PathBuf,
         results: &[DynamicResult],
         params: &DynamicExpParams,
+        run_id: Option<&str>,
+        layout: &ExportLayout,
+        steps_per_degree: f32,
     ) -> Result<(), XlsxError> {
         let mut workbook = Workbook::new();
         let worksheet = workbook.add_worksheet();
-        worksheet.write_row(0, 0, ["index", "time", "steps", "angle"])?;
-        for (i, result) in results.iter().enumerate() {
-            worksheet.write_number(i as u32 + 1, 0, result.index as i32)?;
-            worksheet.write_number(i as u32 + 1, 1, result.time)?;
-            worksheet.write_number(i as u32 + 1, 2, result.steps as i32)?;
-            worksheet.write_number(i as u32 + 1, 3, result.angle as f64)?;
-        }
-        // --- 2. 在旁边写入实验参数信息 (新增代码) ---
-        // 定义参数写入的起始列 (E列留空作为分隔)
+        // 数据列按布局顺序从 A 列起写入，最多 5 列，不会覆盖 F/G 列的参数
+        for (col, column) in layout.enabled().enumerate() {
+            let col = col as u16;
+            worksheet.write_string(0, col, column.header())?;
+            for (i, result) in results.iter().enumerate() {
+                worksheet.write_number(i as u32 + 1, col, dynamic_value(column, result))?;
+            }
+        }
+        if !layout.include_params {
+            workbook.save(path)?;
+            return Ok(());
+        }
+        // 在旁边的 F/G 两列写入实验参数，E 列留空作为分隔
         let param_key_col = 5; // F列
         let param_value_col = 6; // G列
-
-        // 创建一个加粗格式用于标签
         let bold_format = Format::new().set_bold();
-
-        // 写入每一项参数，格式为 "标签: 值"
         worksheet.write_string_with_format(0, param_key_col, "实验参数", &bold_format)?;
-
-        worksheet.write_string(2, param_key_col, "实验温度 (°C)")?;
-        worksheet.write_number(2, param_value_col, params.temperature)?;
-
-        worksheet.write_string(3, param_key_col, "蔗糖浓度")?;
-        worksheet.write_number(3, param_value_col, params.sucrose_conc)?;
-
-        worksheet.write_string(4, param_key_col, "盐酸浓度")?;
-        worksheet.write_number(4, param_value_col, params.hcl_conc)?;
-
-        worksheet.write_string(5, param_key_col, "初始旋光角")?;
-        worksheet.write_number(5, param_value_col, params.pre_rotation_angle)?;
-
-        worksheet.write_string(6, param_key_col, "步进角")?;
-        worksheet.write_number(6, param_value_col, params.step_angle)?;
-
-        worksheet.write_string(7, param_key_col, "采样点数")?;
-        worksheet.write_number(7, param_value_col, params.sample_points)?;
-
-        // // --- 3. (可选但推荐) 调整列宽以获得更好的可读性 ---
-        // worksheet.set_column_width(0, 3, 12)?; // A-D列宽度
-        // worksheet.set_column_width(param_key_col, param_key_col, 15)?; // F列宽度
-        // worksheet.set_column_width(param_value_col, param_value_col, 15)?; // G列宽度
+        for (row, key, value) in dynamic_param_rows(params, run_id, steps_per_degree) {
+            worksheet.write_string(row, param_key_col, key)?;
+            match value {
+                ParamValue::Number(v) => worksheet.write_number(row, param_value_col, v)?,
+                ParamValue::Text(text) => worksheet.write_string(row, param_value_col, text)?,
+            };
+        }
 
         workbook.save(path)?;
         Ok(())
     }
+
+    /// 与 xlsx 相同的数据列，参数块以 "# 标签: 值" 注释行写在表头之前
+    pub fn dynamic_results_csv(
+        results: &[DynamicResult],
+        params: &DynamicExpParams,
+        run_id: Option<&str>,
+        layout: &ExportLayout,
+        steps_per_degree: f32,
+    ) -> String {
+        let columns: Vec<ExportColumn> = layout.enabled().collect();
+        let mut csv = String::new();
+        if layout.include_params {
+            for (_, key, value) in dynamic_param_rows(params, run_id, steps_per_degree) {
+                let value = match value {
+                    ParamValue::Number(v) => v.to_string(),
+                    // 备注可能含换行，注释行内改为空格
+                    ParamValue::Text(text) => text.replace(['\r', '\n'], " "),
+                };
+                csv.push_str(&format!("# {}: {}\n", key, value));
+            }
+        }
+        csv.push_str(&csv_header(&columns));
+        for result in results {
+            let row: Vec<String> = columns
+                .iter()
+                .map(|&column| match column {
+                    ExportColumn::Index => result.index.to_string(),
+                    ExportColumn::Steps => result.steps.to_string(),
+                    ExportColumn::Angle => result.angle.to_string(),
+                    _ => dynamic_value(column, result).to_string(),
+                })
+                .collect();
+            csv.push_str(&format!("{}\n", row.join(",")));
+        }
+        csv
+    }
 }
 
-pub fn cmd(port_arc: Arc<Mutex<Box<dyn serialport::SerialPort>>>, data: u8) -> Result<()> {
+/// 发送一个指令字节并返回原始应答（不做校验）
+pub fn cmd_raw(
+    port_arc: Arc<Mutex<Box<dyn serialport::SerialPort>>>,
+    data: u8,
+    protocol: &SerialProtocol,
+) -> Result<String> {
     let mut port = port_arc.lock();
     port.write_all(&[data])?;
+    if let Some(stop) = protocol.stop_byte {
+        port.write_all(&[stop])?;
+    }
     // thread::sleep(Duration::from_millis(10)); // 对应 python code 的 0.01s delay
     // port.write_all(&[100])?; // Stop command
     // info!("ok");
@@ -96,96 +278,171 @@ pub fn cmd(port_arc: Arc<Mutex<Box<dyn serialport::SerialPort>>>, data: u8) -> R
 
     // read_line 会阻塞，直到它从串口读取到换行符（0x0A）为止
     match reader.read_line(&mut response_buffer) {
-        Ok(_) => {
-            if response_buffer.trim() != "1" {
-                return Err(anyhow!("回复异常"));
-            }
-        }
+        Ok(_) => Ok(response_buffer),
         Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {
             // 如果发生超时，read_line 会返回错误
-            return Err(anyhow!("超时"));
+            Err(BackendError::Timeout("串口应答超时".to_string()).into())
         }
         Err(_e) => {
             // 其他读取错误
-            return Err(anyhow!("未知错误"));
+            Err(anyhow!("未知错误"))
         }
     }
+}
+
+pub fn cmd(
+    port_arc: Arc<Mutex<Box<dyn serialport::SerialPort>>>,
+    data: u8,
+    protocol: &SerialProtocol,
+) -> Result<()> {
+    let response = cmd_raw(port_arc, data, protocol)?;
+    if response.trim() != protocol.ack_token {
+        return Err(anyhow!("回复异常"));
+    }
     // info!("转起来了");
     Ok(())
 }
 
-/// `precision_rotate` 的 Rust 实现
-pub fn precision_rotate(
-    // port: &mut dyn serialport::SerialPort,
-    state: &Arc<Mutex<BackendState>>,
-    tx: &Sender<Update>,
-    steps: i32,
-) -> Result<()> {
+/// 读取保存的结果文件列布局，没有保存过时使用默认布局
+pub fn load_export_layout() -> ExportLayout {
+    AppSettings::load().export_layout
+}
+
+pub fn save_export_layout(layout: &ExportLayout) {
+    let layout = layout.clone();
+    if let Err(e) = AppSettings::update(|settings| settings.export_layout = layout) {
+        tracing::warn!("保存导出列设置失败：{}", e);
+    }
+}
+
+/// 累加实际转动的步数；模拟模式下不计入。保存由监控线程定期完成，见 odometer::flush
+fn add_odometer(s: &mut BackendState, steps: i32) {
+    if s.devices.simulation.is_none() {
+        s.devices.odometer += steps.unsigned_abs() as u64;
+    }
+}
+
+/// 精确旋转中的一段：同一个指令字节连续发送 count 次，每次使 current_steps 变化 delta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RotationChunk {
+    command: u8,
+    count: i32,
+    delta: i32,
+}
+
+const FORWARD_COMMANDS: [u8; 7] = [62, 60, 58, 56, 64, 66, 68]; // 正转指令
+const BACKWARD_COMMANDS: [u8; 7] = [63, 61, 59, 57, 65, 67, 69]; // 反转指令
+const STEP_DIVISORS: [i32; 7] = [3730, 746, 373, 75, 37, 7, 1];
+// 心跳 / 测试指令，不转动电机
+pub(super) const HEARTBEAT_COMMAND: u8 = 77;
+
+/// 把要旋转的步数拆成各档指令；need_reverse 时实际转向取反，但记录的步数变化仍等于 steps
+fn plan_rotation(steps: i32, need_reverse: bool) -> Vec<RotationChunk> {
     let mut steps = steps;
     let mut mul = 1;
-    if { state.lock().rotation_direction_need_reverse } {
+    if need_reverse {
         steps = -steps;
         mul = -1;
     }
-    if state.lock().measurement.isrotation == true {
-        return Ok(());
-    }
-    info!("旋转 {} 步", steps);
-    {
-        state.lock().measurement.isrotation = true;
-        tx.send(Update::Measurement(MeasurementUpdate::Rotation(true)))?;
-    }
     let commands = if steps > 0 {
-        vec![62, 60, 58, 56, 64, 66, 68] // 正转指令
+        FORWARD_COMMANDS
     } else {
         steps = -steps;
         mul = mul * -1;
-        vec![63, 61, 59, 57, 65, 67, 69] // 反转指令
+        BACKWARD_COMMANDS
     };
 
-    let divisors = [3730, 746, 373, 75, 37, 7, 1];
+    let mut plan = Vec::new();
+    for i in 0..STEP_DIVISORS.len() {
+        let count = steps / STEP_DIVISORS[i];
+        steps %= STEP_DIVISORS[i];
+        if count > 0 {
+            plan.push(RotationChunk {
+                command: commands[i],
+                count,
+                delta: STEP_DIVISORS[i] * mul,
+            });
+        }
+    }
+    plan
+}
+
+/// 执行完整个计划后 current_steps 的净变化
+fn plan_delta(plan: &[RotationChunk]) -> i32 {
+    plan.iter().map(|chunk| chunk.count * chunk.delta).sum()
+}
+
+/// `precision_rotate` 的 Rust 实现；每次旋转使用自己的令牌，只有急停会将其置位。
+/// 每发出一条串口指令前检查一次，被置位时停在当前位置并返回 `BackendError::Interrupted`，
+/// 已转动的步数照常计入当前位置。“停止当前任务”与停止录制不中断旋转，由任务在旋转结束后自行退出
+pub fn precision_rotate(
+    // port: &mut dyn serialport::SerialPort,
+    state: &Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
+    steps: i32,
+) -> Result<()> {
+    let token: CancellationToken = Arc::new(AtomicBool::new(false));
+    // 检查与占用在同一次加锁内完成：上一次旋转尚未结束时不能叠加新的旋转，
+    // 否则两路串口指令交错，调用方也会误以为已经到位
+    let need_reverse = {
+        let mut s = state.lock();
+        if s.measurement.isrotation {
+            return Err(BackendError::NotReady("电机正在旋转，请等待当前旋转结束".to_string()).into());
+        }
+        s.measurement.isrotation = true;
+        s.measurement.rotation_token = Some(token.clone());
+        tx.send(Update::Measurement(MeasurementUpdate::Rotation(true)))?;
+        s.rotation_direction_need_reverse
+    };
+    info!("旋转 {} 步", if need_reverse { -steps } else { steps });
 
-    for i in 0..divisors.len() {
-        let num_rotations = steps / divisors[i];
-        steps %= divisors[i];
-        for _ in 0..num_rotations {
+    for chunk in plan_rotation(steps, need_reverse) {
+        for _ in 0..chunk.count {
             let mut s = state.lock();
 
+            if token.load(Ordering::Relaxed) {
+                end_rotation(&mut s, tx)?;
+                drop(s);
+                tracing::warn!("旋转被用户中断");
+                return Err(BackendError::Interrupted.into());
+            }
             if s.devices.serial_port.is_none() {
                 tx.send(Update::Device(DeviceUpdate::SerialConnectionStatus(false)))?;
                 s.measurement.current_steps = None;
                 tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
                     s.measurement.current_steps,
                 )))?;
-                {
-                    s.measurement.isrotation = false;
-                    tx.send(Update::Measurement(MeasurementUpdate::Rotation(false)))?;
-                }
-                return Err(anyhow!("执行失败，请重新连接串口并找零点：串口断开"));
+                end_rotation(&mut s, tx)?;
+                return Err(BackendError::SerialDisconnected("串口断开".to_string()).into());
             }
             let port = s.devices.serial_port.as_mut().unwrap().clone();
+            let protocol = s.devices.serial_protocol.lock().clone();
             drop(s);
-            let res = cmd(port, commands[i]);
+            let res = cmd(port, chunk.command, &protocol);
             if let Err(e) = &res {
                 let mut s = state.lock();
+                let diagnostic = s.devices.ack_monitor.record(&res);
                 s.devices.serial_port = None;
                 tx.send(Update::Device(DeviceUpdate::SerialConnectionStatus(false)))?;
                 s.measurement.current_steps = None;
                 tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
                     s.measurement.current_steps,
                 )))?;
-                {
-                    s.measurement.isrotation = false;
-                    tx.send(Update::Measurement(MeasurementUpdate::Rotation(false)))?;
-                }
+                end_rotation(&mut s, tx)?;
                 //需要实现串口更新
                 error!("执行失败，请重新连接串口并找零点（{}）", e);
-                return Err(anyhow!("执行失败，请重新连接串口并找零点（{}）", e));
+                let message = diagnostic.map_or_else(|| e.to_string(), str::to_string);
+                return Err(BackendError::SerialDisconnected(message).into());
             } else {
                 let mut s = state.lock();
+                s.devices.ack_monitor.record(&res);
                 // info!("金杰活了");
                 s.measurement.current_steps =
-                    s.measurement.current_steps.map(|s| s + divisors[i] * mul);
+                    s.measurement.current_steps.map(|s| s + chunk.delta);
+                add_odometer(&mut s, chunk.delta);
+                if s.recording.cancellation_token.is_some() {
+                    s.recording.steps_moved += chunk.delta;
+                }
                 tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
                     s.measurement.current_steps,
                 )))?;
@@ -193,13 +450,22 @@ pub fn precision_rotate(
         }
     }
     info!("旋转完成");
-    {
-        state.lock().measurement.isrotation = false;
-        tx.send(Update::Measurement(MeasurementUpdate::Rotation(false)))?;
-    }
+    end_rotation(&mut state.lock(), tx)?;
+    Ok(())
+}
+
+fn end_rotation(s: &mut BackendState, tx: &Sender<Update>) -> Result<()> {
+    s.measurement.isrotation = false;
+    s.measurement.rotation_token = None;
+    tx.send(Update::Measurement(MeasurementUpdate::Rotation(false)))?;
     Ok(())
 }
 
+/// 错误是否来自用户中断旋转
+fn is_interrupted(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<BackendError>(), Some(BackendError::Interrupted))
+}
+
 pub fn precision_rotate_to(
     // port: &mut dyn serialport::SerialPort,
     state: &Arc<Mutex<BackendState>>,
@@ -223,6 +489,31 @@ pub fn precision_rotate_to(
     Ok(())
 }
 
+/// 急停：中断正在进行的旋转，并停止所有会转动电机的任务；动态测量结束后不再自动回零
+pub fn stop_all_motion(state: &Arc<Mutex<BackendState>>) {
+    let mut s = state.lock();
+    if s.measurement.dynamic_task_token.is_some() {
+        s.measurement.halted = true;
+    }
+    let tokens = [
+        &s.measurement.rotation_token,
+        &s.measurement.static_task_token,
+        &s.measurement.dynamic_task_token,
+        &s.recording.cancellation_token,
+    ];
+    let mut stopped = false;
+    for token in tokens.into_iter().flatten() {
+        token.store(true, Ordering::Relaxed);
+        stopped = true;
+    }
+    if stopped {
+        tracing::warn!("急停：已中断电机旋转与相关任务");
+    } else {
+        info!("急停：当前没有正在进行的旋转");
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum MoveMode {
     StepForward,
     ResetForward,
@@ -230,6 +521,226 @@ enum MoveMode {
     ResetBackward,
 }
 
+/// 双向逼近中每一步的判定结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MoveDecision {
+    /// 尚未越过零点，继续小步逼近
+    Step(MoveMode),
+    /// 已越过零点，回退一小段后记录位置
+    Crossed(MoveMode),
+}
+
+/// 将模型原始预测按“正值对应”设置换算为方向信号（统一为正值对应 MAM 时的含义），
+/// 换算后 Mam 表示当前位于零点正侧（零点在负方向），Ama 表示位于零点负侧，Unknown 不变
+fn direction_signal(prediction: Prediction, is_ama: bool) -> Prediction {
+    if is_ama {
+        prediction.flipped()
+    } else {
+        prediction
+    }
+}
+
+/// 逼近判定所用的识别窗口长度
+const DECISION_WINDOW: usize = 5;
+/// 判定越过零点所需的最少翻转次数（窗口 5 次中的多数）
+const CROSSING_MAJORITY: usize = 3;
+
+/// 尚无任何识别结果的窗口
+fn unknown_window(len: usize) -> VecDeque<Prediction> {
+    VecDeque::from(vec![Prediction::Unknown; len])
+}
+
+/// 窗口内全部为同一个已知信号时返回该信号，用于确认已位于零点某一侧后更新起始信号
+fn uniform_signal(window: &VecDeque<Prediction>) -> Option<Prediction> {
+    let first = *window.front()?;
+    (first != Prediction::Unknown && window.iter().all(|&p| p == first)).then_some(first)
+}
+/// 静态测量默认的迟滞余量：需要 4 次翻转才反向，避免零点附近来回抖动
+pub const DEFAULT_DECISION_MARGIN: usize = 1;
+
+/// 根据最近几次方向信号与起始信号决定下一步动作：
+/// 起始为 Ama 时向正方向步进，直到窗口中至少 3 + margin 次为 Mam 即视为越过零点；起始为 Mam 时相反。
+/// 继续当前方向无需任何共识，反向则要求比多数多出 margin 次，以此形成迟滞
+fn decide_move(window: &VecDeque<Prediction>, first: Prediction, margin: usize) -> MoveDecision {
+    let threshold = (CROSSING_MAJORITY + margin).min(window.len());
+    let amas = window.iter().filter(|&&x| x == Prediction::Ama).count();
+    let mams = window.iter().filter(|&&x| x == Prediction::Mam).count();
+    if amas >= threshold && first == Prediction::Mam {
+        MoveDecision::Crossed(MoveMode::ResetBackward)
+    } else if mams >= threshold && first == Prediction::Ama {
+        MoveDecision::Crossed(MoveMode::ResetForward)
+    } else if first == Prediction::Ama {
+        MoveDecision::Step(MoveMode::StepForward)
+    } else {
+        MoveDecision::Step(MoveMode::StepBackward)
+    }
+}
+
+/// 动态采样的触发判定：窗口中与起始信号相反的帧数达到 needed 即认为越过零点，
+/// 不要求连续，个别误判的帧不会让检测重新开始
+fn flip_consensus(window: &VecDeque<Prediction>, first: Prediction, needed: usize) -> bool {
+    first != Prediction::Unknown
+        && window
+            .iter()
+            .filter(|&&x| x != Prediction::Unknown && x != first)
+            .count()
+            >= needed
+}
+
+/// 双向逼近的逐帧判定状态：最近的方向信号窗口与本轮逼近的起始信号。
+/// 实际测量与离线回放都通过 `observe` 推进，两者的判定因此完全一致
+struct Approach {
+    window: VecDeque<Prediction>,
+    first: Prediction,
+    margin: usize,
+}
+
+impl Approach {
+    fn new(margin: usize) -> Self {
+        Self {
+            window: unknown_window(DECISION_WINDOW),
+            first: Prediction::Unknown,
+            margin,
+        }
+    }
+
+    /// 送入一帧方向信号并返回判定。Unknown 表示该帧没有识别结果，不进入窗口，返回 None；
+    /// 越过零点后清空窗口，从下一帧重新开始逼近，否则窗口一致时以该信号作为新的起始信号
+    fn observe(&mut self, signal: Prediction) -> Option<MoveDecision> {
+        if signal == Prediction::Unknown {
+            return None;
+        }
+        self.window.pop_front();
+        self.window.push_back(signal);
+        if self.first == Prediction::Unknown {
+            self.first = signal;
+        }
+        let decision = decide_move(&self.window, self.first, self.margin);
+        if let MoveDecision::Crossed(_) = decision {
+            *self = Self::new(self.margin);
+        } else if let Some(side) = uniform_signal(&self.window) {
+            self.first = side;
+        }
+        Some(decision)
+    }
+}
+
+/// 离线回放：把一串方向信号逐个送入与静态测量相同的判定状态，返回每个信号对应的判定
+fn replay_decisions(signals: &[Prediction], margin: usize) -> Vec<Option<MoveDecision>> {
+    let mut approach = Approach::new(margin);
+    signals.iter().map(|&signal| approach.observe(signal)).collect()
+}
+
+/// 读取预测值 CSV：取每行最后一列，开头无法解析的行视为表头，# 开头的行为注释
+fn parse_prediction_csv(text: &str) -> Result<Vec<Prediction>> {
+    let mut values = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let field = line.rsplit(',').next().unwrap_or_default().trim();
+        match field.parse::<usize>() {
+            Ok(value) if value <= 2 => values.push(Prediction::from_class(value)),
+            Ok(value) => {
+                return Err(anyhow!(
+                    "第 {} 行的预测值 {} 无效，只能是 0、1 或 2",
+                    line_no + 1,
+                    value
+                ))
+            }
+            Err(_) if values.is_empty() => continue,
+            Err(_) => return Err(anyhow!("第 {} 行无法解析：{}", line_no + 1, line)),
+        }
+    }
+    Ok(values)
+}
+
+/// 开发者功能：回放记录的模型原始预测（0 = MAM, 1 = AMA, 2 = 无结果），
+/// 按当前的“正值对应”与判定迟滞设置逐帧记录判定结果，无需硬件即可复现和检验逼近逻辑
+pub fn replay_predictions(
+    state: &Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
+    path: &Path,
+) -> Result<()> {
+    let raw = parse_prediction_csv(&std::fs::read_to_string(path)?)?;
+    if raw.is_empty() {
+        return Err(anyhow!("文件中没有预测值"));
+    }
+    let (is_ama, margin) = {
+        let s = state.lock();
+        (s.rotation_direction_is_ama, s.measurement.decision_margin)
+    };
+    let signals: Vec<Prediction> = raw.iter().map(|&p| direction_signal(p, is_ama)).collect();
+    info!("开始回放 {:?}，共 {} 帧，迟滞 {}", path, raw.len(), margin);
+    let mut crossings = 0;
+    for (i, (signal, decision)) in signals.iter().zip(replay_decisions(&signals, margin)).enumerate() {
+        match decision {
+            None => info!("回放第 {} 帧：无识别结果，跳过", i + 1),
+            Some(MoveDecision::Step(mode)) => {
+                info!("回放第 {} 帧：信号 {:?} → 继续逼近 {:?}", i + 1, signal, mode)
+            }
+            Some(MoveDecision::Crossed(mode)) => {
+                crossings += 1;
+                info!("回放第 {} 帧：信号 {:?} → 越过零点 {:?}", i + 1, signal, mode)
+            }
+        }
+    }
+    let summary = format!(
+        "预测回放完成：{} 帧，判定越过零点 {} 次（迟滞 {}）",
+        raw.len(),
+        crossings,
+        margin
+    );
+    info!("{}", summary);
+    tx.send(Update::General(GeneralUpdate::StatusMessage(summary)))?;
+    Ok(())
+}
+
+/// 逻辑动作对应的串口指令与 current_steps 的变化量；
+/// need_reverse 只交换发送的指令，不改变逻辑上的步数变化
+fn move_command(mode: MoveMode, need_reverse: bool) -> (u8, i32) {
+    if !need_reverse {
+        match mode {
+            MoveMode::StepForward => (51, 6),
+            MoveMode::StepBackward => (53, -6),
+            MoveMode::ResetForward => (114, -12),
+            MoveMode::ResetBackward => (55, 12),
+        }
+    } else {
+        match mode {
+            MoveMode::StepBackward => (51, -6),
+            MoveMode::StepForward => (53, 6),
+            MoveMode::ResetBackward => (114, 12),
+            MoveMode::ResetForward => (55, -12),
+        }
+    }
+}
+
+/// 单条指令对应的步数变化（不考虑方向反转），无法识别的字节返回 None；模拟串口据此更新步数
+pub(super) fn command_steps(byte: u8) -> Option<i32> {
+    if byte == HEARTBEAT_COMMAND {
+        return Some(0);
+    }
+    let rotation = FORWARD_COMMANDS.into_iter().zip(STEP_DIVISORS).chain(
+        BACKWARD_COMMANDS
+            .into_iter()
+            .zip(STEP_DIVISORS.map(|steps| -steps)),
+    );
+    let moves = [
+        MoveMode::StepForward,
+        MoveMode::StepBackward,
+        MoveMode::ResetForward,
+        MoveMode::ResetBackward,
+    ]
+    .into_iter()
+    .map(|mode| move_command(mode, false));
+    rotation
+        .chain(moves)
+        .find(|&(command, _)| command == byte)
+        .map(|(_, steps)| steps)
+}
+
 fn step_move(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>, mode: MoveMode) -> Result<()> {
     // let mut s = state.lock();
     let mut s = state.lock();
@@ -239,31 +750,17 @@ fn step_move(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>, mode: MoveMo
         tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
             s.measurement.current_steps,
         )))?;
-        return Err(anyhow!("执行失败，请重新连接串口并找零点：串口断开"));
+        return Err(BackendError::SerialDisconnected("串口断开".to_string()).into());
     }
     let port = s.devices.serial_port.as_mut().unwrap().clone();
+    let protocol = s.devices.serial_protocol.lock().clone();
     let need_reverse = s.rotation_direction_need_reverse;
     drop(s);
-    let (command, steps) = {
-        if !need_reverse {
-            match mode {
-                MoveMode::StepForward => (51, 6),
-                MoveMode::StepBackward => (53, -6),
-                MoveMode::ResetForward => (114, -12),
-                MoveMode::ResetBackward => (55, 12),
-            }
-        } else {
-            match mode {
-                MoveMode::StepBackward => (51, -6),
-                MoveMode::StepForward => (53, 6),
-                MoveMode::ResetBackward => (114, 12),
-                MoveMode::ResetForward => (55, -12),
-            }
-        }
-    };
-    let res = cmd(port, command);
+    let (command, steps) = move_command(mode, need_reverse);
+    let res = cmd(port, command, &protocol);
     if let Err(e) = &res {
         let mut s = state.lock();
+        let diagnostic = s.devices.ack_monitor.record(&res);
         s.devices.serial_port = None;
         tx.send(Update::Device(DeviceUpdate::SerialConnectionStatus(false)))?;
         s.measurement.current_steps = None;
@@ -271,10 +768,13 @@ fn step_move(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>, mode: MoveMo
             s.measurement.current_steps,
         )))?;
         error!("请重新连接串口并找零点：{}", e);
-        return Err(anyhow!("请重新连接串口并找零点：{}", e));
+        let message = diagnostic.map_or_else(|| e.to_string(), str::to_string);
+        return Err(BackendError::SerialDisconnected(message).into());
     }
     let mut s = state.lock();
+    s.devices.ack_monitor.record(&res);
     s.measurement.current_steps = s.measurement.current_steps.map(|s| s + steps);
+    add_odometer(&mut s, steps);
     tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
         s.measurement.current_steps,
     )))?;
@@ -287,8 +787,10 @@ pub fn static_measurement(
     token: CancellationToken,
     find_zero: bool,
     times: i32,
+    // 找零点时限制离起始位置的最大步数；超出即认为附近没有边界
+    search_range: Option<i32>,
 ) -> Result<()> {
-    // if state.lock().training.fitted_model.is_none() || state.lock().devices.camera_manager.is_none() || state.lock().devices.serial_port.is_none()
+    // if state.lock().training.fitted_model.is_none() || state.lock().devices.camera_manager().is_none() || state.lock().devices.serial_port.is_none()
     // {
     //     tx.send(Update::Measurement(MeasurementUpdate::StaticStatus("设备未就绪".to_string())))?;
     //     return;
@@ -298,19 +800,19 @@ pub fn static_measurement(
     {
         let mut s = state.lock();
         if s.training.fitted_model.is_none()
-            || s.devices.camera_manager.is_none()
+            || s.devices.camera_manager().is_none()
             || s.devices.serial_port.is_none()
         {
-            tx.send(Update::General(GeneralUpdate::Error(format!(
-                "设备或模型未就绪"
+            tx.send(Update::General(GeneralUpdate::Error(BackendError::NotReady(
+                "设备或模型未就绪".to_string(),
             ))))?;
             tx.send(Update::Measurement(MeasurementUpdate::StaticRunning(false)))?;
-            return Err(anyhow!("设备或模型未就绪"));
+            return Err(BackendError::NotReady("设备或模型未就绪".to_string()).into());
         }
         if s.measurement.dynamic_task_token.is_some() || s.measurement.static_task_token.is_some() {
-            tx.send(Update::General(GeneralUpdate::Error(format!(
-                "已经有测量任务在进行"
-            ))))?;
+            tx.send(Update::General(GeneralUpdate::Error(
+                BackendError::NotReady("已经有测量任务在进行".to_string()),
+            )))?;
             tx.send(Update::Measurement(MeasurementUpdate::StaticRunning(false)))?;
             return Err(anyhow!("已经有测量任务在进行"));
         }
@@ -327,13 +829,12 @@ pub fn static_measurement(
                 )))?;
                 return Err(anyhow!("测试被用户中断"));
             }
-            let mut predictions: VecDeque<usize> = VecDeque::from(vec![2; 5]);
-            let timeout = Duration::from_secs(90);
+            let timeout = SEARCH_TIMEOUT;
             let start_time = Instant::now();
-            let mut first = 2;
+            let mut last_progress: Option<Instant> = None;
             let mut result1: Option<i32> = None;
             let mut result2: Option<i32> = None;
-            let (model, isama) = {
+            let (model, isama, margin, normalization) = {
                 let mut s = state.lock();
                 if find_zero {
                     s.measurement.current_steps = Some(0); //临时值
@@ -341,10 +842,13 @@ pub fn static_measurement(
                 (
                     s.training.fitted_model.as_ref().unwrap().clone(),
                     s.rotation_direction_is_ama,
+                    s.measurement.decision_margin,
+                    s.training.model_normalization(),
                     // s.rotation_direction_need_reverse,
                 )
             };
-            let mut first_first = 2;
+            let mut approach = Approach::new(margin);
+            let mut first_first = Prediction::Unknown;
             loop {
                 let mut s = state.lock();
                 if start_time.elapsed() > timeout || token.load(Ordering::Relaxed) {
@@ -356,16 +860,35 @@ pub fn static_measurement(
                     )))?;
                     return Err(anyhow!("测试中断"));
                 }
-                if s.devices.camera_manager.is_none() {
-                    s.devices.camera_manager = None;
-                    tx.send(Update::Device(DeviceUpdate::CameraConnectionStatus(false)))?;
+                if let (Some(range), Some(steps)) = (search_range, s.measurement.current_steps) {
+                    if steps.abs() > range {
+                        return Err(anyhow!(
+                            "在预期零点附近 ±{:.1}° 内未找到边界",
+                            range as f32 / s.devices.angle_steps
+                        ));
+                    }
+                }
+                if find_zero
+                    && last_progress.map_or(true, |t| t.elapsed() >= PROGRESS_INTERVAL)
+                {
+                    last_progress = Some(Instant::now());
+                    let elapsed = start_time.elapsed();
+                    tx.send(Update::Measurement(MeasurementUpdate::ZeroSearchProgress(
+                        Some((
+                            elapsed.as_secs_f32(),
+                            timeout.saturating_sub(elapsed).as_secs_f32(),
+                        )),
+                    )))?;
+                }
+                if s.devices.camera_manager().is_none() {
+                    s.devices.drop_primary_camera(tx);
+                    tx.send(super::camera::lost_status(CAMERA_GONE))?;
                     info!("相机异常");
-                    return Err(anyhow!("相机异常"));
+                    return Err(BackendError::CameraLost.into());
                 }
                 let frame = {
                     s.devices
-                        .camera_manager
-                        .as_ref()
+                        .camera_manager()
                         .unwrap()
                         .latest_frame
                         .lock()
@@ -375,14 +898,14 @@ pub fn static_measurement(
                 let frame = match frame {
                     Some(f) => f,
                     None => {
-                        s.devices.camera_manager = None;
-                        tx.send(Update::Device(DeviceUpdate::CameraConnectionStatus(false)))?;
+                        s.devices.drop_primary_camera(tx);
+                        tx.send(super::camera::lost_status(CAMERA_NO_FRAME))?;
                         info!("相机异常");
-                        return Err(anyhow!("相机异常"));
+                        return Err(BackendError::CameraLost.into());
                     }
                 };
 
-                let guard2 = s.devices.camera_settings.lock();
+                let guard2 = s.devices.camera_settings().lock();
                 let circle = {
                     if guard2.lock_circle {
                         guard2.locked_circle
@@ -395,64 +918,43 @@ pub fn static_measurement(
                 drop(guard2);
                 drop(s);
                 let prediction =
-                    match predict_from_frame(&frame, &model, min_radius, max_radius, circle) {
+                    match predict_from_frame(&frame, &model, min_radius, max_radius, circle, normalization) {
                         Ok(p) => p,
                         Err(_) => continue,
                     };
-                let prediction = prediction ^ (isama as usize);
-
-                predictions.pop_front();
-                predictions.push_back(prediction);
-                // info!("预测结果：{:?}", predictions);
+                let prediction = direction_signal(prediction, isama);
+                let Some(decision) = approach.observe(prediction) else {
+                    continue;
+                };
                 let mut should_break = false;
-                let mut pp = predictions.clone();
-                let pred_slice = pp.make_contiguous();
-                if first == 2 {
-                    first = prediction;
-                }
-                if first_first == 2 {
+                if first_first == Prediction::Unknown {
                     first_first = prediction;
-                    if prediction == 0 {
-                        precision_rotate(state, tx,anglesteps.round() as i32)?;
+                    if prediction == Prediction::Mam {
+                        precision_rotate(state, tx, anglesteps.round() as i32)?;
                     } else {
                         precision_rotate(state, tx, -anglesteps.round() as i32)?;
                     }
                 }
                 // thread::sleep(Duration::from_millis(500));(- = 1 0)
 
-                if predictions.iter().filter(|&x| *x == 1).count() >= 3 && first == 0 {
-                    step_move(state, tx, MoveMode::ResetBackward)?;
-                    if result1.is_none() {
-                        result1 = Some(state.lock().measurement.current_steps.unwrap());
-                        first = 2;
-                        predictions = VecDeque::from(vec![2; 5]);
-                        precision_rotate(state, tx, -700)?;
-                    } else {
-                        result2 = Some(state.lock().measurement.current_steps.unwrap());
-                        should_break = true;
+                match decision {
+                    MoveDecision::Crossed(mode) => {
+                        step_move(state, tx, mode)?;
+                        if result1.is_none() {
+                            result1 = Some(state.lock().measurement.current_steps.unwrap());
+                            // 退回到零点另一侧，从反方向再逼近一次
+                            let back = if mode == MoveMode::ResetBackward { -700 } else { 700 };
+                            precision_rotate(state, tx, back)?;
+                        } else {
+                            result2 = Some(state.lock().measurement.current_steps.unwrap());
+                            should_break = true;
+                        }
+                        thread::sleep(Duration::from_millis(150));
                     }
-                    thread::sleep(Duration::from_millis(150));
-                } else if predictions.iter().filter(|&x| *x == 0).count() >= 3 && first == 1 {
-                    step_move(state, tx, MoveMode::ResetForward)?;
-                    if result1.is_none() {
-                        result1 = Some(state.lock().measurement.current_steps.unwrap());
-                        first = 2;
-                        predictions = VecDeque::from(vec![2; 5]);
-                        precision_rotate(state, tx, 700)?;
-                    } else {
-                        result2 = Some(state.lock().measurement.current_steps.unwrap());
-                        should_break = true;
+                    MoveDecision::Step(mode) => {
+                        step_move(state, tx, mode)?;
+                        thread::sleep(Duration::from_millis(5));
                     }
-                    thread::sleep(Duration::from_millis(150));
-                } else if first == 1 {
-                    step_move(state, tx, MoveMode::StepForward)?;
-
-                    // should_break=true;
-                    thread::sleep(Duration::from_millis(5));
-                } else {
-                    step_move(state, tx, MoveMode::StepBackward)?;
-                    // should_break=true;
-                    thread::sleep(Duration::from_millis(5));
                 }
                 if !find_zero {
                     tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
@@ -462,20 +964,11 @@ pub fn static_measurement(
                 if should_break {
                     break;
                 }
-                if pred_slice == [0, 0, 0, 0, 0] {
-                    first = 0;
-                }
-                if pred_slice == [1, 1, 1, 1, 1] {
-                    first = 1;
-                }
             }
             if result1.is_some() && result2.is_some() {
-                let st = { state.lock().measurement.current_steps.unwrap() };
-                precision_rotate(
-                    state,
-                    tx,
-                    ((((result1.unwrap() + result2.unwrap()) as f64) / 2.0).round() as i32) - st,
-                )?;
+                let target =
+                    (((result1.unwrap() + result2.unwrap()) as f64) / 2.0).round() as i32;
+                drive_to(state, tx, target)?;
                 if !find_zero {
                     let mut s = state.lock();
                     let result = StaticResult {
@@ -483,6 +976,13 @@ pub fn static_measurement(
                         steps: s.measurement.current_steps.unwrap(),
                         angle: s.measurement.current_steps.unwrap() as f32 / s.devices.angle_steps,
                     };
+                    record_event(
+                        tx,
+                        format!(
+                            "静态测量第 {} 次：{} 步，{:.3}°",
+                            result.index, result.steps, result.angle
+                        ),
+                    );
                     s.measurement.static_results.push(result);
 
                     tx.send(Update::Measurement(MeasurementUpdate::StaticResults(
@@ -495,30 +995,71 @@ pub fn static_measurement(
         }
         Ok(())
     })();
-    let mut s = state.lock();
-    if let Err(e) = &result {
-        if find_zero {
-            s.measurement.current_steps = None;
+    {
+        let mut s = state.lock();
+        if let Err(e) = &result {
+            // 有范围限制的重新搜索从原零点出发，失败时原零点仍然有效
+            if find_zero && search_range.is_none() {
+                s.measurement.current_steps = None;
+            }
+            info!("静态测量失败：{}", e);
+        } else {
+            if find_zero {
+                let raw_steps = s.measurement.current_steps.unwrap_or(0);
+                record_event(tx, format!("零点已找到（距起始位置 {} 步）", raw_steps));
+                s.measurement.current_steps = Some(0);
+                establish_zero(&mut s, tx, raw_steps)?;
+            }
+
+            info!("静态测量完成");
         }
-        info!("静态测量失败：{}", e);
-    } else {
-        if find_zero {
-            s.measurement.current_steps = Some(0);
+    }
+    // 新零点确立后在零点旁读一次视场，确认 0 步确实对应消光位置
+    if find_zero && result.is_ok() {
+        if let Err(e) = verify_zero(state, tx) {
+            let message = format!("零点已确立，但随后的校验未能完成：{}", e);
+            tracing::warn!("{}", message);
+            tx.send(Update::General(GeneralUpdate::Error(message.into())))?;
         }
-
-        info!("静态测量完成");
     }
+    let mut s = state.lock();
     tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
         s.measurement.current_steps,
     )))?;
     s.measurement.static_task_token = None;
     tx.send(Update::Measurement(MeasurementUpdate::StaticRunning(false)))?;
+    if find_zero {
+        tx.send(Update::Measurement(MeasurementUpdate::ZeroSearchProgress(None)))?;
+    }
     // tx.send(Update::Measurement(MeasurementUpdate::StaticStatus(
     //     "测量完成".to_string(),
     // )))?;
     result
 }
 
+/// 转到指定的步数位置
+fn drive_to(
+    state: &Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
+    target: i32,
+) -> Result<()> {
+    let current = state
+        .lock()
+        .measurement
+        .current_steps
+        .ok_or_else(|| anyhow!("当前位置未知"))?;
+    precision_rotate(state, tx, target - current)
+}
+
+/// 预旋转越过交界时是否满足对齐判据；`pattern` 为越过前视场呈现的图样（模型原始预测）
+fn crossing_accepted(target: PreRotationTarget, pattern: Prediction) -> bool {
+    match target {
+        PreRotationTarget::AnyCrossing => true,
+        PreRotationTarget::FromMam => pattern == Prediction::Mam,
+        PreRotationTarget::FromAma => pattern == Prediction::Ama,
+    }
+}
+
 pub fn pre_rotation(
     state: &Arc<Mutex<BackendState>>,
     tx: &Sender<Update>,
@@ -529,41 +1070,50 @@ pub fn pre_rotation(
         {
             let s = state.lock();
             if s.training.fitted_model.is_none()
-                || s.devices.camera_manager.is_none()
+                || s.devices.camera_manager().is_none()
                 || s.devices.serial_port.is_none()
             {
-                return Err(anyhow!("设备或模型未就绪"));
+                return Err(BackendError::NotReady("设备或模型未就绪".to_string()).into());
             }
         }
 
-        let mut predictions: VecDeque<usize> = VecDeque::from(vec![2; 5]);
-        let timeout = Duration::from_secs(90);
+        let mut predictions = unknown_window(DECISION_WINDOW);
+        let timeout = SEARCH_TIMEOUT;
         let start_time = Instant::now();
-        let mut first = 2;
-        let (model, isama) = {
+        let mut first = Prediction::Unknown;
+        let mut last_status: Option<Instant> = None;
+        let (model, isama, normalization, target) = {
             let s = state.lock();
             (
                 s.training.fitted_model.as_ref().unwrap().clone(),
                 s.rotation_direction_is_ama,
+                s.training.model_normalization(),
+                s.measurement.dynamic_params.pre_rotation_target,
                 // s.rotation_direction_need_reverse,
             )
         };
         loop {
-            let s = state.lock();
-            if start_time.elapsed() > timeout || token.load(Ordering::Relaxed) {
-                return Err(anyhow!("超时或被终止"));
+            if token.load(Ordering::Relaxed) {
+                return Err(anyhow!("预旋转已被终止"));
             }
-            if s.devices.camera_manager.is_none() {
+            if start_time.elapsed() > timeout {
+                return Err(BackendError::Timeout(format!(
+                    "预旋转超时：{} 秒内未找到交界",
+                    timeout.as_secs()
+                ))
+                .into());
+            }
+            let s = state.lock();
+            if s.devices.camera_manager().is_none() {
                 tx.send(Update::Measurement(MeasurementUpdate::DynamicStatus(
                     format!("相机异常"),
                 )))?;
-                tx.send(Update::Device(DeviceUpdate::CameraConnectionStatus(false)))?;
-                return Err(anyhow!("相机异常"));
+                tx.send(super::camera::lost_status(CAMERA_GONE))?;
+                return Err(BackendError::CameraLost.into());
             }
             let frame = {
                 s.devices
-                    .camera_manager
-                    .as_ref()
+                    .camera_manager()
                     .unwrap()
                     .latest_frame
                     .lock()
@@ -575,15 +1125,15 @@ pub fn pre_rotation(
                     tx.send(Update::Measurement(MeasurementUpdate::DynamicStatus(
                         format!("相机异常"),
                     )))?;
-                    tx.send(Update::Device(DeviceUpdate::CameraConnectionStatus(false)))?;
+                    tx.send(super::camera::lost_status(CAMERA_NO_FRAME))?;
                     tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
                         s.measurement.current_steps,
                     )))?;
-                    return Err(anyhow!("相机异常"));
+                    return Err(BackendError::CameraLost.into());
                 }
             };
 
-            let guard2 = s.devices.camera_settings.lock();
+            let guard2 = s.devices.camera_settings().lock();
             let circle = {
                 if guard2.lock_circle {
                     guard2.locked_circle
@@ -596,40 +1146,47 @@ pub fn pre_rotation(
             drop(guard2);
             drop(s);
             let prediction =
-                match predict_from_frame(&frame, &model, min_radius, max_radius, circle) {
+                match predict_from_frame(&frame, &model, min_radius, max_radius, circle, normalization) {
                     Ok(p) => p,
                     Err(_) => continue,
                 };
-            let prediction = prediction ^ (isama as usize);
+            let prediction = direction_signal(prediction, isama);
 
             predictions.pop_front();
             predictions.push_back(prediction);
             // info!("预测结果：{:?}", predictions);
             let mut should_break = false;
-            tx.send(Update::Measurement(MeasurementUpdate::DynamicStatus(
-                format!("预旋转中: {:?}", predictions),
-            )))?;
-            let mut pp = predictions.clone();
-            let pred_slice = pp.make_contiguous();
-            if first == 2 {
+            // 每帧都发送会刷屏，状态栏也来不及看清
+            if last_status.map_or(true, |t| t.elapsed() >= STATUS_INTERVAL) {
+                last_status = Some(Instant::now());
+                tx.send(Update::Measurement(MeasurementUpdate::DynamicStatus(
+                    format!("预旋转中: {:?}", predictions),
+                )))?;
+            }
+            let uniform = uniform_signal(&predictions);
+            if first == Prediction::Unknown {
                 first = prediction;
             }
             // thread::sleep(Duration::from_millis(500));(- = 1 0)
 
-            if predictions.iter().filter(|&x| *x == 1).count() >= 3 && first == 0 {
-                step_move(state, tx, MoveMode::ResetBackward)?;
-                should_break = true;
-                thread::sleep(Duration::from_millis(150));
-            } else if predictions.iter().filter(|&x| *x == 0).count() >= 3 && first == 1 {
-                step_move(state, tx, MoveMode::ResetForward)?;
-                should_break = true;
-                thread::sleep(Duration::from_millis(150));
-            } else if first == 1 {
-                step_move(state, tx, MoveMode::StepForward)?;
-                thread::sleep(Duration::from_millis(5));
-            } else {
-                step_move(state, tx, MoveMode::StepBackward)?;
-                thread::sleep(Duration::from_millis(5));
+            // 预旋转与动态采样沿用原有的多数判定，不加迟滞
+            match decide_move(&predictions, first, 0) {
+                MoveDecision::Crossed(mode) if crossing_accepted(target, direction_signal(first, isama)) => {
+                    step_move(state, tx, mode)?;
+                    should_break = true;
+                    thread::sleep(Duration::from_millis(150));
+                }
+                MoveDecision::Crossed(_) => {
+                    // 从另一侧越过了交界：不回退，清空窗口后从目标图样一侧折返重新逼近
+                    info!("预旋转：越过交界的方向不符合对齐判据，折返重新逼近");
+                    predictions = unknown_window(DECISION_WINDOW);
+                    first = Prediction::Unknown;
+                    continue;
+                }
+                MoveDecision::Step(mode) => {
+                    step_move(state, tx, mode)?;
+                    thread::sleep(Duration::from_millis(5));
+                }
             }
             tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
                 state.lock().measurement.current_steps,
@@ -637,11 +1194,8 @@ pub fn pre_rotation(
             if should_break {
                 return Ok(());
             }
-            if pred_slice == [0, 0, 0, 0, 0] {
-                first = 0;
-            }
-            if pred_slice == [1, 1, 1, 1, 1] {
-                first = 1;
+            if let Some(side) = uniform {
+                first = side;
             }
         }
     })();
@@ -664,33 +1218,35 @@ pub fn run_dynamic_experiment_loop(
     tx: &Sender<Update>,
     token: CancellationToken,
 ) -> Result<()> {
-    let (isama, model) = {
+    let (isama, model, normalization) = {
         let mut s = state.lock();
         if s.training.fitted_model.is_none()
-            || s.devices.camera_manager.is_none()
+            || s.devices.camera_manager().is_none()
             || s.devices.serial_port.is_none()
         {
-            tx.send(Update::General(GeneralUpdate::Error(format!(
-                "设备或模型未就绪"
+            tx.send(Update::General(GeneralUpdate::Error(BackendError::NotReady(
+                "设备或模型未就绪".to_string(),
             ))))?;
             tx.send(Update::Measurement(MeasurementUpdate::DynamicRunning(
                 false,
             )))?;
-            return Err(anyhow!("设备或模型未就绪"));
+            return Err(BackendError::NotReady("设备或模型未就绪".to_string()).into());
         }
 
         if s.measurement.current_steps.is_none() {
-            tx.send(Update::General(GeneralUpdate::Error(format!("未归零"))))?;
+            tx.send(Update::General(GeneralUpdate::Error(BackendError::NotReady(
+                "未归零".to_string(),
+            ))))?;
             tx.send(Update::Measurement(MeasurementUpdate::DynamicRunning(
                 false,
             )))?;
-            return Err(anyhow!("未归零"));
+            return Err(BackendError::NotReady("未归零".to_string()).into());
         }
 
         if s.measurement.dynamic_time.is_none() {
-            tx.send(Update::General(GeneralUpdate::Error(format!(
-                "请先开始计时"
-            ))))?;
+            tx.send(Update::General(GeneralUpdate::Error(
+                BackendError::NotReady("请先开始计时".to_string()),
+            )))?;
             tx.send(Update::Measurement(MeasurementUpdate::DynamicRunning(
                 false,
             )))?;
@@ -698,9 +1254,9 @@ pub fn run_dynamic_experiment_loop(
         }
 
         if s.measurement.dynamic_task_token.is_some() || s.measurement.static_task_token.is_some() {
-            tx.send(Update::General(GeneralUpdate::Error(format!(
-                "已经有测量任务在运行"
-            ))))?;
+            tx.send(Update::General(GeneralUpdate::Error(
+                BackendError::NotReady("已经有测量任务在运行".to_string()),
+            )))?;
             tx.send(Update::Measurement(MeasurementUpdate::DynamicRunning(
                 false,
             )))?;
@@ -709,47 +1265,81 @@ pub fn run_dynamic_experiment_loop(
 
         //过五关斩六将，开始！
         s.measurement.dynamic_task_token = Some(token.clone());
+        s.measurement.halted = false;
         tx.send(Update::Measurement(MeasurementUpdate::DynamicRunning(true)))?;
-        info!("动态追踪启动");
+        let params = &s.measurement.dynamic_params;
+        record_event(
+            tx,
+            format!(
+                "动态追踪开始：温度 {} °C，蔗糖 {} g/mL，盐酸 {} mol/L，初始角 {}°，步进角 {}°，采样 {} 点",
+                params.temperature,
+                params.sucrose_conc,
+                params.hcl_conc,
+                params.pre_rotation_angle,
+                params.step_angle,
+                params.sample_points
+            ),
+        );
         (
             s.rotation_direction_is_ama,
             // s.rotation_direction_need_reverse,
             s.training.fitted_model.as_ref().unwrap().clone(),
+            s.training.model_normalization(),
         )
     };
     let result = (|| -> Result<()> {//
-        info!("动态追踪：开始预旋转");
-        pre_rotation(state, tx, token.clone())?;
-
         let (params,anglesteps) = { let  s =state.lock();(s.measurement.dynamic_params.clone(),s.devices.angle_steps) };
-        precision_rotate(state, tx, (params.step_angle * anglesteps).round() as i32)?;
-        info!("动态追踪：预旋转完成");
+        if params.skip_pre_rotation {
+            info!("动态追踪：跳过预旋转，从当前位置开始跟踪");
+            record_event(tx, "跳过预旋转，从当前位置开始跟踪".to_string());
+        } else {
+            info!("动态追踪：开始预旋转");
+            set_dynamic_phase(tx, DynamicPhase::PreRotating)?;
+            pre_rotation(state, tx, token.clone())?;
+            precision_rotate(state, tx, (params.effective_step_angle() * anglesteps).round() as i32)?;
+            info!("动态追踪：预旋转完成");
+        }
+        tx.send(Update::Measurement(MeasurementUpdate::DynamicStatus("跟踪中".to_string())))?;
+
+        if params.sampling_mode == SamplingMode::Timed {
+            return timed_sampling_loop(state, tx, &token, anglesteps);
+        }
+        set_dynamic_phase(tx, DynamicPhase::WaitingForFlip)?;
 
-        let timeout = Duration::from_secs(5000);
-        let mut predictions: VecDeque<usize> = VecDeque::from(vec![2; 5]);
-        let mut first = 2;
+        // 最近 M 帧中有 N 帧翻转即触发采样
+        let window_len = params.consensus_window.max(1) as usize;
+        let needed = (params.consensus_count as usize).clamp(1, window_len);
+        let mut predictions = unknown_window(window_len);
+        let mut first = Prediction::Unknown;
+        let confirm_delay = Duration::from_millis(params.confirm_delay_ms as u64);
+        // 已触发、等待复核的起始时刻
+        let mut pending: Option<Instant> = None;
         loop {
             let mut s = state.lock();
+            if time_limit_reached(&s) {
+                drop(s);
+                return finish_at_time_limit(state, tx);
+            }
             if token.load(Ordering::Relaxed)
                 || s.measurement.dynamic_results.len()
                     >= s.measurement.dynamic_params.sample_points as usize
-                || s.measurement.dynamic_time.unwrap().elapsed() > timeout
             {
                 // s.measurement.current_static_steps = None;
                 return Ok(());
             }
-            if s.devices.camera_manager.is_none() {
+            let poll_interval =
+                Duration::from_millis(s.measurement.dynamic_params.poll_interval_ms as u64);
+            if s.devices.camera_manager().is_none() {
                 tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
                     s.measurement.current_steps,
                 )))?;
-                s.devices.camera_manager = None;
-                tx.send(Update::Device(DeviceUpdate::CameraConnectionStatus(false)))?;
-                return Err(anyhow!("相机异常"));
+                s.devices.drop_primary_camera(tx);
+                tx.send(super::camera::lost_status(CAMERA_GONE))?;
+                return Err(BackendError::CameraLost.into());
             }
             let frame = {
                 s.devices
-                    .camera_manager
-                    .as_ref()
+                    .camera_manager()
                     .unwrap()
                     .latest_frame
                     .lock()
@@ -761,12 +1351,12 @@ pub fn run_dynamic_experiment_loop(
                     tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
                         s.measurement.current_steps,
                     )))?;
-                    s.devices.camera_manager = None;
-                    tx.send(Update::Device(DeviceUpdate::CameraConnectionStatus(false)))?;
-                    return Err(anyhow!("相机异常"));
+                    s.devices.drop_primary_camera(tx);
+                    tx.send(super::camera::lost_status(CAMERA_NO_FRAME))?;
+                    return Err(BackendError::CameraLost.into());
                 }
             };
-            let guard2 = s.devices.camera_settings.lock();
+            let guard2 = s.devices.camera_settings().lock();
             let circle = {
                 if guard2.lock_circle {
                     guard2.locked_circle
@@ -779,52 +1369,54 @@ pub fn run_dynamic_experiment_loop(
             drop(guard2);
             drop(s);
             let prediction =
-                match predict_from_frame(&frame, &model, min_radius, max_radius, circle) {
+                match predict_from_frame(&frame, &model, min_radius, max_radius, circle, normalization) {
                     Ok(p) => p,
                     Err(_) => continue,
                 };
-            let prediction = prediction ^ (isama as usize);
-            if first == 2 {
+            let prediction = direction_signal(prediction, isama);
+            if first == Prediction::Unknown {
                 first = prediction;
             }
             predictions.pop_front();
             predictions.push_back(prediction);
-            // let mut should_break = false;
-            let pred_slice = predictions.make_contiguous();
 
             // let isama=s.rotation_direction_is_ama;
             // drop(s);
             // thread::sleep(Duration::from_millis(500));(- = 1 0)
-            let mut triggered = false;
-            if predictions.iter().filter(|&x| *x == 1).count() >= 3 && first == 0 {
-                triggered = true;
-            } else if predictions.iter().filter(|&x| *x == 0).count() >= 3 && first == 1 {
-                triggered = true;
-            }
-            if triggered {
+            let triggered = flip_consensus(&predictions, first, needed);
+            // 复核通过时返回首次触发的时刻，测量点按该时刻记录，不把复核等待计入反应时间
+            let confirmed = match pending {
+                None if triggered && !confirm_delay.is_zero() => {
+                    pending = Some(Instant::now());
+                    set_dynamic_phase(tx, DynamicPhase::Confirming)?;
+                    None
+                }
+                None => triggered.then(Instant::now),
+                Some(since) if since.elapsed() >= confirm_delay => {
+                    pending = None;
+                    if !triggered {
+                        // 复核时翻转已不成立，多为单帧误判，丢弃后继续等待
+                        info!("动态追踪：复核未通过，丢弃一次误触发");
+                        record_event(tx, "翻转复核未通过，已丢弃一次误触发".to_string());
+                        set_dynamic_phase(tx, DynamicPhase::WaitingForFlip)?;
+                    }
+                    triggered.then_some(since)
+                }
+                Some(_) => None,
+            };
+            if let Some(triggered_at) = confirmed {
                 // let elapsed_time =
-                let params = {
-                    let mut s = state.lock();
-                    let result = crate::communication::DynamicResult {
-                        index: s.measurement.dynamic_results.len() + 1,
-                        time: s.measurement.dynamic_time.unwrap().elapsed().as_secs_f64(),
-                        steps: s.measurement.current_steps.unwrap(),
-                        angle: s.measurement.current_steps.unwrap() as f32 / s.devices.angle_steps,
-                    };
-                    s.measurement.dynamic_results.push(result);
-                    tx.send(Update::Measurement(MeasurementUpdate::DynamicResults(
-                        s.measurement.dynamic_results.clone(),
-                    )))?;
-                    info!("已测量第 {} 个点", s.measurement.dynamic_results.len());
-                    s.measurement.dynamic_params.clone()
-                };
-                save_dynamic_results(state, tx, params.clone())?;
-                precision_rotate(state, tx, (params.step_angle * anglesteps).round() as i32)?;
-                predictions = VecDeque::from(vec![2; 5]);
-                thread::sleep(Duration::from_millis(100));
+                let params = record_dynamic_sample(state, tx, triggered_at)?;
+                autosave_dynamic(state, tx, params.clone())?;
+                set_dynamic_phase(tx, DynamicPhase::Rotating)?;
+                precision_rotate(state, tx, (params.effective_step_angle() * anglesteps).round() as i32)?;
+                predictions = unknown_window(window_len);
+                set_dynamic_phase(tx, DynamicPhase::Settling)?;
+                thread::sleep(Duration::from_millis(params.settle_delay_ms as u64));
+                set_dynamic_phase(tx, DynamicPhase::WaitingForFlip)?;
             }
 
-            thread::sleep(Duration::from_millis(50));
+            thread::sleep(poll_interval);
         }
     })();
     let mut s = state.lock();
@@ -842,16 +1434,267 @@ pub fn run_dynamic_experiment_loop(
         tracing::warn!("终止原因：{}", e);
     }
     {
-        info!(
-            "测量完成，共测量 {} 个点",
-            s.measurement.dynamic_results.len()
+        record_event(
+            tx,
+            format!(
+                "动态追踪结束，共测量 {} 个点",
+                s.measurement.dynamic_results.len()
+            ),
         );
+        // 急停或旋转被中断时停在原处，不再自动转动电机
+        let interrupted = std::mem::take(&mut s.measurement.halted)
+            || matches!(&result, Err(e) if is_interrupted(e));
         drop(s);
-        precision_rotate_to(state, tx, 0)?;
+        if interrupted {
+            record_event(tx, "电机已停止，未自动返回零点".to_string());
+            set_dynamic_phase(tx, DynamicPhase::Idle)?;
+        } else {
+            set_dynamic_phase(tx, DynamicPhase::Returning)?;
+            let returned = precision_rotate_to(state, tx, 0);
+            set_dynamic_phase(tx, DynamicPhase::Idle)?;
+            returned?;
+        }
     }
     result
 }
 
+fn set_dynamic_phase(tx: &Sender<Update>, phase: DynamicPhase) -> Result<()> {
+    info!("动态追踪阶段：{}", phase.label());
+    tx.send(Update::Measurement(MeasurementUpdate::DynamicPhase(phase)))?;
+    Ok(())
+}
+
+pub fn rezero_here(
+    state: &Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
+    confirmed: bool,
+) -> Result<()> {
+    let mut s = state.lock();
+    if s.devices.serial_port.is_none() {
+        return Err(BackendError::SerialDisconnected("未连接串口".to_string()).into());
+    }
+    if s.measurement.isrotation {
+        return Err(anyhow!("电机正在旋转"));
+    }
+    if s.measurement.current_steps.is_none() && !confirmed {
+        return Err(anyhow!("当前没有有效零点，请确认已手动对准后再设为零点"));
+    }
+    let raw_steps = s.measurement.current_steps.unwrap_or(0);
+    s.measurement.current_steps = Some(0);
+    tx.send(Update::Measurement(MeasurementUpdate::CurrentSteps(
+        s.measurement.current_steps,
+    )))?;
+    record_event(tx, "已将当前位置手动设为零点".to_string());
+    establish_zero(&mut s, tx, raw_steps)?;
+    Ok(())
+}
+
+/// 记录零点确立的时间与原始步数，并通知前端
+fn establish_zero(s: &mut BackendState, tx: &Sender<Update>, raw_steps: i32) -> Result<()> {
+    let record = ZeroRecord {
+        timestamp: chrono::Local::now(),
+        raw_steps,
+    };
+    s.measurement.last_zero = Some(record);
+    tx.send(Update::Measurement(MeasurementUpdate::ZeroEstablished(record)))?;
+    Ok(())
+}
+
+/// 对最新画面连续识别几次，返回多数表决后的方向信号
+fn sample_direction_signal(
+    state: &Arc<Mutex<BackendState>>,
+    model: &FittedLogisticRegression<f64, usize>,
+    is_ama: bool,
+) -> Result<Prediction> {
+    let mut amas = 0;
+    let mut total = 0;
+    let mut attempts = 0;
+    // 个别帧识别失败时多试几次
+    while total < ZERO_CHECK_SAMPLES && attempts < ZERO_CHECK_SAMPLES * 3 {
+        attempts += 1;
+        let (frame, settings, normalization) = {
+            let s = state.lock();
+            let manager = s.devices.camera_manager().ok_or(BackendError::CameraLost)?;
+            let frame = manager.latest_frame.lock().clone();
+            (frame, s.devices.camera_settings().lock().clone(), s.training.model_normalization())
+        };
+        let frame = frame.ok_or(BackendError::CameraLost)?;
+        let circle = if settings.lock_circle { settings.locked_circle } else { None };
+        if let Ok(p) = predict_from_frame(
+            &frame,
+            model,
+            settings.min_radius,
+            settings.max_radius,
+            circle,
+            normalization,
+        ) {
+            match direction_signal(p, is_ama) {
+                Prediction::Ama => {
+                    amas += 1;
+                    total += 1;
+                }
+                Prediction::Mam => total += 1,
+                Prediction::Unknown => {}
+            }
+        }
+        thread::sleep(Duration::from_millis(40));
+    }
+    if total < ZERO_CHECK_SAMPLES {
+        return Err(anyhow!("无法识别视场，请检查圆形识别设置"));
+    }
+    Ok(if amas * 2 > total { Prediction::Ama } else { Prediction::Mam })
+}
+
+/// 不重新寻找零点，只在当前位置读取一次识别结果，检查视场是否与零点哪一侧相符，
+/// 用于在两次测量之间发现电机丢步造成的零点漂移
+pub fn verify_zero(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Result<()> {
+    let (model, is_ama, current, angle_steps) = {
+        let s = state.lock();
+        if s.devices.serial_port.is_none() || s.devices.camera_manager().is_none() {
+            return Err(BackendError::NotReady("设备未就绪".to_string()).into());
+        }
+        if s.measurement.isrotation {
+            return Err(anyhow!("电机正在旋转"));
+        }
+        let model = s.training.fitted_model.clone().ok_or_else(|| BackendError::NotReady("模型未就绪".to_string()))?;
+        let current = s.measurement.current_steps.ok_or_else(|| anyhow!("当前没有有效零点"))?;
+        (model, s.rotation_direction_is_ama, current, s.devices.angle_steps)
+    };
+
+    // 离零点太近时先转到零点正侧读数，读完再转回原位
+    let offset = ((ZERO_CHECK_OFFSET_DEG * angle_steps).round() as i32).max(1);
+    let nudge = if current.abs() < offset { offset - current } else { 0 };
+    if nudge != 0 {
+        precision_rotate(state, tx, nudge)?;
+    }
+    thread::sleep(Duration::from_millis(150));
+    let signal = sample_direction_signal(state, &model, is_ama);
+    if nudge != 0 {
+        precision_rotate(state, tx, -nudge)?;
+    }
+    let signal = signal?;
+
+    let position = current + nudge;
+    let angle = position as f32 / angle_steps;
+    // 方向信号 Mam 表示位于零点正侧
+    let expected = if position > 0 { Prediction::Mam } else { Prediction::Ama };
+    if signal == expected {
+        let message = format!("零点校验通过（在 {:.2}° 处视场与预期一致）", angle);
+        info!("{}", message);
+        record_event(tx, message.clone());
+        tx.send(Update::General(GeneralUpdate::StatusMessage(message)))?;
+    } else {
+        let message = format!(
+            "零点可能已漂移：在 {:.2}° 处看到的是零点{}侧的视场，建议重新寻找零点",
+            angle,
+            if expected == Prediction::Mam { "负" } else { "正" }
+        );
+        tracing::warn!("{}", message);
+        record_event(tx, message.clone());
+        tx.send(Update::General(GeneralUpdate::Error(message.into())))?;
+    }
+    Ok(())
+}
+
+/// 以当前电机位置记录一个动态测量点，返回记录时的实验参数
+/// `at` 为该点对应的时刻，按它计算距开始计时的时间
+fn record_dynamic_sample(
+    state: &Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
+    at: Instant,
+) -> Result<DynamicExpParams> {
+    let mut s = state.lock();
+    let start = s.measurement.dynamic_time.ok_or_else(|| anyhow!("请先开始计时"))?;
+    let steps = s.measurement.current_steps.ok_or_else(|| anyhow!("未归零"))?;
+    let result = crate::communication::DynamicResult {
+        index: s.measurement.dynamic_results.len() + 1,
+        time: at.saturating_duration_since(start).as_secs_f64(),
+        steps,
+        angle: steps as f32 / s.devices.angle_steps,
+    };
+    record_event(
+        tx,
+        format!(
+            "记录第 {} 个点：t = {:.1} s，{:.3}°",
+            result.index, result.time, result.angle
+        ),
+    );
+    s.measurement.dynamic_results.push(result);
+    tx.send(Update::Measurement(MeasurementUpdate::DynamicResults(
+        s.measurement.dynamic_results.clone(),
+    )))?;
+    Ok(s.measurement.dynamic_params.clone())
+}
+
+/// 手动记录一个动态测量点，不转动电机；追踪进行中或仅在计时时均可使用
+pub fn record_dynamic_now(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Result<()> {
+    if state.lock().measurement.isrotation {
+        return Err(anyhow!("电机正在旋转，请稍后再记录"));
+    }
+    record_event(tx, "手动记录动态测量点".to_string());
+    let params = record_dynamic_sample(state, tx, Instant::now())?;
+    save_dynamic_results(state, tx, params)
+}
+
+/// 从开始计时起是否已超过设定的最长运行时间
+fn time_limit_reached(s: &BackendState) -> bool {
+    s.measurement
+        .dynamic_time
+        .is_some_and(|start| start.elapsed() > s.measurement.dynamic_params.max_duration())
+}
+
+/// 达到最长运行时间：保存已采集的数据并记录停止原因
+fn finish_at_time_limit(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Result<()> {
+    let params = state.lock().measurement.dynamic_params.clone();
+    let message = format!("已达到最长运行时间 {} 分钟，停止跟踪", params.max_duration_min);
+    info!("{}", message);
+    record_event(tx, message.clone());
+    tx.send(Update::Measurement(MeasurementUpdate::DynamicStatus(message)))?;
+    save_dynamic_results(state, tx, params)
+}
+
+/// 按时间采样：每隔固定时间记录一次当前角度，并在两次采样之间转动步进角
+fn timed_sampling_loop(
+    state: &Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
+    token: &CancellationToken,
+    anglesteps: f32,
+) -> Result<()> {
+    let mut last_sample = Instant::now();
+    set_dynamic_phase(tx, DynamicPhase::WaitingForInterval)?;
+    loop {
+        let (interval, poll_interval) = {
+            let s = state.lock();
+            if time_limit_reached(&s) {
+                drop(s);
+                return finish_at_time_limit(state, tx);
+            }
+            if token.load(Ordering::Relaxed)
+                || s.measurement.dynamic_results.len()
+                    >= s.measurement.dynamic_params.sample_points as usize
+            {
+                return Ok(());
+            }
+            let params = &s.measurement.dynamic_params;
+            (
+                Duration::from_secs_f32(params.sample_interval.max(0.1)),
+                Duration::from_millis(params.poll_interval_ms as u64),
+            )
+        };
+        if last_sample.elapsed() >= interval {
+            last_sample = Instant::now();
+            let params = record_dynamic_sample(state, tx, Instant::now())?;
+            autosave_dynamic(state, tx, params.clone())?;
+            set_dynamic_phase(tx, DynamicPhase::Rotating)?;
+            precision_rotate(state, tx, (params.effective_step_angle() * anglesteps).round() as i32)?;
+            set_dynamic_phase(tx, DynamicPhase::Settling)?;
+            thread::sleep(Duration::from_millis(params.settle_delay_ms as u64));
+            set_dynamic_phase(tx, DynamicPhase::WaitingForInterval)?;
+        }
+        thread::sleep(poll_interval);
+    }
+}
+
 pub fn return_to_zero(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Result<()> {
     info!("请求返回零点");
     // let mut s = state.lock();
@@ -865,25 +1708,136 @@ pub fn return_to_zero(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) ->
     Ok(())
 }
 
+/// 回到上次零点：先按步数转回记录的零点，再在附近有限范围内用识别结果重新寻找边界，
+/// 消除累计丢步带来的误差
+pub fn return_to_zero_by_search(
+    state: &Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
+    token: CancellationToken,
+) -> Result<()> {
+    let angle_steps = {
+        let s = state.lock();
+        if s.measurement.current_steps.is_none() {
+            return Err(anyhow!("当前没有有效零点"));
+        }
+        s.devices.angle_steps
+    };
+    return_to_zero(state, tx)?;
+    let range = (ZERO_RESEARCH_RANGE_DEG * angle_steps).round() as i32;
+    static_measurement(state, tx, token, true, 1, Some(range))
+}
+
 pub fn save_static(
     state: &Arc<Mutex<BackendState>>,
     save_path: PathBuf,
     tx: &Sender<Update>,
 ) -> Result<()> {
-    let results = state.lock().measurement.static_results.clone();
+    let (results, layout, steps_per_degree) = {
+        let s = state.lock();
+        (
+            s.measurement.static_results.clone(),
+            s.measurement.export_layout.clone(),
+            s.devices.angle_steps,
+        )
+    };
     if results.is_empty() {
-        error!("静态测量结果为空");
-        return Ok(());
+        return Err(anyhow!("静态测量结果为空"));
     }
-    if file_saver::save_static_results(&save_path, &results).is_err() {
-        error!("静态测量保存失败");
+    // 写入失败时返回错误，前端保留“未保存”标记
+    if is_csv(&save_path) {
+        let csv = file_saver::static_results_csv(&results, &layout, steps_per_degree);
+        std::fs::write(&save_path, csv).map_err(|e| anyhow!("静态测量保存失败：{}", e))?;
+    } else {
+        file_saver::save_static_results(&save_path, &results, &layout, steps_per_degree)
+            .map_err(|e| anyhow!("静态测量保存失败：{}", e))?;
     }
     tx.send(Update::Measurement(MeasurementUpdate::StaticStatus(
         "保存成功".to_string(),
     )))?;
+    tx.send(Update::Measurement(MeasurementUpdate::StaticSaved))?;
     info!("静态测量结果保存成功");
     Ok(())
 }
+/// 从之前保存的动态测量 xlsx 中读回实验参数块（F/G 两列的 "标签: 值"）
+pub fn import_dynamic_params(
+    state: &Arc<Mutex<BackendState>>,
+    path: PathBuf,
+    tx: &Sender<Update>,
+) -> Result<()> {
+    let mut workbook: calamine::Xlsx<_> = calamine::open_workbook(&path)?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| anyhow!("文件中没有工作表"))??;
+    let mut params = state.lock().measurement.dynamic_params.clone();
+    let mut found = 0;
+    for row in range.rows() {
+        let (Some(key), Some(value)) = (row.get(5), row.get(6)) else {
+            continue;
+        };
+        let number = value.get_float().map(|v| v as f32);
+        let text = value.get_string().unwrap_or_default();
+        match (key.get_string().unwrap_or_default(), number) {
+            ("实验温度 (°C)", Some(v)) => params.temperature = v,
+            // 旧版文件的键不带单位，数值同样是标准单位
+            ("蔗糖浓度" | "蔗糖浓度 (g/mL)", Some(v)) => params.sucrose_conc = v,
+            ("盐酸浓度" | "盐酸浓度 (mol/L)", Some(v)) => params.hcl_conc = v,
+            ("蔗糖浓度输入单位", _) => match ConcUnit::from_label(text) {
+                Some(unit) => params.sucrose_unit = unit,
+                None => continue,
+            },
+            ("盐酸浓度输入单位", _) => match ConcUnit::from_label(text) {
+                Some(unit) => params.hcl_unit = unit,
+                None => continue,
+            },
+            ("初始旋光角", Some(v)) => params.pre_rotation_angle = v,
+            // 旧版文件的步进角带符号，这里只取大小，方向由样品旋光方向决定
+            ("步进角", Some(v)) => params.step_angle = v.abs(),
+            ("采样点数", Some(v)) => params.sample_points = v.round().max(0.0) as u32,
+            ("采样间隔 (s)", Some(v)) => params.sample_interval = v,
+            ("最长运行时间 (min)", Some(v)) => params.max_duration_min = v.round().max(1.0) as u32,
+            ("翻转复核延时 (ms)", Some(v)) => params.confirm_delay_ms = v.round().clamp(0.0, 5000.0) as u32,
+            ("样品旋光方向", _) => {
+                params.sample_direction = if text == "左旋" {
+                    SampleDirection::Levo
+                } else {
+                    SampleDirection::Dextro
+                }
+            }
+            ("预旋转", _) => {
+                params.skip_pre_rotation = text == "跳过";
+                if let Some(target) = PreRotationTarget::from_label(text) {
+                    params.pre_rotation_target = target;
+                }
+            }
+            ("采样方式", _) => {
+                params.sampling_mode = if text == "按时间" {
+                    SamplingMode::Timed
+                } else {
+                    SamplingMode::PredictionFlip
+                }
+            }
+            _ => continue,
+        }
+        found += 1;
+    }
+    if found == 0 {
+        return Err(anyhow!("文件中没有找到实验参数"));
+    }
+    state.lock().measurement.dynamic_params = params.clone();
+    tx.send(Update::Measurement(MeasurementUpdate::DynamicParams(params)))?;
+    info!("已从 {:?} 导入 {} 项实验参数", path, found);
+    Ok(())
+}
+
+/// 生成运行编号：启动时间戳加 4 位随机十六进制，例如 20250101-093000-a3f9
+pub fn new_run_id() -> String {
+    format!(
+        "{}-{:04x}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        rand::random::<u16>()
+    )
+}
+
 pub fn save_dynamic_results(
     state: &Arc<Mutex<BackendState>>,
     tx: &Sender<Update>,
@@ -895,9 +1849,437 @@ pub fn save_dynamic_results(
         error!("动态测量结果为空");
         return Ok(());
     }
-    if file_saver::save_dynamic_results(&params.path, &results, &params).is_err() {
-        error!("动态测量保存失败");
+    let run_id = s.measurement.dynamic_run_id.as_deref();
+    let layout = &s.measurement.export_layout;
+    let steps_per_degree = s.devices.angle_steps;
+    if is_csv(&params.path) {
+        let csv = file_saver::dynamic_results_csv(
+            &results,
+            &params,
+            run_id,
+            layout,
+            steps_per_degree,
+        );
+        std::fs::write(&params.path, csv).map_err(|e| anyhow!("动态测量保存失败：{}", e))?;
+    } else {
+        file_saver::save_dynamic_results(
+            &params.path,
+            &results,
+            &params,
+            run_id,
+            layout,
+            steps_per_degree,
+        )
+        .map_err(|e| anyhow!("动态测量保存失败：{}", e))?;
     }
+    drop(s);
+    tx.send(Update::Measurement(MeasurementUpdate::DynamicSaved))?;
     info!("动态测量结果保存成功");
     Ok(())
 }
+
+/// 跟踪过程中每记录一个点就自动保存一次；保存失败不中断跟踪，数据仍在内存中，
+/// 前端保留“未保存”标记并提示用户
+fn autosave_dynamic(
+    state: &Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
+    params: DynamicExpParams,
+) -> Result<()> {
+    if let Err(e) = save_dynamic_results(state, tx, params) {
+        error!("{}", e);
+        tx.send(Update::General(GeneralUpdate::Error(e.to_string().into())))?;
+    }
+    Ok(())
+}
+
+/// 把当前动态结果另存为 CSV，不改变自动保存的路径
+pub fn save_dynamic_csv(
+    state: &Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
+    path: &Path,
+) -> Result<usize> {
+    let csv = {
+        let s = state.lock();
+        if s.measurement.dynamic_results.is_empty() {
+            return Err(anyhow!("动态测量结果为空"));
+        }
+        file_saver::dynamic_results_csv(
+            &s.measurement.dynamic_results,
+            &s.measurement.dynamic_params,
+            s.measurement.dynamic_run_id.as_deref(),
+            &s.measurement.export_layout,
+            s.devices.angle_steps,
+        )
+    };
+    std::fs::write(path, csv)?;
+    tx.send(Update::Measurement(MeasurementUpdate::DynamicSaved))?;
+    let count = state.lock().measurement.dynamic_results.len();
+    info!("已将 {} 个动态测量点导出到 {:?}", count, path);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use Prediction::{Ama, Mam, Unknown};
+
+    /// 按模型类别编号（0 = MAM, 1 = AMA, 2 = 无结果）构造识别序列
+    fn signals(codes: &[usize]) -> Vec<Prediction> {
+        codes.iter().map(|&c| Prediction::from_class(c)).collect()
+    }
+
+    fn window(codes: [usize; 5]) -> VecDeque<Prediction> {
+        VecDeque::from(signals(&codes))
+    }
+
+    #[test]
+    fn direction_signal_flips_only_for_ama() {
+        assert_eq!(direction_signal(Mam, false), Mam);
+        assert_eq!(direction_signal(Ama, false), Ama);
+        assert_eq!(direction_signal(Mam, true), Ama);
+        assert_eq!(direction_signal(Ama, true), Mam);
+        // 没有识别结果时无论如何换算都保持未知
+        assert_eq!(direction_signal(Unknown, true), Unknown);
+    }
+
+    #[test]
+    fn decide_move_steps_until_majority_flips() {
+        assert_eq!(
+            decide_move(&window([1, 1, 1, 1, 1]), Ama, 0),
+            MoveDecision::Step(MoveMode::StepForward)
+        );
+        assert_eq!(
+            decide_move(&window([0, 0, 0, 0, 0]), Mam, 0),
+            MoveDecision::Step(MoveMode::StepBackward)
+        );
+        // 只有两次翻转，尚不足以判定越过零点
+        assert_eq!(
+            decide_move(&window([1, 1, 1, 0, 0]), Ama, 0),
+            MoveDecision::Step(MoveMode::StepForward)
+        );
+        assert_eq!(
+            decide_move(&window([1, 1, 0, 0, 0]), Ama, 0),
+            MoveDecision::Crossed(MoveMode::ResetForward)
+        );
+        assert_eq!(
+            decide_move(&window([0, 0, 1, 1, 1]), Mam, 0),
+            MoveDecision::Crossed(MoveMode::ResetBackward)
+        );
+        // 窗口未填满时的 Unknown 不计入任何一方
+        assert_eq!(
+            decide_move(&window([2, 2, 2, 0, 0]), Ama, 0),
+            MoveDecision::Step(MoveMode::StepForward)
+        );
+    }
+
+    #[test]
+    fn reverse_swaps_commands_but_keeps_logical_steps() {
+        for mode in [
+            MoveMode::StepForward,
+            MoveMode::StepBackward,
+            MoveMode::ResetForward,
+            MoveMode::ResetBackward,
+        ] {
+            let (cmd, steps) = move_command(mode, false);
+            let (cmd_rev, steps_rev) = move_command(mode, true);
+            assert_eq!(steps, steps_rev, "{:?}", mode);
+            assert_ne!(cmd, cmd_rev, "{:?}", mode);
+        }
+    }
+
+    /// 对 is_ama / need_reverse / 起始状态的所有组合，逼近方向都朝向零点，
+    /// 越过零点后回退方向与逼近方向相反
+    #[test]
+    fn approach_moves_towards_zero_for_all_settings() {
+        for is_ama in [false, true] {
+            for need_reverse in [false, true] {
+                for first_raw in [0usize, 1] {
+                    let first = direction_signal(Prediction::from_class(first_raw), is_ama);
+                    let flipped = first.flipped();
+
+                    let MoveDecision::Step(step_mode) =
+                        decide_move(&VecDeque::from(vec![first; 5]), first, 0)
+                    else {
+                        panic!("起始状态不应判定为越过零点");
+                    };
+                    let (_, step) = move_command(step_mode, need_reverse);
+                    // 信号 Mam 表示位于零点正侧，应向负方向逼近
+                    let expected_sign = if first == Mam { -1 } else { 1 };
+                    assert_eq!(
+                        step.signum(),
+                        expected_sign,
+                        "is_ama={} need_reverse={} first={}",
+                        is_ama,
+                        need_reverse,
+                        first_raw
+                    );
+
+                    let MoveDecision::Crossed(reset_mode) =
+                        decide_move(
+                            &VecDeque::from(vec![first, first, flipped, flipped, flipped]),
+                            first,
+                            0,
+                        )
+                    else {
+                        panic!("多数翻转后应判定为越过零点");
+                    };
+                    let (_, reset) = move_command(reset_mode, need_reverse);
+                    assert_eq!(reset, -2 * step);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn margin_raises_crossing_threshold() {
+        assert_eq!(
+            decide_move(&window([1, 1, 0, 0, 0]), Ama, 1),
+            MoveDecision::Step(MoveMode::StepForward)
+        );
+        assert_eq!(
+            decide_move(&window([1, 0, 0, 0, 0]), Ama, 1),
+            MoveDecision::Crossed(MoveMode::ResetForward)
+        );
+        // 余量再大也不会超过窗口长度
+        assert_eq!(
+            decide_move(&window([0, 0, 0, 0, 0]), Ama, 9),
+            MoveDecision::Crossed(MoveMode::ResetForward)
+        );
+    }
+
+    /// 模拟一次单侧逼近，零点位于 0 步，返回判定越过零点并回退后的位置。
+    /// 零点两侧 36 步以内分类器容易翻转：每 5 次识别中有连续 3 次给出相反结果
+    fn simulate_approach(start: i32, margin: usize) -> i32 {
+        let mut x = start;
+        let mut approach = Approach::new(margin);
+        for n in 0..200 {
+            let truth = if x < 0 { Ama } else { Mam };
+            let flip_prone = x.abs() < 36 && n % 5 < 3;
+            let signal = if flip_prone { truth.flipped() } else { truth };
+            match approach.observe(signal) {
+                Some(MoveDecision::Crossed(mode)) => return x + move_command(mode, false).1,
+                Some(MoveDecision::Step(mode)) => x += move_command(mode, false).1,
+                None => unreachable!(),
+            }
+        }
+        panic!("逼近未收敛");
+    }
+
+    #[test]
+    fn replay_matches_live_decisions() {
+        let decisions = replay_decisions(&signals(&[1, 1, 1, 1, 1, 0, 2, 0, 0, 1]), 0);
+        let step = Some(MoveDecision::Step(MoveMode::StepForward));
+        assert_eq!(decisions[..6], [step; 6]);
+        // 无识别结果的帧不进入窗口
+        assert_eq!(decisions[6], None);
+        assert_eq!(decisions[7], step);
+        assert_eq!(decisions[8], Some(MoveDecision::Crossed(MoveMode::ResetForward)));
+        // 越过零点后窗口清空，按新的第一帧重新确定方向
+        assert_eq!(decisions[9], step);
+    }
+
+    #[test]
+    fn crossing_with_uniform_window_restarts_approach() {
+        // 窗口全部翻转才判定越过时，越过后也不能沿用越过前的一致信号作为起始信号
+        let decisions = replay_decisions(&signals(&[1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1]), 2);
+        assert_eq!(decisions[9], Some(MoveDecision::Crossed(MoveMode::ResetForward)));
+        assert_eq!(decisions[10], Some(MoveDecision::Step(MoveMode::StepForward)));
+    }
+
+    #[test]
+    fn prediction_csv_skips_header_and_rejects_bad_values() {
+        let values = parse_prediction_csv("frame,prediction\n# 注释\n1,0\n2,1\n3,2\n").unwrap();
+        assert_eq!(values, vec![Mam, Ama, Unknown]);
+        assert_eq!(parse_prediction_csv("0\n1\n").unwrap(), vec![Mam, Ama]);
+        assert!(parse_prediction_csv("0\n3\n").is_err());
+        assert!(parse_prediction_csv("0\nabc\n").is_err());
+    }
+
+    #[test]
+    fn uniform_signal_requires_full_known_window() {
+        assert_eq!(uniform_signal(&window([0, 0, 0, 0, 0])), Some(Mam));
+        assert_eq!(uniform_signal(&window([1, 1, 1, 1, 1])), Some(Ama));
+        assert_eq!(uniform_signal(&window([1, 1, 1, 1, 0])), None);
+        assert_eq!(uniform_signal(&unknown_window(DECISION_WINDOW)), None);
+    }
+
+    #[test]
+    fn hysteresis_converges_on_flip_prone_sequence() {
+        // 没有迟滞时，零点前的一串误判就会被当成越过零点
+        assert!(simulate_approach(-60, 0) < 0);
+        assert!(simulate_approach(60, 0) > 0);
+
+        // 加上迟滞后两侧都在真正越过零点之后才判定，取平均回到零点附近
+        let from_below = simulate_approach(-60, DEFAULT_DECISION_MARGIN);
+        let from_above = simulate_approach(60, DEFAULT_DECISION_MARGIN);
+        assert!(from_below > 0, "from_below = {}", from_below);
+        assert!(from_above < 0, "from_above = {}", from_above);
+        assert!(((from_below + from_above) as f64 / 2.0).abs() <= 6.0);
+    }
+
+    #[test]
+    fn flip_consensus_ignores_stray_frames() {
+        // 单个误判帧不会打断计数
+        let stray = VecDeque::from(signals(&[1, 0, 1, 0, 0, 1, 0]));
+        assert!(flip_consensus(&stray, Ama, 4));
+        assert!(!flip_consensus(&stray, Ama, 5));
+        // Unknown 帧与起始信号未确定时都不触发
+        assert!(!flip_consensus(&window([2, 2, 2, 0, 0]), Ama, 3));
+        assert!(!flip_consensus(&window([0, 0, 0, 0, 0]), Unknown, 3));
+    }
+
+    #[test]
+    fn plan_rotation_decomposes_positive_steps() {
+        let plan = plan_rotation(4500, false);
+        // 4500 = 3730 + 746 + 7*3 + 1*3
+        assert_eq!(
+            plan,
+            vec![
+                RotationChunk { command: 62, count: 1, delta: 3730 },
+                RotationChunk { command: 60, count: 1, delta: 746 },
+                RotationChunk { command: 66, count: 3, delta: 7 },
+                RotationChunk { command: 68, count: 3, delta: 1 },
+            ]
+        );
+        assert_eq!(plan_delta(&plan), 4500);
+    }
+
+    #[test]
+    fn plan_rotation_uses_backward_commands_for_negative_steps() {
+        let plan = plan_rotation(-80, false);
+        assert_eq!(
+            plan,
+            vec![
+                RotationChunk { command: 57, count: 1, delta: -75 },
+                RotationChunk { command: 69, count: 5, delta: -1 },
+            ]
+        );
+        assert_eq!(plan_delta(&plan), -80);
+    }
+
+    #[test]
+    fn plan_rotation_reverse_flips_direction_but_keeps_delta() {
+        // 方向反转时实际发送反向指令，但步数变化仍与请求一致
+        let plan = plan_rotation(80, true);
+        assert!(plan.iter().all(|c| BACKWARD_COMMANDS.contains(&c.command)));
+        assert_eq!(plan_delta(&plan), 80);
+
+        let plan = plan_rotation(-80, true);
+        assert!(plan.iter().all(|c| FORWARD_COMMANDS.contains(&c.command)));
+        assert_eq!(plan_delta(&plan), -80);
+    }
+
+    #[test]
+    fn static_csv_follows_export_layout() {
+        let results = [
+            StaticResult { index: 1, steps: 7460, angle: 10.0 },
+            StaticResult { index: 2, steps: -373, angle: -0.5 },
+        ];
+        let mut layout = ExportLayout::default();
+        assert_eq!(
+            file_saver::static_results_csv(&results, &layout, 746.0),
+            "# 每度步数: 746\nindex,steps,angle,valid\n1,7460,10,1\n2,-373,-0.5,1\n"
+        );
+        layout.columns.retain(|(c, _)| !matches!(c, ExportColumn::Index | ExportColumn::Valid));
+        layout.include_params = false;
+        assert_eq!(
+            file_saver::static_results_csv(&results, &layout, 746.0),
+            "steps,angle\n7460,10\n-373,-0.5\n"
+        );
+        assert!(is_csv(Path::new("结果.CSV")));
+        assert!(!is_csv(Path::new("结果.xlsx")));
+    }
+
+    #[test]
+    fn dynamic_csv_writes_param_block_and_follows_export_layout() {
+        let results = [
+            DynamicResult { index: 1, time: 0.0, steps: 3730, angle: 5.0 },
+            DynamicResult { index: 2, time: 60.5, steps: 3357, angle: 4.5 },
+        ];
+        let mut params = DynamicExpParams {
+            path: PathBuf::new(),
+            temperature: 25.0,
+            sucrose_conc: 0.2,
+            hcl_conc: 1.0,
+            sucrose_unit: ConcUnit::GramPerL,
+            hcl_unit: ConcUnit::MolPerL,
+            pre_rotation_angle: 5.0,
+            step_angle: 0.5,
+            sample_points: 12,
+            sample_direction: SampleDirection::Levo,
+            sampling_mode: SamplingMode::Timed,
+            sample_interval: 60.0,
+            settle_delay_ms: 100,
+            poll_interval_ms: 50,
+            consensus_window: 5,
+            consensus_count: 3,
+            confirm_delay_ms: 200,
+            max_duration_min: 90,
+            notes: "水浴\n不稳".to_string(),
+            skip_pre_rotation: true,
+            pre_rotation_target: PreRotationTarget::default(),
+        };
+        let mut layout = ExportLayout::default();
+        assert_eq!(
+            file_saver::dynamic_results_csv(&results, &params, Some("R1"), &layout, 746.0),
+            "# 实验温度 (°C): 25\n# 蔗糖浓度 (g/mL): 0.2\n# 盐酸浓度 (mol/L): 1\n\
+             # 初始旋光角: 5\n# 步进角: 0.5\n# 采样点数: 12\n# 样品旋光方向: 左旋\n\
+             # 采样方式: 按时间\n# 采样间隔 (s): 60\n# 预旋转: 跳过\n# 运行编号: R1\n\
+             # 蔗糖浓度输入单位: g/L\n# 盐酸浓度输入单位: mol/L\n# 最长运行时间 (min): 90\n\
+             # 备注: 水浴 不稳\n# 每度步数: 746\n\
+             index,time,steps,angle,valid\n1,0,3730,5,1\n2,60.5,3357,4.5,1\n"
+        );
+        // 按识别翻转采样时记录复核延时，不记录采样间隔
+        params.sampling_mode = SamplingMode::PredictionFlip;
+        let csv = file_saver::dynamic_results_csv(&results, &params, None, &layout, 746.0);
+        assert!(csv.contains("# 采样方式: 按识别翻转\n"));
+        assert!(csv.contains("# 翻转复核延时 (ms): 200\n"));
+        assert!(!csv.contains("采样间隔"));
+        assert!(!csv.contains("运行编号"));
+
+        layout.columns.retain(|(c, _)| matches!(c, ExportColumn::Time | ExportColumn::Angle));
+        layout.columns.reverse();
+        layout.include_params = false;
+        assert_eq!(
+            file_saver::dynamic_results_csv(&results, &params, Some("R1"), &layout, 746.0),
+            "angle,time\n5,0\n4.5,60.5\n"
+        );
+    }
+
+    #[test]
+    fn static_xlsx_records_steps_per_degree_in_param_rows() {
+        let results = vec![StaticResult { index: 1, steps: 8000, angle: 10.0 }];
+        let path = std::env::temp_dir().join(format!("static_params_{}.xlsx", std::process::id()));
+        file_saver::save_static_results(&path, &results, &ExportLayout::default(), 800.0).unwrap();
+        // 文件记录的每度步数优先于传入的当前标定
+        let run = super::super::data::load_run(&path, None, 746.0).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(run.steps_per_degree, 800.0);
+        assert_eq!(run.param("每度步数"), Some("800"));
+    }
+
+    #[test]
+    fn command_steps_matches_plan_rotation() {
+        // 模拟串口按 command_steps 累加步数，必须与规划的每档步数一致
+        for steps in [4500, -4500, 80, -80] {
+            for chunk in plan_rotation(steps, false) {
+                assert_eq!(command_steps(chunk.command), Some(chunk.delta));
+            }
+        }
+        assert_eq!(command_steps(HEARTBEAT_COMMAND), Some(0));
+        assert_eq!(command_steps(0), None);
+    }
+
+    #[test]
+    fn plan_rotation_delta_always_matches_request() {
+        for steps in (-8000..=8000).step_by(37).chain([0, 1, -1, 3730, -3731]) {
+            for need_reverse in [false, true] {
+                let plan = plan_rotation(steps, need_reverse);
+                assert_eq!(plan_delta(&plan), steps, "steps = {}, reverse = {}", steps, need_reverse);
+                assert!(plan.iter().all(|c| c.count > 0));
+            }
+        }
+        assert!(plan_rotation(0, false).is_empty());
+    }
+}
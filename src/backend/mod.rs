@@ -1,29 +1,34 @@
 mod camera;
 mod command;
 mod data;
+mod dataset_repo;
 mod measurement;
 mod model;
+mod odometer;
 mod recording;
 mod serial;
+mod simulation;
 
 use self::camera::{CameraManager, CameraSettings};
+use self::serial::{AckMonitor, SerialProtocol};
 use crate::communication::{
-    Command, DataProcessingStateUpdate, DeviceCommand, DeviceUpdate, DynamicExpParams,
-    GeneralCommand, GeneralUpdate, MeasurementUpdate, RegressionMode, Update,
+    BackendError, Command, ConcUnit, ConfusionMatrixData, DataProcessingStateUpdate, DeviceCommand, DeviceUpdate, DynamicExpParams, ExportLayout,
+    Diagnostics, GeneralCommand, GeneralUpdate, MeasurementUpdate, Normalization, PreRotationTarget, RegressionMode, SampleDirection, SamplingMode,
+    Update, ZeroRecord,
 };
 use crossbeam_channel::{Receiver, Sender};
 use parking_lot::Mutex;
+use std::collections::BTreeMap;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{
     path::PathBuf,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicI32, Ordering},
         Arc,
     },
 };
 use tracing::{error, info};
-// use self::error::{ BackendError};
 use super::communication::{DynamicResult, StaticResult};
 use anyhow::Result;
 use linfa_logistic::FittedLogisticRegression;
@@ -39,12 +44,52 @@ pub struct BackgroundTask {
 
 pub type CancellationToken = Arc<AtomicBool>;
 
+// 关停时等待所有任务线程结束的总时长，超时后不再等待卡住的任务
+const SHUTDOWN_JOIN_TIMEOUT: Duration = Duration::from_secs(3);
+
 pub struct DeviceState {
-    camera_manager: Option<CameraManager>,
+    // 已连接的相机，按索引存放；primary_camera 指定用于识别的那一路
+    cameras: BTreeMap<usize, CameraManager>,
+    primary_camera: Option<usize>,
     serial_port: Option<Arc<Mutex<Box<dyn serialport::SerialPort>>>>,
-    camera_settings: Arc<Mutex<CameraSettings>>,
+    // 与模拟串口共享，修改协议后无需重新连接即可生效
+    serial_protocol: Arc<Mutex<SerialProtocol>>,
+    ack_monitor: AckMonitor,
+    // 每路相机各自的曝光、半径等设置，按相机索引存放；断开后保留，重连时沿用
+    per_camera_settings: BTreeMap<usize, Arc<Mutex<CameraSettings>>>,
+    // 没有主相机时界面修改的设置，也是第一路相机的初始设置
+    default_camera_settings: Arc<Mutex<CameraSettings>>,
     angle_steps: f32,
+    // 置信度预览任务的取消令牌，存在即表示预览正在运行
+    prediction_preview_token: Option<CancellationToken>,
+    // 模拟模式下假串口与合成相机共享的电机步数，None 表示使用真实硬件
+    simulation: Option<Arc<AtomicI32>>,
+    // 电机累计转动步数，用于安排维护；odometer_saved 为最近写入设置文件的值
+    odometer: u64,
+    odometer_saved: u64,
+}
+impl DeviceState {
+    /// 驱动识别（测量、录制、预览）的主相机
+    fn camera_manager(&self) -> Option<&CameraManager> {
+        self.primary_camera.and_then(|i| self.cameras.get(&i))
+    }
+
+    /// 主相机的设置；没有主相机时为默认设置
+    fn camera_settings(&self) -> &Arc<Mutex<CameraSettings>> {
+        self.primary_camera
+            .and_then(|i| self.per_camera_settings.get(&i))
+            .unwrap_or(&self.default_camera_settings)
+    }
+
+    /// 主相机异常时将其移除并通知前端，其他相机保持连接
+    fn drop_primary_camera(&mut self, tx: &Sender<Update>) {
+        if let Some(index) = self.primary_camera.take() {
+            self.cameras.remove(&index);
+            let _ = tx.send(camera::cameras_changed(self));
+        }
+    }
 }
+
 // --- NEW: State for the recording task ---
 pub struct RecordingState {
     pub cancellation_token: Option<CancellationToken>,
@@ -58,6 +103,14 @@ pub struct TrainingState {
     persistent_mam: Vec<Vec<u8>>,
     persistent_ama: Vec<Vec<u8>>,
     fitted_model: Option<FittedLogisticRegression<f64, usize>>,
+    // 训练模型时的特征与裁剪设置，识别时与之对照
+    model_config: Option<model::FeatureConfig>,
+    // 下次训练使用的归一化方式；识别时使用的是 model_config 中记录的方式
+    normalization: Normalization,
+    // 最近一次训练的评估结果，供导出使用
+    last_evaluation: Option<ConfusionMatrixData>,
+    // 最近一次训练所用的 (录制帧数, 常驻帧数)
+    last_composition: Option<(usize, usize)>,
 }
 
 impl TrainingState {
@@ -68,8 +121,19 @@ impl TrainingState {
             persistent_mam: Vec::new(),
             persistent_ama: Vec::new(),
             fitted_model: None,
+            model_config: None,
+            normalization: Normalization::None,
+            last_evaluation: None,
+            last_composition: None,
         }
     }
+
+    /// 当前模型训练时使用的归一化方式，识别时必须与之一致
+    fn model_normalization(&self) -> Normalization {
+        self.model_config
+            .as_ref()
+            .map_or(Normalization::None, |config| config.normalization)
+    }
 }
 
 pub struct MeasurementState {
@@ -79,16 +143,40 @@ pub struct MeasurementState {
     dynamic_results: Vec<DynamicResult>,
     dynamic_task_token: Option<CancellationToken>,
     dynamic_time: Option<std::time::Instant>,
+    // 本次动态实验的运行编号，写入结果文件便于事后对照
+    dynamic_run_id: Option<String>,
     dynamic_params: DynamicExpParams,
-    isrotation: bool
+    isrotation: bool,
+    // 正在进行的旋转所用的取消令牌，急停时通过它中断手动旋转与收尾旋转
+    rotation_token: Option<CancellationToken>,
+    // 急停后置位，动态测量结束时据此跳过自动回零
+    halted: bool,
+    // 静态测量判定越过零点时的迟滞余量
+    decision_margin: usize,
+    // 最近一次确立零点的记录
+    last_zero: Option<ZeroRecord>,
+    // 保存 xlsx 结果时的列布局
+    export_layout: ExportLayout,
 }
 #[derive(Clone, Debug)]
 pub struct DataProcessingState {
     pub raw_data: Option<Vec<(f64, i32, f64, bool)>>, // time, steps, angle
-    pub alpha_inf: f64,
+    // 用户设置或估算的 α∞，None 表示尚未设置（α∞ = 0 是合法值）
+    pub alpha_inf: Option<f64>,
     pub regression_mode: RegressionMode,
     // Calculated results are also part of the state
     pub regression_formula: String,
+    // 可直接复制的纯文本拟合结果，每行一项
+    pub regression_summary: String,
+    // 非空时提示用户部分数据点被跳过（如 α∞ 设置过大）
+    pub regression_warning: String,
+    // 当前拟合的 R²，没有拟合结果时为 NaN
+    pub r_squared: f64,
+    // 已加载数据的样品旋光方向，决定旋光角的预期变化趋势
+    pub sample_direction: SampleDirection,
+    pub exclude_trend_reversals: bool,
+    // 文件有效性列的内容，与 raw_data 一一对应；为 false 的点始终不参与拟合
+    pub file_valid: Vec<bool>,
     pub plot_scatter_points: Vec<(f64, f64)>, // --- NEW ---
     pub plot_line_points: Vec<(f64, f64)>,
 }
@@ -97,9 +185,15 @@ impl DataProcessingState {
     fn new() -> Self {
         Self {
             raw_data: None,
-            alpha_inf: 0.0,
+            alpha_inf: None,
             regression_mode: RegressionMode::Log, // Default mode
             regression_formula: String::new(),
+            regression_summary: String::new(),
+            regression_warning: String::new(),
+            r_squared: f64::NAN,
+            sample_direction: SampleDirection::Dextro,
+            exclude_trend_reversals: true,
+            file_valid: Vec::new(),
             plot_scatter_points: Vec::new(), // --- NEW ---
             plot_line_points: Vec::new(),
         }
@@ -126,6 +220,10 @@ impl From<DataProcessingState> for DataProcessingStateUpdate {
             alpha_inf: dp_state.alpha_inf,
             regression_mode: dp_state.regression_mode,
             regression_formula: dp_state.regression_formula,
+            regression_summary: dp_state.regression_summary,
+            regression_warning: dp_state.regression_warning,
+            r_squared: dp_state.r_squared,
+            exclude_trend_reversals: dp_state.exclude_trend_reversals,
             plot_line_points: dp_state.plot_line_points,
             plot_scatter_points: dp_state.plot_scatter_points,
         }
@@ -133,19 +231,30 @@ impl From<DataProcessingState> for DataProcessingStateUpdate {
 }
 
 impl BackendState {
-    fn new() -> Self {
+    fn new(simulate: bool) -> Self {
+        let odometer = odometer::load();
         Self {
             devices: DeviceState {
-                camera_manager: None,
+                cameras: BTreeMap::new(),
+                primary_camera: None,
                 serial_port: None,
-                camera_settings: Arc::new(Mutex::new(CameraSettings {
+                serial_protocol: Arc::new(Mutex::new(SerialProtocol::default())),
+                ack_monitor: AckMonitor::default(),
+                per_camera_settings: BTreeMap::new(),
+                default_camera_settings: Arc::new(Mutex::new(CameraSettings {
                     exposure: -8.0,
                     lock_circle: false,
                     locked_circle: None,
                     min_radius: 30,
                     max_radius: 45,
+                    capture_fps: camera::DEFAULT_CAPTURE_FPS,
+                    paused: false,
                 })),
-                angle_steps: 746.0,
+                angle_steps: crate::communication::DEFAULT_STEPS_PER_DEGREE,
+                prediction_preview_token: None,
+                simulation: simulate.then(|| Arc::new(AtomicI32::new(0))),
+                odometer,
+                odometer_saved: odometer,
             },
             recording: RecordingState {
                 // --- NEW ---
@@ -160,15 +269,35 @@ impl BackendState {
                 dynamic_results: Vec::new(),
                 dynamic_task_token: None,
                 isrotation:false,
+                rotation_token: None,
+                halted: false,
+                decision_margin: measurement::DEFAULT_DECISION_MARGIN,
+                last_zero: None,
+                export_layout: measurement::load_export_layout(),
                 dynamic_time: None,
+                dynamic_run_id: None,
                 dynamic_params: DynamicExpParams {
                     path: PathBuf::new(),
                     temperature: 25.0,
                     sucrose_conc: 0.0,
                     hcl_conc: 0.0,
+                    sucrose_unit: ConcUnit::GramPerMl,
+                    hcl_unit: ConcUnit::MolPerL,
                     pre_rotation_angle: 5.0,
-                    step_angle: -0.5,
+                    step_angle: 0.5,
                     sample_points: 12,
+                    sample_direction: SampleDirection::Dextro,
+                    sampling_mode: SamplingMode::PredictionFlip,
+                    sample_interval: 60.0,
+                    settle_delay_ms: 100,
+                    poll_interval_ms: 50,
+                    consensus_window: 5,
+                    consensus_count: 3,
+                    confirm_delay_ms: 0,
+                    max_duration_min: 90,
+                    notes: String::new(),
+                    skip_pre_rotation: false,
+                    pre_rotation_target: PreRotationTarget::default(),
                 },
             },
             data_processing: DataProcessingState::new(),
@@ -179,11 +308,40 @@ impl BackendState {
     }
 }
 
+// 监控线程每隔几秒统计一次资源占用
+const DIAGNOSTICS_PERIOD: u32 = 5;
+
+/// 统计进程内存、CPU 与各大缓冲区的大小，用于发现长时间运行中的无界增长
+fn collect_diagnostics(
+    state: &Arc<Mutex<BackendState>>,
+    system: &mut sysinfo::System,
+    pid: Option<sysinfo::Pid>,
+) -> Diagnostics {
+    let process = pid.and_then(|pid| {
+        system.refresh_process(pid);
+        system.process(pid)
+    });
+    let s = state.lock();
+    let images = s.training.mam_images.iter().chain(&s.training.ama_images);
+    Diagnostics {
+        memory_bytes: process.map(|p| p.memory()),
+        cpu_percent: process.map(|p| p.cpu_usage()),
+        mam_images: s.training.mam_images.len(),
+        ama_images: s.training.ama_images.len(),
+        image_bytes: images.map(Vec::len).sum(),
+        dynamic_results: s.measurement.dynamic_results.len(),
+        raw_data: s.data_processing.raw_data.as_ref().map_or(0, Vec::len),
+    }
+}
+
 /// 后端主循环 (修正后的最终版)
-pub fn backend_loop(cmd_rx: Receiver<Command>, update_tx: Sender<Update>) {
+pub fn backend_loop(cmd_rx: Receiver<Command>, update_tx: Sender<Update>, simulate: bool) {
     info!("后端线程已启动");
+    if simulate {
+        info!("模拟模式：使用假串口与合成相机画面");
+    }
     let mut active_tasks: Vec<BackgroundTask> = Vec::new();
-    let state = Arc::new(Mutex::new(BackendState::new()));
+    let state = Arc::new(Mutex::new(BackendState::new(simulate)));
     let global_shutdown_signal = state.lock().shutdown_signal.clone();
 
     if true {
@@ -198,6 +356,8 @@ pub fn backend_loop(cmd_rx: Receiver<Command>, update_tx: Sender<Update>) {
             info!("状态监控线程已启动。");
             // 只要未收到取消信号，就持续运行
             let mut times = 1;
+            let mut system = sysinfo::System::new();
+            let pid = sysinfo::get_current_pid().ok();
             while !token_for_monitor.load(Ordering::Relaxed) {
                 {
                     // 使用独立的块来限制 MutexGuard 的生命周期
@@ -214,12 +374,28 @@ pub fn backend_loop(cmd_rx: Receiver<Command>, update_tx: Sender<Update>) {
                         // info!("串口断开");
                     } else if times % 10 == 0 {
                         let port = s.devices.serial_port.as_mut().unwrap().clone();
+                        let protocol = s.devices.serial_protocol.lock().clone();
                         drop(s);
-                        let _=measurement::cmd(port, 77 as u8);
+                        let result = measurement::cmd(port, measurement::HEARTBEAT_COMMAND, &protocol);
+                        let mut s = state_for_monitor.lock();
+                        if let Some(message) = s.devices.ack_monitor.record(&result) {
+                            // 设备从未应答，继续心跳只会重复超时，直接断开并给出诊断
+                            s.devices.serial_port = None;
+                            error!("{}", message);
+                            let _ = tx.send(Update::General(GeneralUpdate::Error(
+                                BackendError::SerialDisconnected(message.to_string()),
+                            )));
+                        }
                     } else {
                         drop(s);
                     }
 
+                    let _ = odometer::flush(&state_for_monitor, &tx);
+                    if times % DIAGNOSTICS_PERIOD == 0 {
+                        let diagnostics = collect_diagnostics(&state_for_monitor, &mut system, pid);
+                        let _ = tx.send(Update::General(GeneralUpdate::Diagnostics(diagnostics)));
+                    }
+
                     // TODO: 在这里执行对 state_guard 中数据的检查逻辑
                     // 例如: if state_guard.measurement.some_field > threshold { ... }
                     // 锁会在这个块的末尾自动释放，这很重要，
@@ -240,6 +416,14 @@ pub fn backend_loop(cmd_rx: Receiver<Command>, update_tx: Sender<Update>) {
             cancellation_token: monitor_token,
         });
     }
+    let settings_worker = Worker::spawn("settings-worker", Arc::clone(&state), update_tx.clone());
+    if let Err(e) = camera::check_opencv(&update_tx) {
+        tracing::warn!("无法发送 OpenCV 自检结果：{}", e);
+    }
+    let odometer = state.lock().devices.odometer;
+    let _ = update_tx.send(Update::Device(DeviceUpdate::Odometer(odometer)));
+    let layout = state.lock().measurement.export_layout.clone();
+    let _ = update_tx.send(Update::General(GeneralUpdate::ExportLayout(layout)));
     // 当主循环退出时，state 的最后一个 Arc 将被销毁，
     // 其内部的 active_tasks 会被 drop，进而 join 所有的 handle。
     while !global_shutdown_signal.load(Ordering::Relaxed) {
@@ -251,6 +435,12 @@ pub fn backend_loop(cmd_rx: Receiver<Command>, update_tx: Sender<Update>) {
                 continue; // 继续循环，下一次迭代将因为 while 条件不满足而退出
             }
 
+            // 高频的参数类命令交给常驻工作线程串行执行，不再每条都新建线程
+            if is_lightweight(&command) {
+                settings_worker.send(command);
+                continue;
+            }
+
             // 清理已完成的旧任务
             active_tasks.retain(|task| !task.handle.is_finished());
 
@@ -261,18 +451,9 @@ pub fn backend_loop(cmd_rx: Receiver<Command>, update_tx: Sender<Update>) {
             let update_tx_clone = update_tx.clone();
             let token_clone = task_token.clone();
 
-            // 为每个命令创建一个工作线程
+            // 为每个长耗时命令创建一个工作线程
             let handle = thread::spawn(move || {
-                // 在这个新线程里直接执行命令，并传入它的取消令牌
-                let result =
-                    dispatch_command(command, state_clone, update_tx_clone.clone(), token_clone);
-
-                // 错误处理...
-                if let Err(e) = result {
-                    let error_msg = format!("执行命令时出错: {}", e);
-                    error!("{}", error_msg);
-                    let _ = update_tx_clone.send(Update::General(GeneralUpdate::Error(error_msg)));
-                }
+                run_command(command, state_clone, &update_tx_clone, token_clone);
             });
 
             // 将新任务的 handle 和 token 注册到状态中
@@ -290,30 +471,155 @@ pub fn backend_loop(cmd_rx: Receiver<Command>, update_tx: Sender<Update>) {
 
         // 1. 停止并清理 CameraManager (因为它有自己的线程)
         info!("正在关闭相机管理器...");
-        state_guard.devices.camera_manager = None;
+        state_guard.devices.primary_camera = None;
+        state_guard.devices.cameras.clear();
     }
 
-    // 2. 向所有活动任务发送取消信号
+    // 2. 关闭常驻工作线程，取消其正在执行的命令，并加入待等待的任务列表
+    active_tasks.push(settings_worker.shutdown());
+
+    // 3. 向所有活动任务发送取消信号
     info!("向 {} 个活动任务发送停止信号...", active_tasks.len());
     for task in &active_tasks {
         task.cancellation_token.store(true, Ordering::Relaxed);
     }
 
-    // 3. 等待所有任务线程结束
+    // 4. 等待所有任务线程结束
     // 我们需要 take 走 handles 来 join 它们，这会清空 active_tasks
     let tasks_to_join = std::mem::take(&mut active_tasks);
     info!("等待 {} 个任务线程结束...", tasks_to_join.len());
+    let deadline = Instant::now() + SHUTDOWN_JOIN_TIMEOUT;
     for (i, task) in tasks_to_join.into_iter().enumerate() {
-        if let Err(e) = task.handle.join() {
-            error!("等待任务 {} 时发生错误: {:?}", i, e);
-        } else {
-            info!("任务 {} 已成功结束", i);
+        match join_with_deadline(task.handle, deadline) {
+            Some(Ok(())) => info!("任务 {} 已成功结束", i),
+            Some(Err(e)) => error!("等待任务 {} 时发生错误: {:?}", i, e),
+            None => tracing::warn!("任务 {} 未能在超时前结束（可能卡在串口读取），跳过等待", i),
         }
     }
 
+    let _ = odometer::flush(&state, &update_tx);
     info!("后端线程已完全清理并终止");
 }
 
+/// 执行一条命令，出错时归类并推送给前端
+fn run_command(
+    command: Command,
+    state: Arc<Mutex<BackendState>>,
+    update_tx: &Sender<Update>,
+    token: CancellationToken,
+) {
+    if let Err(e) = dispatch_command(command, state, update_tx.clone(), token) {
+        let error = BackendError::classify(&e);
+        error!("{}", error);
+        let _ = update_tx.send(Update::General(GeneralUpdate::Error(error)));
+    }
+}
+
+/// 只修改参数、很快返回的命令；拖动滑块时会连续发送，交给常驻线程处理
+fn is_lightweight(command: &Command) -> bool {
+    use crate::communication::{
+        CameraCommand, DataProcessingCommand, DynamicMeasureCommand, StaticMeasureCommand,
+    };
+    matches!(
+        command,
+        Command::Camera(
+            CameraCommand::SetHoughCircleRadius { .. }
+                | CameraCommand::SetLock(_)
+                | CameraCommand::Exposure(_)
+                | CameraCommand::SetCaptureFps(_)
+        ) | Command::Device(
+            DeviceCommand::SetRotationDirection(_)
+                | DeviceCommand::SetRotationReverse(_)
+                | DeviceCommand::SetStep(_)
+                | DeviceCommand::SetSerialProtocol { .. }
+        ) | Command::General(GeneralCommand::SetExportLayout(_))
+            | Command::StaticMeasure(StaticMeasureCommand::SetDecisionMargin(_))
+            | Command::DynamicMeasure(DynamicMeasureCommand::UpdateParams { .. })
+            | Command::DataProcessing(
+                DataProcessingCommand::SetAlphaInf { .. }
+                    | DataProcessingCommand::SetRegressionMode { .. }
+                    | DataProcessingCommand::SetExcludeTrendReversals(_)
+            )
+    )
+}
+
+/// 常驻工作线程：按顺序执行收到的命令，每条命令仍有自己的取消令牌
+struct Worker {
+    tx: Option<Sender<Command>>,
+    handle: JoinHandle<()>,
+    // 正在执行的命令的取消令牌，关停时用于中止它
+    current: Arc<Mutex<Option<CancellationToken>>>,
+}
+
+impl Worker {
+    fn spawn(name: &str, state: Arc<Mutex<BackendState>>, update_tx: Sender<Update>) -> Self {
+        let (tx, rx) = crossbeam_channel::unbounded::<Command>();
+        let current: Arc<Mutex<Option<CancellationToken>>> = Arc::new(Mutex::new(None));
+        let current_clone = Arc::clone(&current);
+        let handle = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                // 发送端被丢弃后 rx 迭代结束，线程随之退出
+                for command in rx {
+                    let token = Arc::new(AtomicBool::new(false));
+                    *current_clone.lock() = Some(token.clone());
+                    run_command(command, Arc::clone(&state), &update_tx, token);
+                    *current_clone.lock() = None;
+                }
+            })
+            .expect("无法创建常驻工作线程");
+        Self {
+            tx: Some(tx),
+            handle,
+            current,
+        }
+    }
+
+    fn send(&self, command: Command) {
+        if let Some(tx) = &self.tx {
+            if tx.send(command).is_err() {
+                error!("常驻工作线程已退出，命令被丢弃");
+            }
+        }
+    }
+
+    /// 停止接收新命令，并把线程作为普通后台任务交给关停流程等待
+    fn shutdown(mut self) -> BackgroundTask {
+        self.tx = None;
+        let cancellation_token = self
+            .current
+            .lock()
+            .clone()
+            .unwrap_or_else(|| Arc::new(AtomicBool::new(false)));
+        BackgroundTask {
+            handle: self.handle,
+            cancellation_token,
+        }
+    }
+}
+
+/// 在截止时间前等待线程结束，超时返回 None；未结束的线程会随进程退出
+fn join_with_deadline(handle: JoinHandle<()>, deadline: Instant) -> Option<thread::Result<()>> {
+    while !handle.is_finished() {
+        if Instant::now() >= deadline {
+            return None;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    Some(handle.join())
+}
+
+/// 向前端的“实验记录”追加一条里程碑事件，同时写入日志
+pub(crate) fn record_event(tx: &Sender<Update>, message: String) {
+    info!("{}", message);
+    let _ = tx.send(Update::General(GeneralUpdate::ExperimentEvent(
+        crate::communication::ExperimentEvent {
+            timestamp: chrono::Local::now(),
+            message,
+        },
+    )));
+}
+
 fn dispatch_command(
     command: Command,
     state: Arc<Mutex<BackendState>>,
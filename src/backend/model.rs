@@ -1,4 +1,4 @@
-use super::{Arc, BackendState, CancellationToken, Mutex, TrainingState};
+use super::{Arc, BackendState, CameraSettings, CancellationToken, Mutex, TrainingState};
 use crate::communication::*;
 use anyhow::{anyhow, Result};
 use crossbeam_channel::{ Sender};
@@ -7,9 +7,18 @@ use linfa_logistic::{FittedLogisticRegression, LogisticRegression};
 use ndarray::{Array1, Array2, ArrayBase, Dim, OwnedRepr};
 use opencv::{core, imgproc, prelude::*, videoio};
 use rand::thread_rng;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
 use tracing::info;
 
+// 置信度预览的刷新间隔，避免每帧都做推理
+const PREVIEW_INTERVAL: Duration = Duration::from_millis(200);
+// 模型输入图像的边长，特征数为其平方
+pub const FEATURE_SIZE: u32 = 20;
+
 pub fn process_frame_for_ml(
     frame: &Mat,
     min_radius: i32,
@@ -25,35 +34,13 @@ pub fn process_frame_for_ml(
         core::AlgorithmHint::ALGO_HINT_DEFAULT,
     )?;
 
-    let (center, radius) = if cir.is_none() {
-        let mut circles = core::Vector::<core::Vec3f>::new();
-        imgproc::hough_circles(
-            &gray,
-            &mut circles,
-            imgproc::HOUGH_GRADIENT,
-            1.0,
-            30.0,
-            40.0,
-            10.0,
-            min_radius,
-            max_radius,
-        )?;
-
-        if circles.is_empty() {
-            return Err(anyhow!("找不到圆"));
-        }
-
-        let p = circles.get(0)?;
-        let center = core::Point::new(p[0] as i32, p[1] as i32);
-        let radius = p[2] as i32;
-        (center, radius)
-    } else {
-        let center = core::Point::new(cir.unwrap().0 as i32, cir.unwrap().1 as i32);
-        let radius = cir.unwrap().2 as i32;
-        (center, radius)
+    // 没有锁定的圆时与画面显示共用同一组霍夫参数定位区域
+    let (x, y, radius) = match cir {
+        Some(circle) => circle,
+        None => super::camera::find_circle_gray(&gray, min_radius, max_radius)?
+            .ok_or_else(|| anyhow!("找不到圆"))?,
     };
-    // 霍夫圆检测以定位区域
-
+    let center = core::Point::new(x, y);
     // 裁剪并缩放
     let rect = core::Rect::new(center.x - radius, center.y - radius, radius * 2, radius * 2);
     let cropped = Mat::roi(&gray, rect)?;
@@ -61,7 +48,7 @@ pub fn process_frame_for_ml(
     imgproc::resize(
         &cropped,
         &mut resized,
-        core::Size::new(20, 20),
+        core::Size::new(FEATURE_SIZE as i32, FEATURE_SIZE as i32),
         0.0,
         0.0,
         imgproc::INTER_AREA,
@@ -77,115 +64,277 @@ pub fn process_frame_for_ml(
     Ok(flat)
 }
 
+/// 把特征图换算为模型输入；训练与识别必须使用同一种归一化
+pub fn normalize_features(image: &[u8], normalization: Normalization) -> Vec<f64> {
+    match normalization {
+        Normalization::None => image.iter().map(|&p| p as f64 / 255.0).collect(),
+        Normalization::Standardize => {
+            let n = image.len().max(1) as f64;
+            let mean = image.iter().map(|&p| p as f64).sum::<f64>() / n;
+            let variance = image.iter().map(|&p| (p as f64 - mean).powi(2)).sum::<f64>() / n;
+            // 整幅图几乎同一亮度时不放大噪声
+            let std = variance.sqrt().max(1.0);
+            image.iter().map(|&p| (p as f64 - mean) / std).collect()
+        }
+        Normalization::HistogramEqualize => {
+            let mut histogram = [0usize; 256];
+            for &p in image {
+                histogram[p as usize] += 1;
+            }
+            let mut cdf = [0usize; 256];
+            let mut total = 0;
+            for (value, count) in histogram.iter().enumerate() {
+                total += count;
+                cdf[value] = total;
+            }
+            let cdf_min = cdf.iter().copied().find(|&c| c > 0).unwrap_or(0);
+            let range = (image.len() - cdf_min.min(image.len())).max(1) as f64;
+            image
+                .iter()
+                .map(|&p| (cdf[p as usize] - cdf_min) as f64 / range)
+                .collect()
+        }
+    }
+}
+
+/// 训练模型时的特征与裁剪设置。识别时若与之不同，送入模型的图像与训练数据不再一致，
+/// 识别会悄悄变差，因此随模型一起保存以便对照
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureConfig {
+    pub feature_size: u32,
+    pub min_radius: i32,
+    pub max_radius: i32,
+    pub normalization: Normalization,
+}
+
+impl FeatureConfig {
+    pub fn current(settings: &CameraSettings, normalization: Normalization) -> Self {
+        Self {
+            feature_size: FEATURE_SIZE,
+            min_radius: settings.min_radius,
+            max_radius: settings.max_radius,
+            normalization,
+        }
+    }
+
+    /// 逐条列出当前设置与训练时不一致的地方
+    pub fn differences(&self, settings: &CameraSettings) -> Vec<String> {
+        let current = Self::current(settings, self.normalization);
+        let mut diffs = Vec::new();
+        if current.feature_size != self.feature_size {
+            diffs.push(format!(
+                "特征尺寸 训练时 {0}×{0}，当前 {1}×{1}",
+                self.feature_size, current.feature_size
+            ));
+        }
+        if (current.min_radius, current.max_radius) != (self.min_radius, self.max_radius) {
+            diffs.push(format!(
+                "半径范围 训练时 {}~{}，当前 {}~{}",
+                self.min_radius, self.max_radius, current.min_radius, current.max_radius
+            ));
+        }
+        if let (true, Some((_, _, radius))) = (settings.lock_circle, settings.locked_circle) {
+            if radius < self.min_radius || radius > self.max_radius {
+                diffs.push(format!(
+                    "锁定的圆半径 {} 不在训练时的范围 {}~{} 内",
+                    radius, self.min_radius, self.max_radius
+                ));
+            }
+        }
+        diffs
+    }
+}
+
+/// 对照当前识别设置与模型训练时的设置，不一致时记录警告；结果（None 表示一致或无模型）发给前端
+pub fn check_feature_config(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Result<()> {
+    let warning = {
+        let s = state.lock();
+        s.training.model_config.as_ref().and_then(|config| {
+            let diffs = config.differences(&s.devices.camera_settings().lock());
+            (!diffs.is_empty()).then(|| format!("当前识别设置与模型训练时不同：{}", diffs.join("；")))
+        })
+    };
+    if let Some(warning) = &warning {
+        tracing::warn!("{}", warning);
+    }
+    tx.send(Update::Training(TrainingUpdate::FeatureConfigWarning(warning)))?;
+    Ok(())
+}
+
+/// 单帧识别结果：模型类别 0 为 MAM，1 为 AMA；Unknown 表示该位置还没有识别结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prediction {
+    Mam,
+    Ama,
+    Unknown,
+}
+
+impl Prediction {
+    /// 由模型输出的类别编号换算，0、1 以外的编号视为没有结果
+    pub fn from_class(class: usize) -> Self {
+        match class {
+            0 => Prediction::Mam,
+            1 => Prediction::Ama,
+            _ => Prediction::Unknown,
+        }
+    }
+
+    /// 对调 MAM 与 AMA，Unknown 保持不变
+    pub fn flipped(self) -> Self {
+        match self {
+            Prediction::Mam => Prediction::Ama,
+            Prediction::Ama => Prediction::Mam,
+            Prediction::Unknown => Prediction::Unknown,
+        }
+    }
+}
+
 pub fn predict_from_frame(
     frame: &Mat,
     model: &FittedLogisticRegression<f64, usize>,
     min_radius: i32,
     max_radius: i32,
     cir: Option<(i32, i32, i32)>,
-) -> Result<usize> {
+    normalization: Normalization,
+) -> Result<Prediction> {
     let features_u8 = process_frame_for_ml(frame, min_radius, max_radius, cir)?;
-    let features_f64: Vec<f64> = features_u8.iter().map(|&p| p as f64 / 255.0).collect();
+    let features_f64 = normalize_features(&features_u8, normalization);
     let features_arr = Array1::from(features_f64);
 
     // (已优化) 不再需要 new_from_raw，直接使用传入的、已存在的模型对象进行预测
     let dataset = DatasetBase::from(features_arr.insert_axis(ndarray::Axis(0)));
     let prediction = model.predict(&dataset);
 
-    Ok(prediction[0])
+    Ok(Prediction::from_class(prediction[0]))
+}
+
+/// 返回预测类别以及该类别的概率（置信度）
+pub fn predict_proba_from_frame(
+    frame: &Mat,
+    model: &FittedLogisticRegression<f64, usize>,
+    min_radius: i32,
+    max_radius: i32,
+    cir: Option<(i32, i32, i32)>,
+    normalization: Normalization,
+) -> Result<(usize, f64)> {
+    let features_u8 = process_frame_for_ml(frame, min_radius, max_radius, cir)?;
+    let features_f64 = normalize_features(&features_u8, normalization);
+    let features_arr = Array1::from(features_f64).insert_axis(ndarray::Axis(0));
+
+    let probability = model.predict_probabilities(&features_arr)[0];
+    let prediction = model.predict(&DatasetBase::from(features_arr))[0];
+    // predict_probabilities 给出的是正类概率，这里统一换算为所预测类别的概率
+    Ok((prediction, probability.max(1.0 - probability)))
+}
+
+/// 置信度预览循环：周期性地对最新一帧做推理，直到被关闭
+pub fn run_prediction_preview(
+    state: &Arc<Mutex<BackendState>>,
+    tx: &Sender<Update>,
+    token: CancellationToken,
+) -> Result<()> {
+    info!("识别置信度预览已开启");
+    while !token.load(Ordering::Relaxed) {
+        let sample = {
+            let s = state.lock();
+            match (
+                s.training.fitted_model.as_ref(),
+                s.devices.camera_manager(),
+            ) {
+                (Some(model), Some(manager)) => {
+                    let frame = manager.latest_frame.lock().clone();
+                    let settings = s.devices.camera_settings().lock().clone();
+                    let normalization = s.training.model_normalization();
+                    frame.map(|f| (f, model.clone(), settings, normalization))
+                }
+                _ => None,
+            }
+        };
+        let preview = sample.and_then(|(frame, model, settings, normalization)| {
+            let circle = if settings.lock_circle {
+                settings.locked_circle
+            } else {
+                None
+            };
+            predict_proba_from_frame(
+                &frame,
+                &model,
+                settings.min_radius,
+                settings.max_radius,
+                circle,
+                normalization,
+            )
+            .ok()
+        });
+        tx.send(Update::Device(DeviceUpdate::PredictionPreview(preview)))?;
+        thread::sleep(PREVIEW_INTERVAL);
+    }
+    state.lock().devices.prediction_preview_token = None;
+    tx.send(Update::Device(DeviceUpdate::PredictionPreview(None)))?;
+    info!("识别置信度预览已关闭");
+    Ok(())
+}
+
+/// 从单个视频逐帧提取训练图像，替换对应类别（"MAM" / "AMA"）的录制数据，返回提取的图像数
+pub fn process_video_for_training(
+    state: &Arc<Mutex<BackendState>>,
+    video_path: &Path,
+    mode: &str,
+    tx: &Sender<Update>,
+    token: &CancellationToken,
+) -> Result<usize> {
+    info!("开始处理视频: {:?}, 模式: {}", video_path, mode);
+    let send_progress = |message: String| {
+        tx.send(Update::Training(TrainingUpdate::VideoProcessingUpdate {
+            mode: mode.to_string(),
+            message,
+        }))
+    };
+    send_progress("打开视频...".to_string())?;
+    let settings = state.lock().devices.camera_settings().lock().clone();
+    let circle = if settings.lock_circle { settings.locked_circle } else { None };
+    let mut cap =
+        videoio::VideoCapture::from_file(&video_path.to_string_lossy(), videoio::CAP_ANY)?;
+    if !cap.is_opened()? {
+        send_progress("无法打开视频".to_string())?;
+        return Err(anyhow!("无法打开视频 {}", video_path.display()));
+    }
+    let total_frames = cap.get(videoio::CAP_PROP_FRAME_COUNT).unwrap_or(0.0) as u32;
+    let mut images = Vec::new();
+    let mut frame_count = 0;
+    let mut frame = Mat::default();
+    while cap.read(&mut frame)? && !frame.empty() {
+        if token.load(Ordering::Relaxed) {
+            return Err(anyhow!("视频处理已取消"));
+        }
+        frame_count += 1;
+        if frame_count % 10 == 0 {
+            send_progress(format!("处理中: {}/{}", frame_count, total_frames))?;
+        }
+        if let Ok(processed) =
+            process_frame_for_ml(&frame, settings.min_radius, settings.max_radius, circle)
+        {
+            images.push(processed);
+        }
+    }
+    let extracted = images.len();
+    if extracted == 0 {
+        send_progress("未提取到图像".to_string())?;
+        return Err(anyhow!("{} 视频中没有识别到视场，请检查圆形识别设置", mode));
+    }
+    {
+        let training_state = &mut state.lock().training;
+        if mode == "MAM" {
+            training_state.mam_images = images;
+        } else {
+            training_state.ama_images = images;
+        }
+    }
+    send_progress(format!("完成，{} 帧中提取 {} 张", frame_count, extracted))?;
+    info!("视频处理完成: {:?}, 提取 {} 张", video_path, extracted);
+    Ok(extracted)
 }
 
-// pub fn process_video_for_training(
-//     state: &Arc<Mutex<BackendState>>,
-//     video_path: &PathBuf,
-//     mode: &str,
-//     tx: &Sender<Update>,
-//     token: CancellationToken,
-// ) -> Result<()> {
-//     info!("[后端] 开始处理视频: {:?}, 模式: {}", video_path, mode);
-//     tx.send(Update::Training(TrainingUpdate::VideoProcessingUpdate {
-//         mode: mode.to_string(),
-//         message: "打开视频...".to_string(),
-//     }))
-//     .unwrap();
-//     let guard1 = state.lock();
-//     let guard2 = guard1.devices.camera_settings.lock();
-//     let circle = {
-//         if guard2.lock_circle {
-//             guard2.locked_circle
-//         } else {
-//             None
-//         }
-//     };
-//     let mut cap =
-//         match videoio::VideoCapture::from_file(video_path.to_str().unwrap(), videoio::CAP_ANY) {
-//             Ok(cap) => cap,
-//             Err(_e) => {
-//                 tx.send(Update::Training(TrainingUpdate::VideoProcessingUpdate {
-//                     mode: mode.to_string(),
-//                     message: "错误了".to_string(),
-//                 }))
-//                 .unwrap();
-//                 return Ok(());
-//             }
-//         };
-//     let total_frames = cap.get(videoio::CAP_PROP_FRAME_COUNT).unwrap_or(0.0) as u32;
-//     let mut processed_images = Vec::new();
-//     let mut frame_count = 0;
-//     let min_radius = guard2.min_radius;
-//     let max_radius = guard2.max_radius;
-//     drop(guard2);
-//     drop(guard1);
-//     while let Ok(true) = cap.is_opened() {
-//         if token.load(std::sync::atomic::Ordering::Relaxed) {
-//             break;
-//         }
-//         let mut frame = Mat::default();
-//         if let Ok(true) = cap.read(&mut frame) {
-//             if frame.empty() {
-//                 break;
-//             }
-//             frame_count += 1;
-//             // info!("yep");
-//             if frame_count % 10 == 0 {
-//                 // 每10帧更新一次进度
-//                 let msg = format!("处理中: {}/{}", frame_count, total_frames);
-//                 tx.send(Update::Training(TrainingUpdate::VideoProcessingUpdate {
-//                     mode: mode.to_string(),
-//                     message: msg,
-//                 }))
-//                 .unwrap();
-//             }
-//             if let Ok(processed) = process_frame_for_ml(&frame, min_radius, max_radius, circle) {
-//                 processed_images.push(processed);
-//             }
-//         } else {
-//             break;
-//         }
-//     }
-
-//     if mode == "MAM" {
-//         state.lock().training.mam_images = processed_images;
-//         info!("man");
-//         tx.send(Update::Training(TrainingUpdate::MAMDatasetStatus(
-//             "完成".to_string(),
-//         )))
-//         .unwrap();
-//         info!("man");
-//     } else {
-//         state.lock().training.ama_images = processed_images;
-//         tx.send(Update::Training(TrainingUpdate::AMADatasetStatus(
-//             "完成".to_string(),
-//         )))
-//         .unwrap();
-//     }
-//     tx.send(Update::Training(TrainingUpdate::VideoProcessingUpdate {
-//         mode: mode.to_string(),
-//         message: format!("完成, 提取了 {} 帧", frame_count),
-//     }))
-//     .unwrap();
-//     Ok(())
-// }
 pub fn load_recorded_dataset(
     state: &Arc<Mutex<BackendState>>,
     path: &Path,
@@ -196,39 +345,16 @@ pub fn load_recorded_dataset(
         "正在加载".to_string(),
     )))
     .unwrap();
-    let mut loaded_mam = 0;
-    let mut loaded_ama = 0;
-
-    // 加载 dataset0 (MAM)
-    let mam_path = path.join("dataset0");
-    let training_state = &mut state.lock().training;
-    training_state.mam_images.clear();
-    if let Ok(entries) = std::fs::read_dir(mam_path) {
-        for entry in entries.flatten() {
-            if let Ok(img) = image::open(entry.path()) {
-                let luma_img = img.to_luma8();
-                // 注意：这里我们假设图片已经是20x20，如果不是，还需要resize
-                // let resized = image::imageops::resize(&luma_img, 20, 20, image::imageops::FilterType::Triangle);
-                training_state.mam_images.push(luma_img.into_raw());
-                loaded_mam += 1;
-            }
-        }
+    let (mam, skipped_mam) = load_image_folder(&path.join("dataset0"));
+    let (ama, skipped_ama) = load_image_folder(&path.join("dataset1"));
+    let (loaded_mam, loaded_ama) = (mam.len(), ama.len());
+    {
+        let training_state = &mut state.lock().training;
+        training_state.mam_images = mam;
+        training_state.ama_images = ama;
     }
 
-    // 加载 dataset1 (AMA)
-    let ama_path = path.join("dataset1");
-    training_state.ama_images.clear();
-    if let Ok(entries) = std::fs::read_dir(ama_path) {
-        for entry in entries.flatten() {
-            if let Ok(img) = image::open(entry.path()) {
-                let luma_img = img.to_luma8();
-                training_state.ama_images.push(luma_img.into_raw());
-                loaded_ama += 1;
-            }
-        }
-    }
-
-    let msg = format!("MAM {}, AMA {}",loaded_mam,loaded_ama);
+    let msg = dataset_status(loaded_mam, loaded_ama, skipped_mam + skipped_ama);
     info!("录制数据集加载完成：{}", msg);
     tx.send(Update::Training(TrainingUpdate::MAMDatasetStatus(
         msg,
@@ -237,6 +363,50 @@ pub fn load_recorded_dataset(
     Ok(())
 }
 
+/// 读取数据集文件夹中的全部图片并统一为 FEATURE_SIZE×FEATURE_SIZE 灰度图，
+/// 返回图片数据和因无法解码而跳过的文件数
+fn load_image_folder(dir: &Path) -> (Vec<Vec<u8>>, usize) {
+    let mut images = Vec::new();
+    let mut skipped = 0;
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (images, skipped);
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        match image::open(&path) {
+            Ok(img) => {
+                let mut luma_img = img.to_luma8();
+                // 尺寸不符的图片缩放到模型输入大小，否则训练时特征矩阵形状会出错
+                if luma_img.dimensions() != (FEATURE_SIZE, FEATURE_SIZE) {
+                    luma_img = image::imageops::resize(
+                        &luma_img,
+                        FEATURE_SIZE,
+                        FEATURE_SIZE,
+                        image::imageops::FilterType::Triangle,
+                    );
+                }
+                images.push(luma_img.into_raw());
+            }
+            Err(e) => {
+                tracing::warn!("跳过无法读取的图片 {:?}：{}", path, e);
+                skipped += 1;
+            }
+        }
+    }
+    (images, skipped)
+}
+
+fn dataset_status(mam: usize, ama: usize, skipped: usize) -> String {
+    if skipped > 0 {
+        format!("MAM {}, AMA {}（跳过 {} 个无法读取的文件）", mam, ama, skipped)
+    } else {
+        format!("MAM {}, AMA {}", mam, ama)
+    }
+}
+
 pub fn train_model(
     state: &Arc<Mutex<BackendState>>,
     show_roc: bool,
@@ -245,6 +415,12 @@ pub fn train_model(
 ) -> Result<()> {
     info!("开始训练模型");
 
+    let feature_config = {
+        let s = state.lock();
+        let settings = s.devices.camera_settings().lock();
+        FeatureConfig::current(&settings, s.training.normalization)
+    };
+    let normalization = feature_config.normalization;
     let training_state = &mut state.lock().training;
 
     let all_mam = [
@@ -258,6 +434,8 @@ pub fn train_model(
     ]
     .concat();
     info!("最终数据量——MAM：{}；AMA：{}",all_mam.len(),all_ama.len());
+    let recorded = training_state.mam_images.len() + training_state.ama_images.len();
+    let persistent = training_state.persistent_mam.len() + training_state.persistent_ama.len();
     if all_mam.is_empty() || all_ama.is_empty() {
         tx.send(Update::Training(TrainingUpdate::TrainingStatus(
             "数据集为空".to_string(),
@@ -269,14 +447,14 @@ pub fn train_model(
     let mam_records = all_mam.len();
     let ama_records = all_ama.len();
     let records = mam_records + ama_records;
-    let features = 400; // 20x20
+    let features = (FEATURE_SIZE * FEATURE_SIZE) as usize;
     let mut data_vec: Vec<f64> = Vec::with_capacity(records * features);
     all_mam
         .iter()
-        .for_each(|img| data_vec.extend(img.iter().map(|&p| p as f64 / 255.0)));
+        .for_each(|img| data_vec.extend(normalize_features(img, normalization)));
     all_ama
         .iter()
-        .for_each(|img| data_vec.extend(img.iter().map(|&p| p as f64 / 255.0)));
+        .for_each(|img| data_vec.extend(normalize_features(img, normalization)));
     let data_array = Array2::from_shape_vec((records, features), data_vec).unwrap();
 
     let mut labels_vec: Vec<usize> = Vec::with_capacity(records);
@@ -293,11 +471,25 @@ pub fn train_model(
         LogisticRegression::default().fit(&train).unwrap();
 
     training_state.fitted_model = Some(model.clone());
+    training_state.model_config = Some(feature_config);
     let predictions = model.predict(&valid);
     let cm = predictions.confusion_matrix(valid.targets()).unwrap();
     let accuracy = cm.accuracy();
     let cm = calculate_binary_confusion_matrix(&predictions, valid.targets());
-    info!("训练完成，模型准确度: {}", accuracy);
+    let roc = roc_curve(&ama_scores(&model, valid.records()), &valid.targets().to_vec());
+    match &roc {
+        Some(roc) => info!("验证集 ROC 曲线 AUC: {:.4}", roc.auc),
+        None => tracing::warn!("验证集中缺少 MAM 或 AMA 样本，跳过 ROC 曲线"),
+    }
+    info!("训练完成，模型准确度: {}（归一化 {:?}）", accuracy, normalization);
+    training_state.last_evaluation = Some(ConfusionMatrixData {
+        matrix: cm,
+        accuracy,
+    });
+    training_state.last_composition = Some((recorded, persistent));
+    let composition = format!("模型基于 {} 录制 + {} 常驻帧", recorded, persistent);
+    info!("{}", composition);
+    tx.send(Update::Training(TrainingUpdate::TrainingStatus(composition)))?;
 
     // 发送图表数据
     tx.send(Update::Training(TrainingUpdate::TrainingPlotsReady {
@@ -309,11 +501,12 @@ pub fn train_model(
         } else {
             None
         },
-        roc: if show_roc { None } else { None }, // ROC 计算较复杂，暂留空
+        roc: if show_roc { roc } else { None },
     }))
     .unwrap();
 
     tx.send(Update::Training(TrainingUpdate::ModelReady(true)))?;
+    tx.send(Update::Training(TrainingUpdate::FeatureConfigWarning(None)))?;
 
     Ok(())
 }
@@ -328,39 +521,16 @@ pub fn load_persistent_dataset(
         "正在加载".to_string(),
     )))
     .unwrap();
-    let mut loaded_mam = 0;
-    let mut loaded_ama = 0;
-
-    // 加载 dataset0 (MAM)
-    let mam_path = path.join("dataset0");
-    let training_state = &mut state.lock().training;
-    training_state.persistent_mam.clear();
-    if let Ok(entries) = std::fs::read_dir(mam_path) {
-        for entry in entries.flatten() {
-            if let Ok(img) = image::open(entry.path()) {
-                let luma_img = img.to_luma8();
-                // 注意：这里我们假设图片已经是20x20，如果不是，还需要resize
-                // let resized = image::imageops::resize(&luma_img, 20, 20, image::imageops::FilterType::Triangle);
-                training_state.persistent_mam.push(luma_img.into_raw());
-                loaded_mam += 1;
-            }
-        }
-    }
-
-    // 加载 dataset1 (AMA)
-    let ama_path = path.join("dataset1");
-    training_state.persistent_ama.clear();
-    if let Ok(entries) = std::fs::read_dir(ama_path) {
-        for entry in entries.flatten() {
-            if let Ok(img) = image::open(entry.path()) {
-                let luma_img = img.to_luma8();
-                training_state.persistent_ama.push(luma_img.into_raw());
-                loaded_ama += 1;
-            }
-        }
+    let (mam, skipped_mam) = load_image_folder(&path.join("dataset0"));
+    let (ama, skipped_ama) = load_image_folder(&path.join("dataset1"));
+    let (loaded_mam, loaded_ama) = (mam.len(), ama.len());
+    {
+        let training_state = &mut state.lock().training;
+        training_state.persistent_mam = mam;
+        training_state.persistent_ama = ama;
     }
 
-    let msg = format!("MAM {}, AMA {}",loaded_mam,loaded_ama);
+    let msg = dataset_status(loaded_mam, loaded_ama, skipped_mam + skipped_ama);
     info!("数据集加载完成 {}", msg);
     tx.send(Update::Training(TrainingUpdate::PersistentDatasetStatus(
         msg,
@@ -369,11 +539,117 @@ pub fn load_persistent_dataset(
     Ok(())
 }
 
+pub fn export_evaluation(state: &Arc<Mutex<BackendState>>, path: &Path) -> Result<()> {
+    let (evaluation, composition) = {
+        let s = state.lock();
+        (s.training.last_evaluation.clone(), s.training.last_composition)
+    };
+    let Some(cm) = evaluation else {
+        return Err(anyhow!("尚未训练模型，没有可导出的评估结果"));
+    };
+    let [[tn, fp], [fn_, tp]] = cm.matrix;
+    let ratio = |num: u32, den: u32| {
+        if den == 0 {
+            f64::NAN
+        } else {
+            num as f64 / den as f64
+        }
+    };
+    let mut content = String::from("metric,value\n");
+    content.push_str(&format!("TN (MAM->MAM),{}\n", tn));
+    content.push_str(&format!("FP (MAM->AMA),{}\n", fp));
+    content.push_str(&format!("FN (AMA->MAM),{}\n", fn_));
+    content.push_str(&format!("TP (AMA->AMA),{}\n", tp));
+    content.push_str(&format!("accuracy,{}\n", cm.accuracy));
+    content.push_str(&format!("MAM precision,{}\n", ratio(tn, tn + fn_)));
+    content.push_str(&format!("MAM recall,{}\n", ratio(tn, tn + fp)));
+    content.push_str(&format!("AMA precision,{}\n", ratio(tp, tp + fp)));
+    content.push_str(&format!("AMA recall,{}\n", ratio(tp, tp + fn_)));
+    if let Some((recorded, persistent)) = composition {
+        content.push_str(&format!("recorded frames,{}\n", recorded));
+        content.push_str(&format!("persistent frames,{}\n", persistent));
+    }
+    std::fs::write(path, content)?;
+    info!("模型评估已导出到 {:?}", path);
+    Ok(())
+}
+
+/// 模型文件的内容：拟合好的模型连同训练时的特征设置，加载后仍能对照当前识别设置
+#[derive(Serialize, Deserialize)]
+struct SavedModel {
+    feature_dim: usize,
+    config: FeatureConfig,
+    model: FittedLogisticRegression<f64, usize>,
+    // 训练所用的 (录制帧数, 常驻帧数)；旧版模型文件没有这一项
+    #[serde(default)]
+    composition: Option<(usize, usize)>,
+}
+
+pub fn save_model(state: &Arc<Mutex<BackendState>>, path: &Path) -> Result<()> {
+    let content = {
+        let s = state.lock();
+        let (Some(model), Some(config)) = (&s.training.fitted_model, &s.training.model_config)
+        else {
+            return Err(anyhow!("尚未训练模型，没有可保存的模型"));
+        };
+        let saved = SavedModel {
+            feature_dim: model.params().len(),
+            config: config.clone(),
+            model: model.clone(),
+            composition: s.training.last_composition,
+        };
+        serde_json::to_string(&saved)?
+    };
+    std::fs::write(path, content)?;
+    info!("模型已保存到 {:?}", path);
+    Ok(())
+}
+
+pub fn load_model(state: &Arc<Mutex<BackendState>>, path: &Path, tx: &Sender<Update>) -> Result<()> {
+    let content = std::fs::read_to_string(path)?;
+    let saved: SavedModel =
+        serde_json::from_str(&content).map_err(|e| anyhow!("无法解析模型文件：{}", e))?;
+    let expected = (FEATURE_SIZE * FEATURE_SIZE) as usize;
+    if saved.feature_dim != expected || saved.model.params().len() != expected {
+        return Err(anyhow!(
+            "模型特征维数为 {}，与当前程序的 {} 不一致，无法使用",
+            saved.feature_dim,
+            expected
+        ));
+    }
+    let normalization = saved.config.normalization;
+    let composition = saved.composition;
+    {
+        let training = &mut state.lock().training;
+        training.fitted_model = Some(saved.model);
+        training.model_config = Some(saved.config);
+        training.last_evaluation = None;
+        training.last_composition = composition;
+    }
+    info!("已从 {:?} 加载模型（归一化 {:?}）", path, normalization);
+    tx.send(Update::Training(TrainingUpdate::ModelReady(true)))?;
+    let status = match composition {
+        Some((recorded, persistent)) => format!(
+            "已加载模型 {}（基于 {} 录制 + {} 常驻帧）",
+            path.display(),
+            recorded,
+            persistent
+        ),
+        None => format!("已加载模型 {}", path.display()),
+    };
+    tx.send(Update::Training(TrainingUpdate::TrainingStatus(status)))?;
+    check_feature_config(state, tx)?;
+    Ok(())
+}
+
 pub fn reset_model(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Result<()> {
     let mut s = state.lock();
+    let normalization = s.training.normalization;
     s.training = TrainingState::new(); // 重置为新的空状态
+    s.training.normalization = normalization; // 归一化是用户设置，不随模型清除
 
     tx.send(Update::Training(TrainingUpdate::ModelReady(false)))?;
+    tx.send(Update::Training(TrainingUpdate::FeatureConfigWarning(None)))?;
     tx.send(Update::Training(TrainingUpdate::TrainingStatus(
         "无可用模型".to_string(),
     )))?;
@@ -382,6 +658,53 @@ pub fn reset_model(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Res
     Ok(())
 }
 
+/// 每个样本属于 AMA（类别 1）的概率。predict_probabilities 只给出正类概率，
+/// 这里与 predict_proba_from_frame 一样借助预测类别换算，不依赖正类是哪一类
+fn ama_scores(model: &FittedLogisticRegression<f64, usize>, records: &Array2<f64>) -> Vec<f64> {
+    let probabilities = model.predict_probabilities(records);
+    let predictions = model.predict(records);
+    probabilities
+        .iter()
+        .zip(predictions.iter())
+        .map(|(&p, &class)| {
+            let confidence = p.max(1.0 - p);
+            if class == 1 { confidence } else { 1.0 - confidence }
+        })
+        .collect()
+}
+
+/// 从高到低扫过判定阈值，得到 (假阳性率, 真阳性率) 折线及其下面积（AMA 为阳性）。
+/// 任一类别没有样本时无法计算，返回 None
+fn roc_curve(scores: &[f64], labels: &[usize]) -> Option<RocCurveData> {
+    let positives = labels.iter().filter(|&&l| l == 1).count();
+    let negatives = labels.len() - positives;
+    if positives == 0 || negatives == 0 || scores.len() != labels.len() {
+        return None;
+    }
+    let mut samples: Vec<(f64, usize)> = scores.iter().copied().zip(labels.iter().copied()).collect();
+    samples.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut points = vec![(0.0, 0.0)];
+    let (mut tp, mut fp) = (0usize, 0usize);
+    let mut auc = 0.0;
+    for (i, &(score, label)) in samples.iter().enumerate() {
+        if label == 1 {
+            tp += 1;
+        } else {
+            fp += 1;
+        }
+        // 分数相同的样本在同一个阈值下一起翻转，只在分组末尾取点
+        if samples.get(i + 1).is_some_and(|next| next.0 == score) {
+            continue;
+        }
+        let point = (fp as f64 / negatives as f64, tp as f64 / positives as f64);
+        let last = points[points.len() - 1];
+        auc += (point.0 - last.0) * (point.1 + last.1) / 2.0;
+        points.push(point);
+    }
+    Some(RocCurveData { points, auc })
+}
+
 fn calculate_binary_confusion_matrix(
     predictions: &ArrayBase<OwnedRepr<usize>, Dim<[usize; 1]>>,
     targets: &ArrayBase<OwnedRepr<usize>, Dim<[usize; 1]>>,
@@ -416,3 +739,50 @@ fn calculate_binary_confusion_matrix(
 
     confusion_matrix
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalization_removes_overall_brightness() {
+        let dark: Vec<u8> = (0..20).map(|i| 40 + i).collect();
+        let bright: Vec<u8> = dark.iter().map(|p| p + 100).collect();
+        // 仅除以 255 时亮度差异保留，标准化与均衡化后两者一致
+        assert_ne!(
+            normalize_features(&dark, Normalization::None),
+            normalize_features(&bright, Normalization::None)
+        );
+        for normalization in [Normalization::Standardize, Normalization::HistogramEqualize] {
+            assert_eq!(
+                normalize_features(&dark, normalization),
+                normalize_features(&bright, normalization)
+            );
+        }
+        let equalized = normalize_features(&dark, Normalization::HistogramEqualize);
+        assert_eq!(equalized.first(), Some(&0.0));
+        assert_eq!(equalized.last(), Some(&1.0));
+    }
+
+    #[test]
+    fn roc_curve_of_separable_and_tied_scores() {
+        let perfect = roc_curve(&[0.1, 0.2, 0.8, 0.9], &[0, 0, 1, 1]).unwrap();
+        assert_eq!(perfect.auc, 1.0);
+        assert_eq!(perfect.points.first(), Some(&(0.0, 0.0)));
+        assert_eq!(perfect.points.last(), Some(&(1.0, 1.0)));
+        // 分数全部相同时只有一个阈值，曲线为对角线
+        let tied = roc_curve(&[0.5; 4], &[0, 1, 0, 1]).unwrap();
+        assert_eq!(tied.points, vec![(0.0, 0.0), (1.0, 1.0)]);
+        assert_eq!(tied.auc, 0.5);
+        assert!(roc_curve(&[0.3, 0.7], &[1, 1]).is_none());
+    }
+
+    #[test]
+    fn normalization_handles_uniform_image() {
+        let flat = vec![128u8; 400];
+        assert!(normalize_features(&flat, Normalization::Standardize).iter().all(|&v| v == 0.0));
+        assert!(normalize_features(&flat, Normalization::HistogramEqualize)
+            .iter()
+            .all(|v| v.is_finite()));
+    }
+}
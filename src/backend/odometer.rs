@@ -0,0 +1,39 @@
+// src/backend/odometer.rs
+//
+// 电机累计转动步数：保存在设置文件中，与具体实验无关，用于安排维护。
+// 测量代码只通过 measurement::add_odometer 累加，保存与推送由这里负责。
+
+use super::{Arc, BackendState, Mutex};
+use crate::communication::{DeviceUpdate, Update};
+use crate::settings::AppSettings;
+use anyhow::Result;
+use crossbeam_channel::Sender;
+use tracing::info;
+
+/// 读取上次保存的累计步数，没有保存过时从 0 开始
+pub fn load() -> u64 {
+    AppSettings::load().odometer
+}
+
+/// 累计步数有变化时保存并推送；转动中不逐步写文件，由监控线程定期调用
+pub fn flush(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Result<()> {
+    let value = {
+        let mut s = state.lock();
+        if s.devices.odometer == s.devices.odometer_saved {
+            return Ok(());
+        }
+        s.devices.odometer_saved = s.devices.odometer;
+        s.devices.odometer
+    };
+    if let Err(e) = AppSettings::update(|settings| settings.odometer = value) {
+        tracing::warn!("保存累计步数失败：{}", e);
+    }
+    tx.send(Update::Device(DeviceUpdate::Odometer(value)))?;
+    Ok(())
+}
+
+pub fn reset(state: &Arc<Mutex<BackendState>>, tx: &Sender<Update>) -> Result<()> {
+    let previous = std::mem::take(&mut state.lock().devices.odometer);
+    info!("累计步数已清零（清零前 {} 步）", previous);
+    flush(state, tx)
+}
@@ -5,14 +5,13 @@ use crate::communication::{RecordingStatus, RecordingUpdate, Update};
 use anyhow::Result;
 use crossbeam_channel::Sender;
 use opencv::{prelude::*, videoio};
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::PathBuf;
 use std::sync::atomic::Ordering;
 use std::time::{Duration, Instant};
 use tracing::info;
 
-const TARGET_FPS: f32 = 30.0;
-const FRAME_INTERVAL: Duration = Duration::from_micros((1_000_000.0 / TARGET_FPS) as u64);
-
 /// The main video recording loop, runs in its own thread.
 pub fn record_video_loop(
     state: &Arc<Mutex<BackendState>>,
@@ -20,13 +19,18 @@ pub fn record_video_loop(
     save_path: PathBuf,
     mode: String, // "MAM" or "AMA"
     num: i32,
+    fps: f32,
     token: CancellationToken,
 ) -> Result<()> {
     let state_guard = state.lock();
+    // 保存帧率不超过相机采集帧率，否则只会得到重复帧
+    let capture_fps = state_guard.devices.camera_settings().lock().capture_fps;
+    let fps = fps.clamp(1.0, capture_fps.max(1.0));
+    let frame_interval = Duration::from_secs_f32(1.0 / fps);
     let (serial_port_present, camera_present) = {
         (
             state_guard.devices.serial_port.is_some(),
-            state_guard.devices.camera_manager.is_some(),
+            state_guard.devices.camera_manager().is_some(),
         )
     };
     if !camera_present {
@@ -46,11 +50,19 @@ pub fn record_video_loop(
     }
     std::fs::create_dir_all(&target_dir)?;
     info!("处理后的帧将保存到: {:?}", target_dir);
+    // 旁路 CSV：记录每一帧对应的电机累计步数，便于事后对照帧与实际位置。
+    // 它只是辅助记录，创建或写入失败时记录日志并停止写入，不中断录制
+    let sidecar_path = save_path.join(format!("{}_frames.csv", dataset_folder_name));
+    let mut sidecar = std::fs::File::create(&sidecar_path)
+        .map(BufWriter::new)
+        .map_err(|e| tracing::error!("无法创建帧位置记录 {:?}: {}", sidecar_path, e))
+        .ok();
+    write_sidecar(&mut sidecar, format_args!("frame,elapsed_s,steps_moved,current_steps"));
 
     update_tx.send(Update::Recording(RecordingUpdate::StatusUpdate(
         RecordingStatus::Started,
     )))?;
-    info!("录制开始: {:?}, 模式: {}", save_path, mode);
+    info!("录制开始: {:?}, 模式: {}, 帧率: {:.1} fps", save_path, mode, fps);
     let state_clone = Arc::clone(state);
     let tx_clone = update_tx.clone();
     let rotation_handle = std::thread::spawn(move || {
@@ -86,7 +98,7 @@ pub fn record_video_loop(
             break;
         }
         let now = Instant::now();
-        if now.duration_since(last_frame_time) < FRAME_INTERVAL {
+        if now.duration_since(last_frame_time) < frame_interval {
             std::thread::sleep(Duration::from_millis(5));
             continue;
         }
@@ -94,13 +106,12 @@ pub fn record_video_loop(
         let state_guard = state.lock();
         let frame = state_guard
             .devices
-            .camera_manager
-            .as_ref()
+            .camera_manager()
             .unwrap()
             .latest_frame
             .lock()
             .clone();
-        let settings = state_guard.devices.camera_settings.lock().clone();
+        let settings = state_guard.devices.camera_settings().lock().clone();
         drop(state_guard);
         if let Some(frame) = frame {
             let circle = if settings.lock_circle {
@@ -126,14 +137,28 @@ pub fn record_video_loop(
                     ) {
                         tracing::error!("保存PNG帧失败 {:?}: {}", file_path, e);
                     }
+                    let (steps_moved, current_steps) = {
+                        let s = state.lock();
+                        (s.recording.steps_moved, s.measurement.current_steps)
+                    };
+                    write_sidecar(
+                        &mut sidecar,
+                        format_args!(
+                            "{},{:.3},{},{}",
+                            saved_frame_count,
+                            start_time.elapsed().as_secs_f64(),
+                            steps_moved,
+                            current_steps.map(|v| v.to_string()).unwrap_or_default()
+                        ),
+                    );
                 }
                 Err(e) => {
                     tracing::warn!("处理帧失败，跳过: {}", e);
                 }
             }
         } else {
-            state.lock().devices.camera_manager = None;
-            update_tx.send(Update::Device(crate::communication::DeviceUpdate::CameraConnectionStatus(false)))?;
+            state.lock().devices.drop_primary_camera(update_tx);
+            update_tx.send(super::camera::lost_status("录制中相机没有返回画面"))?;
             break;
         }
 
@@ -148,6 +173,12 @@ pub fn record_video_loop(
     // 保存总步数以备“倒带”
 
     info!("录制结束，共 {} 帧",saved_frame_count);
+    if let Some(mut writer) = sidecar {
+        match writer.flush() {
+            Ok(()) => info!("帧位置记录已保存到 {:?}", sidecar_path),
+            Err(e) => tracing::error!("帧位置记录写入失败: {}", e),
+        }
+    }
     if let Err(e) = rotation_handle.join() {
         tracing::error!("旋转线程 panic: {:?}", e);
     }
@@ -160,6 +191,16 @@ pub fn record_video_loop(
     Ok(())
 }
 
+/// 向旁路 CSV 写一行；失败时记录日志并丢弃写入器，之后的帧不再记录
+fn write_sidecar(sidecar: &mut Option<BufWriter<File>>, line: std::fmt::Arguments) {
+    if let Some(writer) = sidecar {
+        if let Err(e) = writeln!(writer, "{}", line) {
+            tracing::error!("帧位置记录写入失败，后续帧不再记录: {}", e);
+            *sidecar = None;
+        }
+    }
+}
+
 // 在 `src/backend/serial.rs` 中，您需要一个类似于 `rotate_motor` 的函数，但它接受步数
 // src/backend/serial.rs (示意)
 // pub fn precision_rotate_steps(state: &Arc<Mutex<BackendState>>, steps: i32) -> BackendResult<()> {
@@ -1,14 +1,78 @@
-use super::{Arc, BackendState, Mutex};
-use crate::{backend::{CancellationToken,measurement::cmd}, communication::{DeviceUpdate, GeneralUpdate, Update}};
+use super::{simulation::FakeSerialPort, Arc, BackendState, Mutex};
+use crate::{backend::{CancellationToken,measurement::{cmd_raw, HEARTBEAT_COMMAND}}, communication::{BackendError, DeviceUpdate, GeneralUpdate, SerialTestResult, Update}};
 use anyhow::Result;
 use crossbeam_channel::Sender;
 use serialport;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{error, info};
 use std::io::{BufRead,BufReader};
 use std::sync::atomic::Ordering;
 
-pub fn get_available_ports(token: CancellationToken) -> Vec<String> {
+/// 串口协议参数：不同固件的应答字符串与结束字节可能不同
+#[derive(Clone, Debug)]
+pub struct SerialProtocol {
+    // 每条指令执行完成后 Arduino 回复的应答字符串（去除首尾空白后比较）
+    pub ack_token: String,
+    // 指令字节之后追加发送的结束字节，None 表示不发送
+    pub stop_byte: Option<u8>,
+}
+
+impl Default for SerialProtocol {
+    fn default() -> Self {
+        Self {
+            ack_token: "1".to_string(),
+            stop_byte: None,
+        }
+    }
+}
+
+// 从未收到过应答且连续这么多次应答超时，判定为设备无应答（波特率或固件不对）
+const MUTE_TIMEOUT_RUN: u32 = 3;
+const MUTE_DIAGNOSTIC: &str = "串口已打开但设备无应答，检查波特率/固件";
+
+/// 串口应答统计：区分波特率/固件错误导致的“完全无应答”与偶发的超时
+#[derive(Default, Debug)]
+pub struct AckMonitor {
+    // 统计所对应的串口与波特率；换了其中之一就重新统计
+    link: Option<(String, u32)>,
+    // 该串口与波特率下是否收到过正确应答
+    seen: bool,
+    // 连续应答超时的次数
+    timeouts: u32,
+}
+
+impl AckMonitor {
+    fn connected(&mut self, port_name: &str, baud_rate: u32) {
+        let link = Some((port_name.to_string(), baud_rate));
+        if self.link != link {
+            *self = Self {
+                link,
+                ..Self::default()
+            };
+        }
+    }
+
+    /// 记录一次指令的结果；判定设备无应答时返回诊断信息
+    pub fn record(&mut self, result: &Result<()>) -> Option<&'static str> {
+        match result {
+            Ok(()) => {
+                self.seen = true;
+                self.timeouts = 0;
+                None
+            }
+            Err(e) if matches!(e.downcast_ref::<BackendError>(), Some(BackendError::Timeout(_))) => {
+                self.timeouts += 1;
+                (!self.seen && self.timeouts >= MUTE_TIMEOUT_RUN).then_some(MUTE_DIAGNOSTIC)
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+pub fn get_available_ports(state: &Arc<Mutex<BackendState>>, token: CancellationToken) -> Vec<String> {
+    if state.lock().devices.simulation.is_some() {
+        return vec!["模拟串口".to_string()];
+    }
     // 1. 获取原始的串口信息列表
     let ports = match serialport::available_ports() {
         Ok(ports) => ports,
@@ -66,6 +130,14 @@ pub fn connect(
     let mut s = state.lock();
     s.devices.serial_port = None;
 
+    if let Some(sim_steps) = s.devices.simulation.clone() {
+        let port = FakeSerialPort::new(sim_steps, s.devices.serial_protocol.clone(), baud_rate);
+        s.devices.serial_port = Some(Arc::new(Mutex::new(Box::new(port))));
+        tx.send(Update::Device(DeviceUpdate::SerialConnectionStatus(true)))?;
+        info!("已连接模拟串口");
+        return Ok(());
+    }
+
     s.devices.serial_port = serialport::new(&port_name, baud_rate)
         .timeout(Duration::from_millis(5000))
         .open()
@@ -77,8 +149,15 @@ pub fn connect(
     if s.devices.serial_port.is_none() {
         return Err(anyhow::anyhow!("连接失败"))
     }
+    s.devices.ack_monitor.connected(&port_name, baud_rate);
     tx.send(Update::Device(DeviceUpdate::SerialConnectionStatus(true)))?;
     info!("连接成功");
+    if let Some(zero) = s.measurement.last_zero {
+        tracing::warn!(
+            "串口已重新连接，零点确立于 {}，可能已失效，建议重新寻找零点",
+            zero.timestamp.format("%H:%M")
+        );
+    }
     Ok(())
     
 }
@@ -96,17 +175,108 @@ pub fn test(state: &Arc<Mutex<BackendState>>,
     tx: &Sender<Update>,)-> Result<()>{
     let mut s= state.lock();
     if s.devices.serial_port.is_none() {
-        return Err(anyhow::anyhow!("未连接串口"))
+        return Err(BackendError::SerialDisconnected("未连接串口".to_string()).into())
     }
     let port=s.devices.serial_port.as_mut().unwrap().clone();
+    let protocol = s.devices.serial_protocol.lock().clone();
     drop(s);
-    if cmd(port,77 as u8).is_ok(){//cmd(port,51).is_ok()||
-        info!("测试成功");
-        
-    }else{
-        info!("测试失败");
+    let start = Instant::now();
+    let reply = cmd_raw(port, HEARTBEAT_COMMAND, &protocol);
+    let round_trip_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let (result, outcome) = match reply {
+        Ok(reply) => {
+            let success = reply.trim() == protocol.ack_token;
+            let outcome = if success { Ok(()) } else { Err(anyhow::anyhow!("回复异常")) };
+            let result = SerialTestResult {
+                success,
+                reply: reply.trim().to_string(),
+                round_trip_ms,
+            };
+            (result, outcome)
+        }
+        Err(e) => {
+            let result = SerialTestResult {
+                success: false,
+                reply: e.to_string(),
+                round_trip_ms,
+            };
+            (result, Err(e))
+        }
+    };
+    let diagnostic = {
+        let mut s = state.lock();
+        let diagnostic = s.devices.ack_monitor.record(&outcome);
+        // 与心跳相同：设备从未应答时断开串口，避免后续指令继续超时
+        if diagnostic.is_some() {
+            s.devices.serial_port = None;
+        }
+        diagnostic
+    };
+    if result.success {
+        info!("测试成功，耗时 {:.1} ms", result.round_trip_ms);
+    } else {
+        info!("测试失败：{:?}", result.reply);
+    }
+    tx.send(Update::Device(DeviceUpdate::SerialTestResult(result)))?;
+    if let Some(message) = diagnostic {
+        error!("{}", message);
+        tx.send(Update::Device(DeviceUpdate::SerialConnectionStatus(false)))?;
+        tx.send(Update::General(GeneralUpdate::Error(BackendError::SerialDisconnected(
+            message.to_string(),
+        ))))?;
     }
     Ok(())
 }
 
+pub fn set_protocol(
+    state: &Arc<Mutex<BackendState>>,
+    ack_token: String,
+    stop_byte: Option<u8>,
+) -> Result<()> {
+    if ack_token.trim().is_empty() {
+        return Err(anyhow::anyhow!("应答字符串不能为空"));
+    }
+    let protocol = SerialProtocol {
+        ack_token: ack_token.trim().to_string(),
+        stop_byte,
+    };
+    info!("串口协议已更新：应答 {:?}，结束字节 {:?}", protocol.ack_token, protocol.stop_byte);
+    *state.lock().devices.serial_protocol.lock() = protocol;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeout() -> Result<()> {
+        Err(BackendError::Timeout("串口应答超时".to_string()).into())
+    }
+
+    #[test]
+    fn ack_monitor_flags_only_a_mute_link() {
+        let mut monitor = AckMonitor::default();
+        monitor.connected("COM3", 9600);
+        assert_eq!(monitor.record(&timeout()), None);
+        assert_eq!(monitor.record(&timeout()), None);
+        assert_eq!(monitor.record(&timeout()), Some(MUTE_DIAGNOSTIC));
 
+        // 重新连接同一串口与波特率时继续累计；换波特率后重新统计
+        monitor.connected("COM3", 9600);
+        assert_eq!(monitor.record(&timeout()), Some(MUTE_DIAGNOSTIC));
+        monitor.connected("COM3", 115200);
+        assert_eq!(monitor.record(&timeout()), None);
+    }
+
+    #[test]
+    fn ack_monitor_treats_timeouts_after_an_ack_as_intermittent() {
+        let mut monitor = AckMonitor::default();
+        monitor.connected("COM3", 9600);
+        assert_eq!(monitor.record(&Ok(())), None);
+        for _ in 0..5 {
+            assert_eq!(monitor.record(&timeout()), None);
+        }
+        // 应答内容不符说明设备在回复，不算无应答
+        assert_eq!(monitor.record(&Err(anyhow::anyhow!("回复异常"))), None);
+    }
+}
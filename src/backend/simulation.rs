@@ -0,0 +1,203 @@
+// src/backend/simulation.rs
+// 模拟模式：在没有硬件时用假串口和合成相机画面跑通整个流程
+use super::measurement::command_steps;
+use super::serial::SerialProtocol;
+use crate::communication::DEFAULT_STEPS_PER_DEGREE;
+use anyhow::Result;
+use opencv::{core, prelude::*};
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicI32, Ordering};
+use parking_lot::Mutex;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+// 合成画面的尺寸与视场圆
+const FRAME_WIDTH: i32 = 640;
+const FRAME_HEIGHT: i32 = 480;
+const FIELD_RADIUS: i32 = 38;
+// 每度对应的亮度差，差值越大分类越容易
+const BRIGHTNESS_PER_DEGREE: f64 = 40.0;
+// 模拟样品的旋光角随时间指数衰减，便于演示动态测量
+const SAMPLE_INITIAL_ANGLE: f64 = 6.0;
+const SAMPLE_FINAL_ANGLE: f64 = -2.0;
+const SAMPLE_TIME_CONSTANT: f64 = 900.0;
+
+/// 假串口：识别电机指令并立即更新模拟步数，每条指令都回复应答字符串
+pub struct FakeSerialPort {
+    steps: Arc<AtomicI32>,
+    // 与后端共享的协议参数，应答字符串在每次写入时读取
+    protocol: Arc<Mutex<SerialProtocol>>,
+    pending: VecDeque<u8>,
+    timeout: Duration,
+    baud_rate: u32,
+}
+
+impl FakeSerialPort {
+    pub fn new(steps: Arc<AtomicI32>, protocol: Arc<Mutex<SerialProtocol>>, baud_rate: u32) -> Self {
+        Self {
+            steps,
+            protocol,
+            pending: VecDeque::new(),
+            timeout: Duration::from_millis(5000),
+            baud_rate,
+        }
+    }
+}
+
+impl Read for FakeSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "模拟串口无数据"));
+        }
+        let n = buf.len().min(self.pending.len());
+        for (dst, src) in buf.iter_mut().zip(self.pending.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for FakeSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            // 结束字节等无法识别的字节直接忽略
+            if let Some(steps) = command_steps(byte) {
+                self.steps.fetch_add(steps, Ordering::Relaxed);
+                self.pending.clear();
+                self.pending.extend(self.protocol.lock().ack_token.as_bytes());
+                self.pending.push_back(b'\n');
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for FakeSerialPort {
+    fn name(&self) -> Option<String> {
+        Some("模拟串口".to_string())
+    }
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.baud_rate)
+    }
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+    fn set_data_bits(&mut self, _: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn set_flow_control(&mut self, _: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn set_parity(&mut self, _: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn set_stop_bits(&mut self, _: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+    fn write_request_to_send(&mut self, _: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn write_data_terminal_ready(&mut self, _: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        Ok(self.pending.len() as u32)
+    }
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+    fn clear(&self, _: ClearBuffer) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(FakeSerialPort::new(
+            self.steps.clone(),
+            self.protocol.clone(),
+            self.baud_rate,
+        )))
+    }
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+// 模拟样品当前的旋光角（度），从程序启动开始计时
+fn sample_angle() -> f64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    let t = START.get_or_init(Instant::now).elapsed().as_secs_f64();
+    SAMPLE_FINAL_ANGLE
+        + (SAMPLE_INITIAL_ANGLE - SAMPLE_FINAL_ANGLE) * (-t / SAMPLE_TIME_CONSTANT).exp()
+}
+
+/// 生成一帧三分视场画面：中间条带与两侧的亮度差随检偏器与样品的角度差变号
+pub fn synthetic_frame(steps: i32) -> Result<Mat> {
+    // 模拟的机械传动比固定为默认值，界面上修改每度步数相当于标定偏差
+    let angle = steps as f64 / DEFAULT_STEPS_PER_DEGREE as f64 - sample_angle();
+    let delta = (angle * BRIGHTNESS_PER_DEGREE).clamp(-100.0, 100.0);
+    // 默认“正值对应 MAM”：零点正侧中间条带变暗
+    let middle = (128.0 - delta) as u8;
+    let sides = (128.0 + delta) as u8;
+
+    let mut frame = Mat::new_rows_cols_with_default(
+        FRAME_HEIGHT,
+        FRAME_WIDTH,
+        core::CV_8UC3,
+        core::Scalar::all(15.0),
+    )?;
+    let (cx, cy) = (FRAME_WIDTH / 2, FRAME_HEIGHT / 2);
+    let strip = FIELD_RADIUS / 3;
+    let data = frame.data_bytes_mut()?;
+    for y in (cy - FIELD_RADIUS)..=(cy + FIELD_RADIUS) {
+        for x in (cx - FIELD_RADIUS)..=(cx + FIELD_RADIUS) {
+            let (dx, dy) = (x - cx, y - cy);
+            if dx * dx + dy * dy > FIELD_RADIUS * FIELD_RADIUS {
+                continue;
+            }
+            let value = if dx.abs() <= strip { middle } else { sides };
+            let offset = ((y * FRAME_WIDTH + x) * 3) as usize;
+            data[offset..offset + 3].fill(value);
+        }
+    }
+    Ok(frame)
+}
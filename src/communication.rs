@@ -7,7 +7,7 @@ use std::sync::Arc;
 use egui::ColorImage;
 use serde::{Deserialize, Serialize};
 use tracing::Level;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
 //======================================================================
 //  命令: Frontend -> Backend
 //======================================================================
@@ -26,6 +26,10 @@ pub enum Command {
 #[derive(Debug, Clone)]
 pub enum GeneralCommand {
     Shutdown,
+    StopActiveTask, // 停止当前正在运行的测量/找零点/录制任务
+    StopAllMotion,  // 急停：中断正在进行的旋转并停止所有会转动电机的任务，不再自动回零
+    // 保存 xlsx 结果时的列顺序与取舍，保存后下次启动沿用
+    SetExportLayout(ExportLayout),
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +38,7 @@ pub enum DeviceCommand {
     ConnectSerial { port: String, baud_rate: u32 },
     DisconnectSerial,
     TestSerial,
+    SetSerialProtocol { ack_token: String, stop_byte: Option<u8> },
     SetRotationDirection(bool), // true for AMA, false for MAM
     SetStep(f32),
     SetRotationReverse(bool),
@@ -41,18 +46,28 @@ pub enum DeviceCommand {
     RotateTo { steps:i32 },
     FindZeroPoint,
     ReturnToZero,
-    StartRecording { mode: String, save_path: PathBuf ,num:i32},
+    ReturnToZeroBySearch, // 回到上次零点附近并用识别结果重新确定边界
+    // fps: 每秒保存的帧数，不超过相机采集帧率
+    // 只能设置帧率：保存的帧固定为 20×20 的模型输入，分辨率无从设置
+    StartRecording { mode: String, save_path: PathBuf ,num:i32, fps: f32},
     StopRecording,
+    ResetOdometer, // 例如给齿轮重新上油后清零累计步数
 }
 
 #[derive(Debug, Clone)]
 pub enum CameraCommand {
     RefreshCameras,
     Connect { index: usize },
-    Disconnect,
+    Disconnect { index: usize },
+    SetPrimary { index: usize }, // 选择用于识别的主相机
     SetHoughCircleRadius { min: u32, max: u32 },
     SetLock(bool),
     Exposure(f64),
+    SetCaptureFps(f32), // 相机采集线程的目标帧率
+    SetCapturePaused(bool), // 暂停采集以节省资源，不断开相机
+    SetPredictionPreview(bool),
+    CalibrateRadius,
+    SweepExposure, // 扫描曝光并给出建议值
 }
 
 #[derive(Debug, Clone)]
@@ -62,18 +77,33 @@ pub enum TrainingCommand {
     SaveModel { path: PathBuf },
     LoadModel { path: PathBuf },
     ExportDataset { path: PathBuf },
+    ExportEvaluation { path: PathBuf },
     ResetModel,
     LoadPersistentDataset { path: PathBuf },
+    LoadSharedDataset { source: String }, // 网络路径或 HTTP 压缩包地址
     ResetPersistentDataset,
-    ResetRecordedDataset
+    ResetRecordedDataset,
+    // 下次训练使用的图像归一化方式，训练后随模型保存
+    SetNormalization(Normalization),
+    // 直接从一段 MAM 视频和一段 AMA 视频提取图像并训练，用于快速试验
+    QuickTrainFromVideos { mam_video: PathBuf, ama_video: PathBuf, show_roc: bool, show_cm: bool },
 }
 
 #[derive(Debug, Clone)]
 pub enum StaticMeasureCommand {
     RunSingleMeasurement{time: i32},
+    // 按扩展名选择格式：.csv 写为 UTF-8 CSV，其余写为 xlsx，两者的列相同
     SaveResults { path: PathBuf },
     ClearResults,
     Stop,
+    // 将当前位置直接设为零点；没有有效零点时需要用户确认
+    RezeroHere { confirmed: bool },
+    // 判定越过零点时在多数之外额外要求的翻转次数
+    SetDecisionMargin(usize),
+    // 在当前位置快速识别一次，检查零点是否漂移
+    VerifyZero,
+    // 开发者功能：把记录的预测值 CSV 逐个送入判定逻辑，记录每一步的动作
+    ReplayPredictions { path: PathBuf },
 }
 
 #[derive(Debug, Clone)]
@@ -83,21 +113,41 @@ pub enum DynamicMeasureCommand {
     Stop,
     StartNew,
     ClearResults,
+    ImportParams { path: PathBuf },
+    // 自动触发漏点时，手动以当前时间和角度记录一个点
+    RecordNow,
+    // 按当前 α∞ 导出附带 ln(Δα) 与 1/Δα 的结果，可直接用于拟合
+    ExportFitReady { path: PathBuf },
+    // 把当前结果另存为 CSV，列与参数块与自动保存的 xlsx 相同
+    SaveResultsCsv { path: PathBuf },
 }
 
 #[derive(Debug, Clone)]
 pub enum DataProcessingCommand {
-    LoadData { path: PathBuf },
+    // sheet 为 None 且工作簿有多张工作表时，后端先返回工作表列表供选择
+    LoadData { path: PathBuf, sheet: Option<String> },
+    // 把多个结果文件合并为一个多工作表的工作簿
+    ExportMerged { inputs: Vec<PathBuf>, output: PathBuf },
     SetAlphaInf { alpha: f64 },
+    // 用一级反应模型拟合已加载的数据，估算 α∞ 并直接采用
+    EstimateAlphaInf,
     SetRegressionMode { mode: RegressionMode },
+    // 是否把逆着预期趋势跳变的点排除在拟合之外（默认排除）
+    SetExcludeTrendReversals(bool),
+    // 不改变任何输入，按当前状态重新拟合并推送完整状态
+    Recalculate,
 }
 
 #[derive(Clone, Debug)]
 pub struct DataProcessingStateUpdate {
     pub raw_data: Arc<Vec<(f64, i32, f64,bool)>>, // time, steps, angle
-    pub alpha_inf: f64,
+    pub alpha_inf: Option<f64>, // None 表示尚未设置，α∞ = 0 是合法值
     pub regression_mode: RegressionMode,
     pub regression_formula: String,
+    pub regression_summary: String,
+    pub regression_warning: String,
+    pub r_squared: f64, // 没有拟合结果时为 NaN
+    pub exclude_trend_reversals: bool,
     pub plot_scatter_points: Vec<(f64, f64)>, 
     pub plot_line_points: Vec<(f64, f64)>,
 }
@@ -117,6 +167,13 @@ pub struct LogMessage {
     pub target: String,
 }
 
+/// 实验记录：只包含找零点、记录测量点、开始/结束运行等里程碑事件
+#[derive(Debug, Clone)]
+pub struct ExperimentEvent {
+    pub timestamp: DateTime<Local>,
+    pub message: String,
+}
+
 //======================================================================
 //  更新: Backend -> Frontend
 //======================================================================
@@ -134,8 +191,61 @@ pub enum Update {
 #[derive(Clone, Debug)]
 pub enum GeneralUpdate {
     StatusMessage(String),
-    Error(String),
+    Error(BackendError),
     NewLog(LogMessage),
+    ExperimentEvent(ExperimentEvent),
+    ExportLayout(ExportLayout),
+    Diagnostics(Diagnostics),
+}
+
+/// 长时间无人值守运行时的资源占用，由监控线程定期发送
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    pub memory_bytes: Option<u64>, // 进程常驻内存，无法获取时为 None
+    pub cpu_percent: Option<f32>,  // 进程 CPU 占用，多核时可超过 100%
+    pub mam_images: usize,
+    pub ama_images: usize,
+    pub image_bytes: usize, // 两类训练图像占用的字节数
+    pub dynamic_results: usize,
+    pub raw_data: usize, // 数据处理页已加载的点数
+}
+
+/// 后端错误的类别。命令返回的这类错误会原样随 GeneralUpdate::Error 发给前端，
+/// 前端据此给出不同的处理建议（重连串口、检查相机、重新找零点等）
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum BackendError {
+    /// 串口未连接或通信失败，电机位置已不可信
+    #[error("串口通信失败：{0}")]
+    SerialDisconnected(String),
+    /// 主相机断开或取不到画面
+    #[error("相机异常")]
+    CameraLost,
+    /// 等待应答或搜索交界超时
+    #[error("{0}")]
+    Timeout(String),
+    /// 开始任务所需的设备、模型或零点尚未就绪
+    #[error("{0}")]
+    NotReady(String),
+    /// 旋转被急停中断（停止任务会等旋转结束，不产生此错误），已转动的步数仍计入当前位置
+    #[error("旋转被用户中断")]
+    Interrupted,
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<String> for BackendError {
+    fn from(msg: String) -> Self {
+        BackendError::Other(msg)
+    }
+}
+
+impl BackendError {
+    /// 从命令返回的错误中还原类别，其他错误归为 Other
+    pub fn classify(err: &anyhow::Error) -> Self {
+        err.downcast_ref::<BackendError>()
+            .cloned()
+            .unwrap_or_else(|| BackendError::Other(format!("执行命令时出错: {}", err)))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -148,8 +258,43 @@ pub enum DeviceUpdate {
     SerialPortsList(Vec<String>),
     SerialConnectionStatus(bool),
     CameraList(Vec<String>),
-    CameraConnectionStatus(bool),
-    NewCameraFrame(Arc<ColorImage>),
+    // 主相机是否可用；message 说明连接失败或断开的具体原因（被占用、打不开、没有画面等）
+    CameraStatus { connected: bool, message: Option<String> },
+    CameraError(String), // 未发现相机 / 相机被占用等具体原因
+    NewCameraFrame(usize, Arc<ColorImage>), // 相机索引, 画面
+    CamerasChanged { connected: Vec<usize>, primary: Option<usize> },
+    // 每路相机的设置各自保存，主相机变化后界面改为显示新主相机的设置
+    PrimaryCameraSettings { exposure: f64, min_radius: u32, max_radius: u32, lock_circle: bool, capture_fps: f32 },
+    PredictionPreview(Option<(usize, f64)>), // 预测类别, 置信度
+    SerialTestResult(SerialTestResult),
+    RadiusCalibrated { min: u32, max: u32 },
+    ExposureApplied(f64), // 主相机读回的实际曝光值
+    OpenCvStatus(Result<String, String>), // 启动自检：OpenCV 版本或失败原因
+    CapturePaused(bool),
+    ExposureSwept(ExposureSweep),
+    // 主相机的圆半径统计，None 表示圆形位置已锁定或没有数据
+    RadiusStats(Option<RadiusStats>),
+    Odometer(u64), // 电机累计转动的步数（取绝对值），跨会话保存
+}
+
+/// 最近若干帧检测到的圆半径统计，用于发现对焦或振动问题
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RadiusStats {
+    pub min: i32,
+    pub max: i32,
+    pub mean: f32,
+    // 100 × (1 − 标准差 / 平均值)，越接近 100 越稳定
+    pub stability: f32,
+    pub detected: usize, // 窗口内检测到圆的帧数
+    pub frames: usize,   // 窗口内的总帧数
+}
+
+/// 曝光扫描结果
+#[derive(Clone, Debug)]
+pub struct ExposureSweep {
+    pub points: Vec<(f64, f64)>, // 曝光, 识别区域平均亮度 (0~255)
+    pub target: (f64, f64),      // 亮度目标区间
+    pub suggested: Option<f64>,
 }
 
 #[derive(Clone, Debug)]
@@ -164,6 +309,8 @@ pub enum TrainingUpdate {
     PersistentDatasetStatus(String),
     MAMDatasetStatus(String),
     AMADatasetStatus(String),
+    // 当前识别设置与模型训练时不一致的说明，None 表示一致
+    FeatureConfigWarning(Option<String>),
 
 }
 
@@ -172,34 +319,358 @@ pub enum MeasurementUpdate {
     StaticStatus(String),
     StaticRunning(bool),
     StaticResults(Vec<StaticResult>),
+    StaticSaved,
     DynamicStatus(String),
+    DynamicPhase(DynamicPhase),
     DynamicResults(Vec<DynamicResult>),
+    // 动态结果已成功写入文件（自动保存或另存为 CSV）
+    DynamicSaved,
     DynamicRunning(bool),
+    DynamicParams(DynamicExpParams),
     CurrentSteps(Option<i32>),
     StartTime(Option<std::time::Instant>),
     Rotation(bool),
+    ZeroSearchProgress(Option<(f32, f32)>), // 已用时间, 剩余超时 (s)
+    RunId(Option<String>),
+    ZeroEstablished(ZeroRecord),
 }
 
 #[derive(Clone, Debug)]
 pub enum DataProcessingUpdate {
     FullState(DataProcessingStateUpdate),
+    SheetChoices { path: PathBuf, sheets: Vec<String> },
 }
 
 //======================================================================
 //  共享数据结构
 //======================================================================
+/// 默认每度步数，可在设备页修改或标定
+pub const DEFAULT_STEPS_PER_DEGREE: f32 = 746.0;
+
+/// 每度步数必须是有限的正数，否则角度换算会除零
+pub fn is_valid_steps_per_degree(steps_per_degree: f32) -> bool {
+    steps_per_degree.is_finite() && steps_per_degree > 0.0
+}
+
+/// 电机步数换算为角度；每度步数无效时返回 None
+pub fn steps_to_angle(steps: i32, steps_per_degree: f32) -> Option<f32> {
+    is_valid_steps_per_degree(steps_per_degree).then(|| steps as f32 / steps_per_degree)
+}
+
+/// 最近一次确立零点的时间，以及确立时相对起始位置的原始步数
+#[derive(Debug, Clone, Copy)]
+pub struct ZeroRecord {
+    pub timestamp: DateTime<Local>,
+    pub raw_steps: i32,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RegressionMode { Linear, Log, Inverse }
 
+/// 送入模型前对 20×20 特征图的归一化，用于抵消训练与测量时光照的差异
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Normalization {
+    #[default]
+    None,               // 仅除以 255
+    Standardize,        // 减去均值再除以标准差
+    HistogramEqualize,  // 直方图均衡化后除以 255
+}
+
+/// 结果文件中可选的数据列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportColumn {
+    Index,
+    Time, // 仅动态结果
+    Steps,
+    Angle,
+    Valid, // 有效性，写入时全部为 1，可在 Excel 中改为 0 以在拟合时剔除该点
+}
+
+impl ExportColumn {
+    pub const ALL: [ExportColumn; 5] = [
+        ExportColumn::Index,
+        ExportColumn::Time,
+        ExportColumn::Steps,
+        ExportColumn::Angle,
+        ExportColumn::Valid,
+    ];
+
+    /// 写入文件的表头，导入时按表头查找时间、步数、角度列
+    pub fn header(self) -> &'static str {
+        match self {
+            ExportColumn::Index => "index",
+            ExportColumn::Time => "time",
+            ExportColumn::Steps => "steps",
+            ExportColumn::Angle => "angle",
+            ExportColumn::Valid => "valid",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportColumn::Index => "序号",
+            ExportColumn::Time => "时间",
+            ExportColumn::Steps => "步数",
+            ExportColumn::Angle => "角度",
+            ExportColumn::Valid => "有效",
+        }
+    }
+}
+
+/// 结果文件（xlsx 或 CSV）的列布局：数据列按顺序排列，未勾选的列不写入
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ExportLayoutFile", into = "ExportLayoutFile")]
+pub struct ExportLayout {
+    pub columns: Vec<(ExportColumn, bool)>,
+    pub include_params: bool, // 动态结果是否在 F/G 列写入实验参数
+}
+
+/// 设置文件中的列布局：列以表头名称保存，顺序即列顺序
+#[derive(Serialize, Deserialize)]
+struct ExportLayoutFile {
+    columns: Vec<(String, bool)>,
+    #[serde(default = "default_true")]
+    include_params: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl From<ExportLayout> for ExportLayoutFile {
+    fn from(layout: ExportLayout) -> Self {
+        Self {
+            columns: layout
+                .columns
+                .iter()
+                .map(|(column, on)| (column.header().to_string(), *on))
+                .collect(),
+            include_params: layout.include_params,
+        }
+    }
+}
+
+/// 无法识别的列（如旧版的 Δα 列）被忽略，缺少的列按默认启用追加到末尾
+impl From<ExportLayoutFile> for ExportLayout {
+    fn from(file: ExportLayoutFile) -> Self {
+        let mut layout = ExportLayout { columns: Vec::new(), include_params: file.include_params };
+        for (key, on) in file.columns {
+            if let Some(column) = ExportColumn::ALL.iter().find(|c| c.header() == key) {
+                if !layout.columns.iter().any(|(c, _)| c == column) {
+                    layout.columns.push((*column, on));
+                }
+            }
+        }
+        for column in ExportColumn::ALL {
+            if !layout.columns.iter().any(|(c, _)| *c == column) {
+                layout.columns.push((column, true));
+            }
+        }
+        // 手工编辑后没有任何数据列时，结果文件将无法导入，恢复默认的列
+        if !layout.has_data_column() {
+            layout.columns = ExportLayout::default().columns;
+        }
+        layout
+    }
+}
+
+impl Default for ExportLayout {
+    fn default() -> Self {
+        Self {
+            columns: ExportColumn::ALL.iter().map(|&c| (c, true)).collect(),
+            include_params: true,
+        }
+    }
+}
+
+impl ExportLayout {
+    /// 按顺序返回启用的列
+    pub fn enabled(&self) -> impl Iterator<Item = ExportColumn> + '_ {
+        self.columns.iter().filter(|(_, on)| *on).map(|(c, _)| *c)
+    }
+
+    /// 至少启用了时间、步数、角度中的一列；否则导入时找不到数据列，会误读其他列
+    pub fn has_data_column(&self) -> bool {
+        self.enabled()
+            .any(|c| matches!(c, ExportColumn::Time | ExportColumn::Steps | ExportColumn::Angle))
+    }
+}
+
+/// 样品旋光方向：决定动态测量中旋光角随时间的预期变化方向
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleDirection {
+    Dextro, // 右旋：旋光角随反应递减（如蔗糖水解）
+    Levo,   // 左旋：旋光角随反应递增
+}
+
+/// 预旋转的对齐判据：越过交界前视场应呈现的图样
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PreRotationTarget {
+    #[default]
+    AnyCrossing, // 从任一侧越过交界即视为对齐（默认）
+    FromMam,     // 只接受由 MAM 一侧越过的交界，从另一侧开始时越过后折返
+    FromAma,     // 只接受由 AMA 一侧越过的交界
+}
+
+impl PreRotationTarget {
+    pub fn label(self) -> &'static str {
+        match self {
+            PreRotationTarget::AnyCrossing => "任一侧越过交界",
+            PreRotationTarget::FromMam => "由 MAM 侧越过交界",
+            PreRotationTarget::FromAma => "由 AMA 侧越过交界",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        [
+            PreRotationTarget::AnyCrossing,
+            PreRotationTarget::FromMam,
+            PreRotationTarget::FromAma,
+        ]
+        .into_iter()
+        .find(|t| t.label() == label)
+    }
+}
+
+/// 动态测量的采样触发方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplingMode {
+    PredictionFlip, // 识别结果翻转时采样（默认）
+    Timed,          // 每隔固定时间采样
+}
+
+/// 浓度的输入单位；参数中始终保存换算后的标准单位（蔗糖 g/mL，盐酸 mol/L）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConcUnit {
+    GramPerMl, // 蔗糖标准单位
+    GramPerL,
+    PercentWv, // % (w/v)，即 g/100mL
+    MolPerL, // 盐酸标准单位
+    MmolPerL,
+}
+
+impl ConcUnit {
+    pub const SUCROSE: [ConcUnit; 3] = [ConcUnit::GramPerMl, ConcUnit::GramPerL, ConcUnit::PercentWv];
+    pub const HCL: [ConcUnit; 2] = [ConcUnit::MolPerL, ConcUnit::MmolPerL];
+
+    /// 1 个该单位等于多少标准单位
+    pub fn to_canonical(self) -> f32 {
+        match self {
+            ConcUnit::GramPerMl | ConcUnit::MolPerL => 1.0,
+            ConcUnit::GramPerL | ConcUnit::MmolPerL => 0.001,
+            ConcUnit::PercentWv => 0.01,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConcUnit::GramPerMl => "g/mL",
+            ConcUnit::GramPerL => "g/L",
+            ConcUnit::PercentWv => "% (w/v)",
+            ConcUnit::MolPerL => "mol/L",
+            ConcUnit::MmolPerL => "mmol/L",
+        }
+    }
+
+    pub fn from_label(text: &str) -> Option<Self> {
+        [
+            ConcUnit::GramPerMl,
+            ConcUnit::GramPerL,
+            ConcUnit::PercentWv,
+            ConcUnit::MolPerL,
+            ConcUnit::MmolPerL,
+        ]
+        .into_iter()
+        .find(|unit| unit.label() == text)
+    }
+}
+
+/// 动态追踪内部所处的阶段，每次切换时发送给前端，便于判断卡在哪一步
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DynamicPhase {
+    Idle,
+    PreRotating,        // 预旋转：寻找初始位置附近的零点交界
+    WaitingForFlip,     // 按识别翻转采样：等待识别结果翻转
+    Confirming,         // 已触发，等待确认间隔后复核翻转是否仍成立
+    WaitingForInterval, // 按时间采样：等待下一个采样时刻
+    Rotating,           // 记录采样点后转动步进角
+    Settling,           // 转动后的稳定等待
+    Returning,          // 追踪结束，转回零点
+}
+
+impl DynamicPhase {
+    pub fn label(self) -> &'static str {
+        match self {
+            DynamicPhase::Idle => "空闲",
+            DynamicPhase::PreRotating => "预旋转中",
+            DynamicPhase::WaitingForFlip => "等待识别翻转",
+            DynamicPhase::Confirming => "确认翻转",
+            DynamicPhase::WaitingForInterval => "等待采样时刻",
+            DynamicPhase::Rotating => "转动步进角",
+            DynamicPhase::Settling => "转动后稳定等待",
+            DynamicPhase::Returning => "回到零点",
+        }
+    }
+
+    /// 预旋转之后、回零之前的阶段都属于跟踪
+    pub fn is_tracking(self) -> bool {
+        !matches!(
+            self,
+            DynamicPhase::Idle | DynamicPhase::PreRotating | DynamicPhase::Returning
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DynamicExpParams {
     pub path: PathBuf,
     pub temperature: f32,
-    pub sucrose_conc: f32,
-    pub hcl_conc: f32,
+    pub sucrose_conc: f32, // g/mL
+    pub hcl_conc: f32,     // mol/L
+    // 界面上输入浓度时所选的单位，保存时一并记录
+    pub sucrose_unit: ConcUnit,
+    pub hcl_unit: ConcUnit,
     pub pre_rotation_angle: f32,
-    pub step_angle: f32,
+    pub step_angle: f32, // 步进角的大小，方向由 sample_direction 决定
     pub sample_points: u32,
+    pub sample_direction: SampleDirection,
+    pub sampling_mode: SamplingMode,
+    pub sample_interval: f32, // 按时间采样时的间隔 (s)
+    pub settle_delay_ms: u32, // 每次采样转动后的稳定等待时间
+    pub poll_interval_ms: u32, // 识别轮询间隔
+    // 按识别翻转采样：最近 consensus_window 帧中有 consensus_count 帧翻转即触发
+    pub consensus_window: u32,
+    pub consensus_count: u32,
+    // 触发后等待该时长再复核一次翻转，仍成立才记录；0 表示触发即记录
+    pub confirm_delay_ms: u32,
+    // 从开始计时起的最长运行时间 (min)，到时自动停止跟踪
+    pub max_duration_min: u32,
+    // 本次实验的自由备注（水浴不稳、第几次尝试等），随结果一起保存
+    pub notes: String,
+    // 样品已摆好位置时跳过预旋转，直接从当前位置开始跟踪
+    pub skip_pre_rotation: bool,
+    pub pre_rotation_target: PreRotationTarget,
+}
+
+impl DynamicExpParams {
+    pub fn max_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.max_duration_min as u64 * 60)
+    }
+
+    /// 每次采样后实际转动的角度（带符号）
+    pub fn effective_step_angle(&self) -> f32 {
+        match self.sample_direction {
+            SampleDirection::Dextro => -self.step_angle.abs(),
+            SampleDirection::Levo => self.step_angle.abs(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SerialTestResult {
+    pub success: bool,
+    pub reply: String, // 原始应答，失败时为错误信息
+    pub round_trip_ms: f64,
 }
 
 #[derive(Clone, Debug)]
@@ -240,10 +711,78 @@ pub enum FileDialogResult {
     StartRecording(PathBuf),
     RecordedDataset(PathBuf),
     PersistentDataset(PathBuf),
+    ExportEvaluation(PathBuf),
+    SaveModel(PathBuf),
+    LoadModel(PathBuf),
+    TrainingVideo { mode: String, path: PathBuf }, // 快速训练用的单个视频
     // 静态测量
     SaveStaticResults(PathBuf),
+    ReplayPredictions(PathBuf),
     // 动态测量
     SaveDynamicExperiment(PathBuf),
+    ImportDynamicParams(PathBuf),
+    ExportFitReady(PathBuf),
+    SaveDynamicCsv(PathBuf),
     // 数据处理
     LoadDataProcessingFile(PathBuf),
+    ExportMergedRuns { inputs: Vec<PathBuf>, output: PathBuf },
+    ExportPlotData(PathBuf),
+    ExportTwoColumn(PathBuf), // 只含变换后 (t, y) 的两列 CSV
+    // 实验记录
+    ExportExperimentLog(PathBuf),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_layout_round_trips_and_fills_missing_columns() {
+        let mut layout = ExportLayout::default();
+        layout.columns.swap(0, 3);
+        layout.columns[1].1 = false;
+        layout.include_params = false;
+        let text = serde_json::to_string(&layout).unwrap();
+        assert_eq!(serde_json::from_str::<ExportLayout>(&text).unwrap(), layout);
+
+        let partial: ExportLayout = serde_json::from_str(
+            r#"{"columns": [["angle", true], ["time", false], ["delta_alpha", true]]}"#,
+        )
+        .unwrap();
+        assert_eq!(partial.columns[0], (ExportColumn::Angle, true));
+        assert_eq!(partial.columns[1], (ExportColumn::Time, false));
+        assert_eq!(partial.columns.len(), ExportColumn::ALL.len());
+        assert!(partial.include_params);
+
+        let no_data: ExportLayout = serde_json::from_str(
+            r#"{"columns": [["index", true], ["time", false], ["steps", false], ["angle", false]]}"#,
+        )
+        .unwrap();
+        assert!(no_data.has_data_column());
+        assert_eq!(no_data, ExportLayout::default());
+    }
+
+    #[test]
+    fn backend_error_survives_anyhow_and_context() {
+        let err = anyhow::Error::from(BackendError::CameraLost).context("预旋转失败");
+        assert_eq!(BackendError::classify(&err), BackendError::CameraLost);
+        let plain = anyhow::anyhow!("文件中没有预测值");
+        assert_eq!(
+            BackendError::classify(&plain),
+            BackendError::Other("执行命令时出错: 文件中没有预测值".to_string())
+        );
+    }
+
+    #[test]
+    fn steps_to_angle_rejects_non_positive_steps_per_degree() {
+        assert_eq!(steps_to_angle(746, 746.0), Some(1.0));
+        assert_eq!(steps_to_angle(-373, 746.0), Some(-0.5));
+        assert_eq!(steps_to_angle(0, 746.0), Some(0.0));
+        assert!(steps_to_angle(1, 0.001).is_some());
+        assert_eq!(steps_to_angle(746, 0.0), None);
+        assert_eq!(steps_to_angle(746, -0.0), None);
+        assert_eq!(steps_to_angle(746, -746.0), None);
+        assert_eq!(steps_to_angle(746, f32::NAN), None);
+        assert_eq!(steps_to_angle(746, f32::INFINITY), None);
+    }
 }
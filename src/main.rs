@@ -4,6 +4,7 @@ mod app;
 mod backend;
 mod communication;
 mod logging;
+mod settings;
 use crate::app::PolarimeterApp;
 use crate::backend::backend_loop;
 // (已修改) 导入新的通信枚举
@@ -159,9 +160,12 @@ fn main() -> eframe::Result<()> {
             EnvFilter::new("info")
         )
         .init(); // 设置为全局默认订阅者
+    // 模拟模式：`--simulate` 参数或 POLARIMETER_SIMULATE 环境变量，无需连接硬件
+    let simulate = std::env::args().any(|a| a == "--simulate")
+        || std::env::var_os("POLARIMETER_SIMULATE").is_some();
     // 在一个新线程中启动后端
     let backend_handle = thread::spawn(move || {
-        backend_loop(cmd_rx, update_tx);
+        backend_loop(cmd_rx, update_tx, simulate);
     });
 
     // 在主线程中运行 eframe (egui)
@@ -170,8 +174,13 @@ fn main() -> eframe::Result<()> {
         ..Default::default()
     };
     
+    let title = if simulate {
+        "旋光仪控制软件 v1.5.5（模拟模式）"
+    } else {
+        "旋光仪控制软件 v1.5.5"
+    };
     eframe::run_native(
-        "旋光仪控制软件 v1.5.5",
+        title,
         options,
         // 将后端线程的 handle 传递给 App
         Box::new(|cc| {
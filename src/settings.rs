@@ -0,0 +1,198 @@
+// 跨会话保留的设置：设备参数、界面主题、欢迎语、绘图样式、结果文件列布局与累计步数，统一保存在系统用户配置目录的
+// settings.json 中。前端与后端各自只改自己负责的字段，经 AppSettings::update 读改写，互不覆盖
+use crate::communication::{is_valid_steps_per_degree, ExportLayout, DEFAULT_STEPS_PER_DEGREE};
+use egui::Color32;
+use egui_plot::MarkerShape;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 界面主题：跟随系统时按操作系统的深浅色设置，无法获知时使用深色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemePreference {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    pub const ALL: [ThemePreference; 3] =
+        [ThemePreference::System, ThemePreference::Light, ThemePreference::Dark];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemePreference::System => "跟随系统",
+            ThemePreference::Light => "浅色",
+            ThemePreference::Dark => "深色",
+        }
+    }
+
+    pub fn is_dark(self, system: Option<eframe::Theme>) -> bool {
+        match self {
+            ThemePreference::System => system != Some(eframe::Theme::Light),
+            ThemePreference::Light => false,
+            ThemePreference::Dark => true,
+        }
+    }
+}
+
+/// 数据处理图的样式，用于让导出的图与报告的风格一致
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlotStyle {
+    pub marker: MarkerShape,
+    pub marker_radius: f32,
+    pub line_color: Option<Color32>, // None 表示使用绘图库的默认配色
+}
+
+impl Default for PlotStyle {
+    fn default() -> Self {
+        Self {
+            marker: MarkerShape::Cross,
+            marker_radius: 5.0,
+            line_color: None,
+        }
+    }
+}
+
+/// 缺失的字段使用默认值，旧版或手工编辑过的文件也能读取
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub exposure: f64,
+    pub min_radius: u32,
+    pub max_radius: u32,
+    pub lock_circle: bool,
+    pub rotation_direction_is_ama: bool,
+    pub rotation_direction_need_reverse: bool,
+    pub steps_per_degree: f32,
+    pub theme: ThemePreference,
+    pub welcome_text: Option<String>, // None 表示使用内置欢迎语
+    pub plot_style: PlotStyle,
+    pub min_r2: f64, // 拟合 R² 低于此值时回归方程标红
+    pub export_layout: ExportLayout,
+    pub odometer: u64, // 电机累计转动步数，由后端定期写入
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            exposure: -8.0,
+            min_radius: 30,
+            max_radius: 45,
+            lock_circle: false,
+            rotation_direction_is_ama: false,
+            rotation_direction_need_reverse: false,
+            steps_per_degree: DEFAULT_STEPS_PER_DEGREE,
+            theme: ThemePreference::System,
+            welcome_text: None,
+            plot_style: PlotStyle::default(),
+            min_r2: 0.99,
+            export_layout: ExportLayout::default(),
+            odometer: 0,
+        }
+    }
+}
+
+impl AppSettings {
+    /// 系统的用户配置目录（如 Windows 的 %APPDATA%\RustPolarimeter），程序目录往往不可写
+    fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("RustPolarimeter").join("settings.json"))
+    }
+
+    /// 旧版写在程序目录的设置文件，新位置还没有文件时从这里读取
+    fn legacy_path() -> Option<PathBuf> {
+        Some(std::env::current_exe().ok()?.parent()?.join("settings.json"))
+    }
+
+    /// 读取上次保存的设置；文件缺失或无法解析时使用默认值
+    pub fn load() -> Self {
+        let text = Self::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .or_else(|| Self::legacy_path().and_then(|path| std::fs::read_to_string(path).ok()));
+        let Some(text) = text else {
+            return Self::default();
+        };
+        match Self::from_json(&text) {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::warn!("无法解析 settings.json，使用默认设置：{}", e);
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let path = Self::path().ok_or_else(|| std::io::Error::other("无法确定用户配置目录"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, text)
+    }
+
+    /// 读取当前保存的设置，只修改调用方负责的字段后写回
+    pub fn update(change: impl FnOnce(&mut AppSettings)) -> std::io::Result<()> {
+        static WRITE_LOCK: Mutex<()> = Mutex::new(());
+        let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut settings = Self::load();
+        change(&mut settings);
+        settings.save()
+    }
+
+    /// 解析后修正超出界面范围的值，避免手工编辑的文件把相机或电机设成无效状态
+    fn from_json(text: &str) -> serde_json::Result<Self> {
+        let mut settings: Self = serde_json::from_str(text)?;
+        let defaults = Self::default();
+        if !settings.exposure.is_finite() {
+            settings.exposure = defaults.exposure;
+        }
+        settings.exposure = settings.exposure.clamp(-10.0, 10.0);
+        settings.max_radius = settings.max_radius.clamp(1, 200);
+        settings.min_radius = settings.min_radius.clamp(1, settings.max_radius);
+        if !settings.plot_style.marker_radius.is_finite() {
+            settings.plot_style.marker_radius = defaults.plot_style.marker_radius;
+        }
+        settings.plot_style.marker_radius = settings.plot_style.marker_radius.clamp(1.0, 12.0);
+        if !settings.min_r2.is_finite() {
+            settings.min_r2 = defaults.min_r2;
+        }
+        settings.min_r2 = settings.min_r2.clamp(0.0, 1.0);
+        if !is_valid_steps_per_degree(settings.steps_per_degree) {
+            settings.steps_per_degree = defaults.steps_per_degree;
+        }
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_fall_back_to_defaults() {
+        let settings = AppSettings {
+            exposure: -5.0,
+            lock_circle: true,
+            theme: ThemePreference::Light,
+            ..Default::default()
+        };
+        let text = serde_json::to_string(&settings).unwrap();
+        assert_eq!(AppSettings::from_json(&text).unwrap(), settings);
+        // 缺失的字段取默认值，超出范围的值被修正
+        let text = r#"{"min_radius": 80, "max_radius": 60, "steps_per_degree": -1}"#;
+        let partial = AppSettings::from_json(text).unwrap();
+        assert_eq!((partial.min_radius, partial.max_radius), (60, 60));
+        assert_eq!(partial.steps_per_degree, DEFAULT_STEPS_PER_DEGREE);
+        assert!(!partial.lock_circle);
+        assert_eq!(partial.theme, ThemePreference::System);
+        assert_eq!(partial.plot_style, PlotStyle::default());
+        let text = r#"{"plot_style": {"marker": "Circle", "marker_radius": 40}, "min_r2": 2}"#;
+        let style = AppSettings::from_json(text).unwrap();
+        assert_eq!(style.plot_style.marker, MarkerShape::Circle);
+        assert_eq!(style.plot_style.marker_radius, 12.0);
+        assert_eq!(style.min_r2, 1.0);
+        assert!(AppSettings::from_json("not json").is_err());
+    }
+}